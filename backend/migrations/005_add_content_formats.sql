@@ -0,0 +1,20 @@
+-- Migration: Store article bodies as HTML and normalized Markdown
+-- `content` keeps the plain text used for search and excerpts
+
+CREATE TYPE content_format AS ENUM ('text', 'html');
+
+ALTER TABLE articles
+  ADD COLUMN content_html TEXT,
+  ADD COLUMN content_markdown TEXT,
+  ADD COLUMN content_format content_format NOT NULL DEFAULT 'text';
+
+-- smol.ai rows stored cleaned inner HTML in `content`. Keep it as the HTML body;
+-- the startup backfill derives plain text and Markdown from it.
+UPDATE articles
+SET content_format = 'html', content_html = content
+WHERE source = 'smol.ai';
+
+-- AIBase rows are plain paragraphs separated by blank lines, which is already valid Markdown
+UPDATE articles
+SET content_markdown = content
+WHERE source <> 'smol.ai';
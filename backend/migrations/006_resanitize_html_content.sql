@@ -0,0 +1,7 @@
+-- Migration: Re-process stored HTML bodies through the sanitizer
+-- Clearing the Markdown makes the startup backfill sanitize `content_html`
+-- and regenerate plain text and Markdown from the cleaned markup.
+
+UPDATE articles
+SET content_markdown = NULL
+WHERE content_format = 'html' AND content_html IS NOT NULL;
@@ -0,0 +1,21 @@
+-- Migration: Mirrored images for articles
+-- Files live on local disk, content-addressed by their SHA-256 hash
+
+CREATE TYPE asset_kind AS ENUM ('thumbnail', 'inline');
+
+CREATE TABLE article_assets (
+    id SERIAL PRIMARY KEY,
+    article_id UUID NOT NULL REFERENCES articles(id) ON DELETE CASCADE,
+    kind asset_kind NOT NULL,
+    original_url TEXT NOT NULL,
+    hash VARCHAR(64) NOT NULL,
+    content_type VARCHAR(100) NOT NULL,
+    byte_size INTEGER NOT NULL,
+    width INTEGER,
+    height INTEGER,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (article_id, original_url)
+);
+
+CREATE INDEX idx_article_assets_hash ON article_assets(hash);
+CREATE INDEX idx_article_assets_original_url ON article_assets(original_url);
@@ -0,0 +1,20 @@
+-- Migration: Outbound links extracted from article bodies
+
+CREATE TABLE article_links (
+    id SERIAL PRIMARY KEY,
+    article_id UUID NOT NULL REFERENCES articles(id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    domain VARCHAR(255) NOT NULL,
+    anchor_text TEXT,
+    position INTEGER NOT NULL
+);
+
+CREATE INDEX idx_article_links_article_id ON article_links(article_id);
+CREATE INDEX idx_article_links_url ON article_links(url);
+CREATE INDEX idx_article_links_domain ON article_links(domain);
+
+-- One-shot data backfills run by the server after migrations
+CREATE TABLE backfills (
+    name VARCHAR(100) PRIMARY KEY,
+    completed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
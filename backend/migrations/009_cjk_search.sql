@@ -0,0 +1,108 @@
+-- Migration: CJK-aware full-text search
+-- Chinese/Japanese/Korean text has no spaces, so the english parser indexes whole
+-- runs of CJK characters as single words and nothing inside them can match.
+-- CJK runs are indexed as overlapping character bigrams with the `simple`
+-- configuration, while the remaining (Latin) text keeps english stemming.
+-- Every CJK character is indexed on its own as well, so one-character
+-- queries (云, 芯) match inside longer runs.
+
+-- Character class used below: Hiragana/Katakana (U+3040-30FF), CJK Extension A
+-- (U+3400-4DBF), CJK Unified Ideographs (U+4E00-9FFF), CJK Compatibility
+-- Ideographs (U+F900-FAFF) and Hangul Syllables (U+AC00-D7AF)
+
+CREATE OR REPLACE FUNCTION cjk_strip(input TEXT) RETURNS TEXT AS $$
+    SELECT regexp_replace(
+        COALESCE(input, ''),
+        '[぀-ヿ㐀-䶿一-鿿豈-﫿가-힯]+',
+        ' ',
+        'g'
+    );
+$$ LANGUAGE sql IMMUTABLE;
+
+-- '阿里发布大模型' -> '阿里 里发 发布 布大 大模 模型'
+CREATE OR REPLACE FUNCTION cjk_bigrams(input TEXT) RETURNS TEXT AS $$
+DECLARE
+    run TEXT;
+    grams TEXT[] := '{}';
+    i INTEGER;
+BEGIN
+    FOR run IN
+        SELECT (regexp_matches(
+            COALESCE(input, ''),
+            '[぀-ヿ㐀-䶿一-鿿豈-﫿가-힯]+',
+            'g'
+        ))[1]
+    LOOP
+        IF char_length(run) = 1 THEN
+            grams := grams || run;
+        ELSE
+            FOR i IN 1 .. char_length(run) - 1 LOOP
+                grams := grams || substr(run, i, 2);
+            END LOOP;
+        END IF;
+    END LOOP;
+
+    RETURN array_to_string(grams, ' ');
+END;
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+-- '阿里云' -> '阿 里 云 '
+CREATE OR REPLACE FUNCTION cjk_unigrams(input TEXT) RETURNS TEXT AS $$
+    SELECT regexp_replace(
+        regexp_replace(COALESCE(input, ''), '[^぀-ヿ㐀-䶿一-鿿豈-﫿가-힯]+', ' ', 'g'),
+        '([぀-ヿ㐀-䶿一-鿿豈-﫿가-힯])',
+        '\1 ',
+        'g'
+    );
+$$ LANGUAGE sql IMMUTABLE;
+
+-- Unigrams go in a vector of their own, so bigram positions stay adjacent
+-- for phrase queries. Queries use bigrams, or the unigram of a single character.
+CREATE OR REPLACE FUNCTION cjk_tsvector(input TEXT) RETURNS TSVECTOR AS $$
+    SELECT to_tsvector('english', cjk_strip(input))
+        || to_tsvector('simple', cjk_bigrams(input))
+        || to_tsvector('simple', cjk_unigrams(input));
+$$ LANGUAGE sql IMMUTABLE;
+
+-- Counterpart of cjk_tsvector for user queries: all words must match
+CREATE OR REPLACE FUNCTION search_query(input TEXT) RETURNS TSQUERY AS $$
+DECLARE
+    latin TEXT := cjk_strip(input);
+    cjk TEXT := cjk_bigrams(input);
+    query TSQUERY;
+BEGIN
+    IF cjk <> '' THEN
+        query := plainto_tsquery('simple', cjk);
+    END IF;
+
+    IF latin ~ '[[:alnum:]]' THEN
+        IF query IS NULL THEN
+            query := plainto_tsquery('english', latin);
+        ELSE
+            query := query && plainto_tsquery('english', latin);
+        END IF;
+    END IF;
+
+    RETURN COALESCE(query, ''::tsquery);
+END;
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+CREATE OR REPLACE FUNCTION articles_search_vector(title TEXT, excerpt TEXT, content TEXT)
+RETURNS TSVECTOR AS $$
+    SELECT
+        setweight(cjk_tsvector(title), 'A') ||
+        setweight(cjk_tsvector(excerpt), 'B') ||
+        setweight(cjk_tsvector(content), 'C');
+$$ LANGUAGE sql IMMUTABLE;
+
+CREATE OR REPLACE FUNCTION articles_search_update() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.search_vector := articles_search_vector(NEW.title, NEW.excerpt, NEW.content);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Rebuild existing vectors without bumping updated_at
+ALTER TABLE articles DISABLE TRIGGER articles_updated_at;
+UPDATE articles SET search_vector = articles_search_vector(title, excerpt, content);
+ALTER TABLE articles ENABLE TRIGGER articles_updated_at;
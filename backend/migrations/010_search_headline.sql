@@ -0,0 +1,100 @@
+-- Migration: highlighted search snippets
+-- ts_headline cannot highlight CJK text because its parser sees whole runs of
+-- CJK characters as single words. Latin terms go through ts_headline (so
+-- stemmed matches are found) and CJK query runs are marked literally.
+
+-- Highlighted excerpt of `doc` around the matches of a search_query() input.
+-- max_fragments = 0 highlights the whole text instead of extracting fragments.
+-- Returns NULL when nothing in `doc` can be highlighted.
+CREATE OR REPLACE FUNCTION search_headline(
+    doc TEXT,
+    input TEXT,
+    start_sel TEXT,
+    stop_sel TEXT,
+    max_fragments INTEGER
+) RETURNS TEXT AS $$
+DECLARE
+    latin TEXT := cjk_strip(input);
+    terms TEXT[];
+    term TEXT;
+    first_pos INTEGER;
+    window_start INTEGER;
+    options TEXT;
+    result TEXT;
+BEGIN
+    IF COALESCE(doc, '') = '' THEN
+        RETURN NULL;
+    END IF;
+
+    -- Whole CJK runs of the query, or their bigrams when no run occurs verbatim
+    SELECT array_agg(m[1]) INTO terms
+    FROM regexp_matches(COALESCE(input, ''), '[぀-ヿ㐀-䶿一-鿿豈-﫿가-힯]+', 'g') AS m;
+
+    IF terms IS NOT NULL AND NOT EXISTS (
+        SELECT 1 FROM unnest(terms) AS t WHERE strpos(doc, t) > 0
+    ) THEN
+        terms := string_to_array(cjk_bigrams(input), ' ');
+    END IF;
+
+    IF latin ~ '[[:alnum:]]' THEN
+        options := format(
+            'StartSel="%s", StopSel="%s"',
+            replace(start_sel, '"', ''),
+            replace(stop_sel, '"', '')
+        );
+        IF max_fragments = 0 THEN
+            options := options || ', HighlightAll=true';
+        ELSE
+            options := options || format(
+                ', MaxFragments=%s, MaxWords=30, MinWords=10, FragmentDelimiter=" … "',
+                max_fragments
+            );
+        END IF;
+
+        -- The parser glues Latin words to adjacent CJK characters ('大模型Qwen3'),
+        -- so CJK runs are fenced with invisible word joiners (U+2060) meanwhile
+        result := replace(
+            ts_headline(
+                'english',
+                regexp_replace(doc, '([぀-ヿ㐀-䶿一-鿿豈-﫿가-힯]+)', chr(8288) || '\1' || chr(8288), 'g'),
+                plainto_tsquery('english', latin),
+                options
+            ),
+            chr(8288),
+            ''
+        );
+    ELSIF terms IS NULL THEN
+        RETURN NULL;
+    ELSIF max_fragments = 0 THEN
+        result := doc;
+    ELSE
+        -- Single window around the earliest CJK match
+        SELECT min(strpos(doc, t)) INTO first_pos
+        FROM unnest(terms) AS t
+        WHERE strpos(doc, t) > 0;
+
+        IF first_pos IS NULL THEN
+            RETURN NULL;
+        END IF;
+
+        window_start := greatest(1, first_pos - 30);
+        result := substr(doc, window_start, 120);
+        IF window_start > 1 THEN
+            result := '… ' || result;
+        END IF;
+        IF window_start + 120 <= char_length(doc) THEN
+            result := result || ' …';
+        END IF;
+    END IF;
+
+    FOREACH term IN ARRAY COALESCE(terms, '{}') LOOP
+        result := replace(result, term, start_sel || term || stop_sel);
+    END LOOP;
+
+    IF strpos(result, start_sel) = 0 THEN
+        RETURN NULL;
+    END IF;
+
+    RETURN regexp_replace(result, '\s+', ' ', 'g');
+END;
+$$ LANGUAGE plpgsql STABLE;
@@ -0,0 +1,43 @@
+-- Migration: advanced search syntax
+-- The query parser splits user input into terms and combines the tsquery of
+-- each term with &&, || and !!. This builds the tsquery for a single term with
+-- the same CJK bigram / english split as search_query().
+
+CREATE OR REPLACE FUNCTION search_term_query(term TEXT, phrase BOOLEAN, prefix BOOLEAN)
+RETURNS TSQUERY AS $$
+DECLARE
+    latin TEXT := cjk_strip(term);
+    cjk TEXT := cjk_bigrams(term);
+    word TEXT;
+    latin_query TSQUERY;
+    query TSQUERY;
+BEGIN
+    IF cjk <> '' THEN
+        IF phrase THEN
+            query := phraseto_tsquery('simple', cjk);
+        ELSE
+            query := plainto_tsquery('simple', cjk);
+        END IF;
+    END IF;
+
+    IF latin ~ '[[:alnum:]]' THEN
+        IF prefix THEN
+            -- Only letters and digits reach to_tsquery, so input cannot inject operators
+            word := regexp_replace(latin, '[^[:alnum:]]+', '', 'g');
+            latin_query := to_tsquery('english', word || ':*');
+        ELSIF phrase THEN
+            latin_query := phraseto_tsquery('english', latin);
+        ELSE
+            latin_query := plainto_tsquery('english', latin);
+        END IF;
+
+        IF query IS NULL THEN
+            query := latin_query;
+        ELSE
+            query := query && latin_query;
+        END IF;
+    END IF;
+
+    RETURN COALESCE(query, ''::tsquery);
+END;
+$$ LANGUAGE plpgsql IMMUTABLE;
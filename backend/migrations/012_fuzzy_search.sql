@@ -0,0 +1,12 @@
+-- Migration: typo-tolerant search and autocomplete
+-- Trigram indexes back fuzzy title/tag matching ("Gemeni" -> "Gemini") and
+-- the substring lookups of /api/search/suggest. Fuzzy matches use the `%` and
+-- `<%` operators, which unlike similarity() and word_similarity() can use
+-- them; titles are compared without their CJK runs.
+
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX IF NOT EXISTS idx_articles_title_trgm ON articles USING GIN (title gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_articles_title_stripped_trgm ON articles USING GIN (cjk_strip(title) gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_articles_author_trgm ON articles USING GIN (author gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_tags_name_trgm ON tags USING GIN (name gin_trgm_ops);
@@ -0,0 +1,14 @@
+-- Migration: article embeddings for semantic search
+-- Vectors are L2-normalized and searched in-process, so plain REAL[] is
+-- enough and pgvector isn't required. `model` and `content_hash` record what
+-- an embedding was computed with; rows that no longer match are recomputed.
+
+CREATE TABLE IF NOT EXISTS article_embeddings (
+    article_id UUID PRIMARY KEY REFERENCES articles(id) ON DELETE CASCADE,
+    model TEXT NOT NULL,
+    content_hash VARCHAR(64),
+    embedding REAL[] NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_article_embeddings_model ON article_embeddings(model);
@@ -0,0 +1,20 @@
+-- Migration: cross-source story clusters
+-- Articles reporting the same story (reposts, or the same launch covered by
+-- several sources) share a `cluster_id`. `simhash` is a 64-bit fingerprint
+-- computed when an article is parsed; `cluster_checked` is cleared whenever
+-- the article changes so the background clusterer looks at it again.
+
+CREATE TABLE IF NOT EXISTS story_clusters (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE articles
+    ADD COLUMN IF NOT EXISTS simhash BIGINT,
+    ADD COLUMN IF NOT EXISTS cluster_id UUID REFERENCES story_clusters(id) ON DELETE SET NULL,
+    ADD COLUMN IF NOT EXISTS cluster_checked BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE INDEX IF NOT EXISTS idx_articles_cluster_id ON articles(cluster_id);
+CREATE INDEX IF NOT EXISTS idx_articles_cluster_unchecked ON articles(scraped_at)
+    WHERE NOT cluster_checked;
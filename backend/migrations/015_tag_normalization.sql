@@ -0,0 +1,100 @@
+-- Migration: normalized tags, aliases and categories
+-- Tags are identified by `slug`, their trimmed, whitespace-collapsed and
+-- lowercased name, so "LLM" and "llm " are one tag. `tag_aliases` maps
+-- other spellings and translations ("大模型") to a tag; aliases are stored as
+-- slugs too. Existing case variants are merged into the oldest tag.
+
+CREATE TYPE tag_category AS ENUM ('company', 'topic', 'model', 'person');
+
+-- Display form of a scraped tag name: trimmed and single-spaced
+CREATE OR REPLACE FUNCTION tag_name(raw TEXT) RETURNS TEXT AS $$
+    SELECT regexp_replace(btrim(raw), '\s+', ' ', 'g')
+$$ LANGUAGE sql IMMUTABLE;
+
+CREATE OR REPLACE FUNCTION tag_slug(raw TEXT) RETURNS TEXT AS $$
+    SELECT lower(tag_name(raw))
+$$ LANGUAGE sql IMMUTABLE;
+
+ALTER TABLE tags
+    ADD COLUMN IF NOT EXISTS slug TEXT,
+    ADD COLUMN IF NOT EXISTS category tag_category;
+
+CREATE TABLE IF NOT EXISTS tag_aliases (
+    alias TEXT PRIMARY KEY,
+    tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_tag_aliases_tag_id ON tag_aliases(tag_id);
+
+-- Tag id for a name or alias, NULL if unknown
+CREATE OR REPLACE FUNCTION resolve_tag(lookup TEXT) RETURNS INTEGER AS $$
+    SELECT id FROM tags WHERE slug = tag_slug(lookup)
+    UNION ALL
+    SELECT tag_id FROM tag_aliases WHERE alias = tag_slug(lookup)
+    LIMIT 1
+$$ LANGUAGE sql STABLE;
+
+-- Move every article and alias of `from_id` to `into_id`, keep the old slug
+-- as an alias and delete the old tag
+CREATE OR REPLACE FUNCTION merge_tag(from_id INTEGER, into_id INTEGER) RETURNS VOID AS $$
+BEGIN
+    IF from_id = into_id THEN
+        RETURN;
+    END IF;
+
+    INSERT INTO article_tags (article_id, tag_id)
+    SELECT article_id, into_id FROM article_tags WHERE tag_id = from_id
+    ON CONFLICT DO NOTHING;
+
+    UPDATE tag_aliases SET tag_id = into_id WHERE tag_id = from_id;
+
+    INSERT INTO tag_aliases (alias, tag_id)
+    SELECT f.slug, into_id FROM tags f, tags i
+    WHERE f.id = from_id AND i.id = into_id AND f.slug IS DISTINCT FROM i.slug
+    ON CONFLICT (alias) DO UPDATE SET tag_id = EXCLUDED.tag_id;
+
+    UPDATE tags SET category = COALESCE(tags.category, f.category)
+    FROM tags f
+    WHERE tags.id = into_id AND f.id = from_id;
+
+    DELETE FROM tags WHERE id = from_id;
+END;
+$$ LANGUAGE plpgsql;
+
+UPDATE tags SET slug = tag_slug(name);
+
+DO $$
+DECLARE
+    r RECORD;
+BEGIN
+    FOR r IN
+        SELECT t.id, (SELECT MIN(k.id) FROM tags k WHERE k.slug = t.slug) AS keep
+        FROM tags t
+    LOOP
+        IF r.id <> r.keep THEN
+            PERFORM merge_tag(r.id, r.keep);
+        END IF;
+    END LOOP;
+END $$;
+
+UPDATE tags SET name = tag_name(name);
+
+ALTER TABLE tags ALTER COLUMN slug SET NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_tags_slug ON tags(slug);
+
+-- Common spellings of the LLM topic across sources
+INSERT INTO tag_aliases (alias, tag_id)
+SELECT v.alias, t.id
+FROM (VALUES ('大模型'), ('大语言模型'), ('large language models'), ('large language model')) v(alias)
+JOIN tags t ON t.slug = 'llm'
+ON CONFLICT DO NOTHING;
+
+DO $$
+DECLARE
+    r RECORD;
+BEGIN
+    FOR r IN SELECT t.id, a.tag_id FROM tags t JOIN tag_aliases a ON a.alias = t.slug LOOP
+        PERFORM merge_tag(r.id, r.tag_id);
+    END LOOP;
+END $$;
@@ -0,0 +1,60 @@
+-- Migration: rule-based auto-tagging
+-- A rule assigns its tag to articles whose title or content matches any of
+-- its patterns, optionally only for one source or language. `article_tags`
+-- records where each tag came from; rule tags are recomputed whenever rules
+-- are applied, scraped and manual tags are left alone.
+
+CREATE TYPE tag_rule_kind AS ENUM ('keyword', 'phrase', 'regex');
+CREATE TYPE tag_source AS ENUM ('scraped', 'rule', 'manual');
+
+CREATE TABLE IF NOT EXISTS tag_rules (
+    id SERIAL PRIMARY KEY,
+    tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    kind tag_rule_kind NOT NULL,
+    patterns TEXT[] NOT NULL,
+    -- Source display name, e.g. 'AIBase'; NULL matches every source
+    source TEXT,
+    -- 'en', 'zh', 'ja' or 'ko'; NULL matches every language
+    language TEXT,
+    case_sensitive BOOLEAN NOT NULL DEFAULT FALSE,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_tag_rules_tag_id ON tag_rules(tag_id);
+
+-- Deleting a rule removes the tags it assigned
+ALTER TABLE article_tags
+    ADD COLUMN IF NOT EXISTS source tag_source NOT NULL DEFAULT 'scraped',
+    ADD COLUMN IF NOT EXISTS rule_id INTEGER REFERENCES tag_rules(id) ON DELETE CASCADE;
+
+CREATE INDEX IF NOT EXISTS idx_article_tags_rule_id ON article_tags(rule_id)
+    WHERE rule_id IS NOT NULL;
+
+-- Merging tags keeps each article tag's provenance and moves rules along
+CREATE OR REPLACE FUNCTION merge_tag(from_id INTEGER, into_id INTEGER) RETURNS VOID AS $$
+BEGIN
+    IF from_id = into_id THEN
+        RETURN;
+    END IF;
+
+    INSERT INTO article_tags (article_id, tag_id, source, rule_id)
+    SELECT article_id, into_id, source, rule_id FROM article_tags WHERE tag_id = from_id
+    ON CONFLICT DO NOTHING;
+
+    UPDATE tag_rules SET tag_id = into_id, updated_at = NOW() WHERE tag_id = from_id;
+    UPDATE tag_aliases SET tag_id = into_id WHERE tag_id = from_id;
+
+    INSERT INTO tag_aliases (alias, tag_id)
+    SELECT f.slug, into_id FROM tags f, tags i
+    WHERE f.id = from_id AND i.id = into_id AND f.slug IS DISTINCT FROM i.slug
+    ON CONFLICT (alias) DO UPDATE SET tag_id = EXCLUDED.tag_id;
+
+    UPDATE tags SET category = COALESCE(tags.category, f.category)
+    FROM tags f
+    WHERE tags.id = into_id AND f.id = from_id;
+
+    DELETE FROM tags WHERE id = from_id;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,31 @@
+-- Migration: named entities
+-- Companies, models, products and people come from the gazetteer shipped
+-- with the backend (src/entities/gazetteer.json) and are synced into
+-- `entities` on startup. `article_entities` records which entities an article
+-- mentions; `articles.entities_version` is the gazetteer version it was last
+-- checked against, so a changed gazetteer or article is checked again.
+
+CREATE TYPE entity_kind AS ENUM ('company', 'model', 'product', 'person');
+
+CREATE TABLE IF NOT EXISTS entities (
+    id SERIAL PRIMARY KEY,
+    slug TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    kind entity_kind NOT NULL,
+    aliases TEXT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS article_entities (
+    article_id UUID NOT NULL REFERENCES articles(id) ON DELETE CASCADE,
+    entity_id INTEGER NOT NULL REFERENCES entities(id) ON DELETE CASCADE,
+    -- Number of times any alias of the entity occurs
+    mentions INTEGER NOT NULL,
+    in_title BOOLEAN NOT NULL,
+    PRIMARY KEY (article_id, entity_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_article_entities_entity_id ON article_entities(entity_id);
+
+ALTER TABLE articles ADD COLUMN IF NOT EXISTS entities_version TEXT;
@@ -0,0 +1,15 @@
+-- Migration: manual article edits
+-- Fields edited by hand are listed in `manual_fields` and kept when the
+-- article is re-scraped. Tags removed by hand are remembered in
+-- `article_tag_removals` so tagging rules don't add them back.
+
+ALTER TABLE articles
+    ADD COLUMN IF NOT EXISTS manual_fields TEXT[] NOT NULL DEFAULT '{}',
+    ADD COLUMN IF NOT EXISTS edited_at TIMESTAMPTZ;
+
+CREATE TABLE IF NOT EXISTS article_tag_removals (
+    article_id UUID NOT NULL REFERENCES articles(id) ON DELETE CASCADE,
+    tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (article_id, tag_id)
+);
@@ -0,0 +1,21 @@
+-- Migration: soft deletes and tombstones
+-- Deleting an article moves it to the trash by setting `deleted_at`; trashed
+-- articles are hidden everywhere but the trash and purged for good after a
+-- retention period. `article_tombstones` blocks (source, external_id) pairs
+-- from being scraped again: trashing an article adds one, restoring it
+-- removes it, and purging leaves it in place.
+
+ALTER TABLE articles ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_articles_deleted_at ON articles(deleted_at)
+    WHERE deleted_at IS NOT NULL;
+
+CREATE TABLE IF NOT EXISTS article_tombstones (
+    source VARCHAR(255) NOT NULL,
+    external_id VARCHAR(50) NOT NULL,
+    -- Title of the blocked article, if it was ever stored
+    title TEXT,
+    reason TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (source, external_id)
+);
@@ -0,0 +1,4 @@
+-- Migration: read state
+-- When an article was marked read; NULL while unread.
+
+ALTER TABLE articles ADD COLUMN IF NOT EXISTS read_at TIMESTAMPTZ;
@@ -0,0 +1,31 @@
+-- Migration: retention policies
+-- The `retention` setting controls what the maintenance task removes:
+-- raw HTML and archival age per source, and how long scrape history is
+-- kept. Article age thresholds are off until set. Archived articles leave
+-- `articles` for `archived_articles`, with their tags, and are tombstoned
+-- so scrapes don't bring them back.
+
+INSERT INTO scraper_settings (key, value) VALUES
+    ('retention', '{
+        "enabled": true,
+        "interval_hours": 24,
+        "scrape_runs_days": 180,
+        "article_logs_days": 30,
+        "default": {"raw_html_days": null, "archive_after_days": null},
+        "sources": {}
+    }'::jsonb)
+ON CONFLICT (key) DO NOTHING;
+
+CREATE TABLE IF NOT EXISTS archived_articles (
+    id UUID PRIMARY KEY,
+    source VARCHAR(255) NOT NULL,
+    external_id VARCHAR(50) NOT NULL,
+    title TEXT NOT NULL,
+    published_at TIMESTAMPTZ,
+    -- The article row and its tag names as JSON
+    data JSONB NOT NULL,
+    archived_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_archived_articles_source ON archived_articles(source, external_id);
+CREATE INDEX IF NOT EXISTS idx_archived_articles_archived_at ON archived_articles(archived_at DESC);
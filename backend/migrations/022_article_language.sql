@@ -0,0 +1,185 @@
+-- Migration: article language
+-- The scraper detects each article's language (`en`, `zh`, `ja` or `ko`) from
+-- its script. It picks the text-search configuration for the non-CJK part of
+-- the article: English text is stemmed, while the Latin words in CJK articles
+-- (mostly names like "GPT-5" or "Llama") are indexed as written. Search
+-- highlights match terms the same way. Existing articles are detected by the
+-- startup backfill, which rebuilds their vectors.
+
+ALTER TABLE articles ADD COLUMN IF NOT EXISTS language VARCHAR(8);
+
+CREATE INDEX IF NOT EXISTS idx_articles_language ON articles(language);
+
+-- Unknown languages keep the english configuration used so far
+CREATE OR REPLACE FUNCTION search_config(language TEXT) RETURNS REGCONFIG AS $$
+    SELECT CASE WHEN language IN ('zh', 'ja', 'ko') THEN 'simple' ELSE 'english' END::regconfig;
+$$ LANGUAGE sql IMMUTABLE;
+
+-- Replaced by the versions taking a configuration or language
+DROP FUNCTION IF EXISTS articles_search_vector(TEXT, TEXT, TEXT);
+DROP FUNCTION IF EXISTS cjk_tsvector(TEXT);
+DROP FUNCTION IF EXISTS search_headline(TEXT, TEXT, TEXT, TEXT, INTEGER);
+
+CREATE OR REPLACE FUNCTION cjk_tsvector(input TEXT, config REGCONFIG) RETURNS TSVECTOR AS $$
+    SELECT to_tsvector(config, cjk_strip(input))
+        || to_tsvector('simple', cjk_bigrams(input))
+        || to_tsvector('simple', cjk_unigrams(input));
+$$ LANGUAGE sql IMMUTABLE;
+
+CREATE OR REPLACE FUNCTION articles_search_vector(
+    title TEXT,
+    excerpt TEXT,
+    content TEXT,
+    language TEXT
+) RETURNS TSVECTOR AS $$
+    SELECT
+        setweight(cjk_tsvector(title, search_config(language)), 'A') ||
+        setweight(cjk_tsvector(excerpt, search_config(language)), 'B') ||
+        setweight(cjk_tsvector(content, search_config(language)), 'C');
+$$ LANGUAGE sql IMMUTABLE;
+
+CREATE OR REPLACE FUNCTION articles_search_update() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.search_vector := articles_search_vector(NEW.title, NEW.excerpt, NEW.content, NEW.language);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Latin query words now match both stemmed (english) and as-written (simple)
+-- vectors, so a search finds English and CJK articles alike
+CREATE OR REPLACE FUNCTION search_term_query(term TEXT, phrase BOOLEAN, prefix BOOLEAN)
+RETURNS TSQUERY AS $$
+DECLARE
+    latin TEXT := cjk_strip(term);
+    cjk TEXT := cjk_bigrams(term);
+    word TEXT;
+    latin_query TSQUERY;
+    query TSQUERY;
+BEGIN
+    IF cjk <> '' THEN
+        IF phrase THEN
+            query := phraseto_tsquery('simple', cjk);
+        ELSE
+            query := plainto_tsquery('simple', cjk);
+        END IF;
+    END IF;
+
+    IF latin ~ '[[:alnum:]]' THEN
+        IF prefix THEN
+            -- Only letters and digits reach to_tsquery, so input cannot inject operators
+            word := regexp_replace(latin, '[^[:alnum:]]+', '', 'g');
+            latin_query := to_tsquery('english', word || ':*') || to_tsquery('simple', word || ':*');
+        ELSIF phrase THEN
+            latin_query := phraseto_tsquery('english', latin) || phraseto_tsquery('simple', latin);
+        ELSE
+            latin_query := plainto_tsquery('english', latin) || plainto_tsquery('simple', latin);
+        END IF;
+
+        IF query IS NULL THEN
+            query := latin_query;
+        ELSE
+            query := query && latin_query;
+        END IF;
+    END IF;
+
+    RETURN COALESCE(query, ''::tsquery);
+END;
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+-- Highlighted excerpt of `doc` around the matches of a search_query() input,
+-- with Latin terms matched under `config`, the configuration `doc` was indexed with.
+-- max_fragments = 0 highlights the whole text instead of extracting fragments.
+-- Returns NULL when nothing in `doc` can be highlighted.
+CREATE OR REPLACE FUNCTION search_headline(
+    config REGCONFIG,
+    doc TEXT,
+    input TEXT,
+    start_sel TEXT,
+    stop_sel TEXT,
+    max_fragments INTEGER
+) RETURNS TEXT AS $$
+DECLARE
+    latin TEXT := cjk_strip(input);
+    terms TEXT[];
+    term TEXT;
+    first_pos INTEGER;
+    window_start INTEGER;
+    options TEXT;
+    result TEXT;
+BEGIN
+    IF COALESCE(doc, '') = '' THEN
+        RETURN NULL;
+    END IF;
+
+    -- Whole CJK runs of the query, or their bigrams when no run occurs verbatim
+    SELECT array_agg(m[1]) INTO terms
+    FROM regexp_matches(COALESCE(input, ''), '[぀-ヿ㐀-䶿一-鿿豈-﫿가-힯]+', 'g') AS m;
+
+    IF terms IS NOT NULL AND NOT EXISTS (
+        SELECT 1 FROM unnest(terms) AS t WHERE strpos(doc, t) > 0
+    ) THEN
+        terms := string_to_array(cjk_bigrams(input), ' ');
+    END IF;
+
+    IF latin ~ '[[:alnum:]]' THEN
+        options := format(
+            'StartSel="%s", StopSel="%s"',
+            replace(start_sel, '"', ''),
+            replace(stop_sel, '"', '')
+        );
+        IF max_fragments = 0 THEN
+            options := options || ', HighlightAll=true';
+        ELSE
+            options := options || format(
+                ', MaxFragments=%s, MaxWords=30, MinWords=10, FragmentDelimiter=" … "',
+                max_fragments
+            );
+        END IF;
+
+        -- The parser glues Latin words to adjacent CJK characters ('大模型Qwen3'),
+        -- so CJK runs are fenced with invisible word joiners (U+2060) meanwhile
+        result := replace(
+            ts_headline(
+                config,
+                regexp_replace(doc, '([぀-ヿ㐀-䶿一-鿿豈-﫿가-힯]+)', chr(8288) || '\1' || chr(8288), 'g'),
+                plainto_tsquery(config, latin),
+                options
+            ),
+            chr(8288),
+            ''
+        );
+    ELSIF terms IS NULL THEN
+        RETURN NULL;
+    ELSIF max_fragments = 0 THEN
+        result := doc;
+    ELSE
+        -- Single window around the earliest CJK match
+        SELECT min(strpos(doc, t)) INTO first_pos
+        FROM unnest(terms) AS t
+        WHERE strpos(doc, t) > 0;
+
+        IF first_pos IS NULL THEN
+            RETURN NULL;
+        END IF;
+
+        window_start := greatest(1, first_pos - 30);
+        result := substr(doc, window_start, 120);
+        IF window_start > 1 THEN
+            result := '… ' || result;
+        END IF;
+        IF window_start + 120 <= char_length(doc) THEN
+            result := result || ' …';
+        END IF;
+    END IF;
+
+    FOREACH term IN ARRAY COALESCE(terms, '{}') LOOP
+        result := replace(result, term, start_sel || term || stop_sel);
+    END LOOP;
+
+    IF strpos(result, start_sel) = 0 THEN
+        RETURN NULL;
+    END IF;
+
+    RETURN regexp_replace(result, '\s+', ' ', 'g');
+END;
+$$ LANGUAGE plpgsql STABLE;
@@ -0,0 +1,59 @@
+-- Migration: prefix search terms
+-- Prefix terms (`gpt-4*`) used to have every non-alphanumeric character
+-- stripped before reaching to_tsquery, so they searched for `gpt4:*`, a
+-- lexeme no document contains. They now go through the same parser as the
+-- indexed text, with every resulting lexeme matched as a prefix.
+
+-- Mark every lexeme of a parsed query as a prefix. The lexemes are already
+-- quoted by the parser, so the text can't carry operators of its own.
+CREATE OR REPLACE FUNCTION prefix_tsquery(query TSQUERY) RETURNS TSQUERY AS $$
+    SELECT regexp_replace(query::text, '''(?:[^'']|'''')*''', '\&:*', 'g')::tsquery;
+$$ LANGUAGE sql IMMUTABLE;
+
+CREATE OR REPLACE FUNCTION search_term_query(term TEXT, phrase BOOLEAN, prefix BOOLEAN)
+RETURNS TSQUERY AS $$
+DECLARE
+    latin TEXT := cjk_strip(term);
+    cjk TEXT := cjk_bigrams(term);
+    word TEXT;
+    latin_query TSQUERY;
+    query TSQUERY;
+BEGIN
+    IF cjk <> '' THEN
+        IF phrase THEN
+            query := phraseto_tsquery('simple', cjk);
+        ELSE
+            query := plainto_tsquery('simple', cjk);
+        END IF;
+    END IF;
+
+    IF latin ~ '[[:alnum:]]' THEN
+        IF prefix THEN
+            latin_query := prefix_tsquery(plainto_tsquery('english', latin))
+                || prefix_tsquery(plainto_tsquery('simple', latin));
+
+            -- The parser splits `gpt-4` into `gpt` and `-4`, but indexes
+            -- `gpt-4o` as one compound token too, so match that as well
+            word := lower(btrim(latin));
+            IF word ~ '[^[:alnum:]]' AND word !~ '\s' THEN
+                latin_query := latin_query || format(
+                    '''%s'':*',
+                    replace(replace(word, '\', '\\'), '''', '''''')
+                )::tsquery;
+            END IF;
+        ELSIF phrase THEN
+            latin_query := phraseto_tsquery('english', latin) || phraseto_tsquery('simple', latin);
+        ELSE
+            latin_query := plainto_tsquery('english', latin) || plainto_tsquery('simple', latin);
+        END IF;
+
+        IF query IS NULL THEN
+            query := latin_query;
+        ELSE
+            query := query && latin_query;
+        END IF;
+    END IF;
+
+    RETURN COALESCE(query, ''::tsquery);
+END;
+$$ LANGUAGE plpgsql IMMUTABLE;
@@ -3,11 +3,18 @@ use axum::{
     http::StatusCode,
     Json,
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use uuid::Uuid;
 
-use crate::db::{models::ArticlePreview, queries};
+use crate::db::filter::{ArticleFilter, ArticleSort, HighlightOptions, Keyset, SortOrder, TagMode};
+use crate::db::{
+    models::{Article, ArticleEdit, ArticleFacets, ArticlePreview, ArticleTag},
+    pagination::Cursor,
+    queries, search,
+};
+use crate::semantic::related;
 use crate::AppState;
 
 #[derive(Debug, Deserialize)]
@@ -18,7 +25,162 @@ pub struct ListArticlesQuery {
     pub per_page: i64,
     pub search: Option<String>,
     pub tag: Option<String>,
+    /// Comma-separated tag names
+    pub tags: Option<String>,
+    #[serde(default)]
+    pub tag_mode: TagMode,
     pub source: Option<String>,
+    /// Comma-separated source ids or names
+    pub sources: Option<String>,
+    /// Comma-separated language codes (`en`, `zh`, `ja`, `ko`)
+    pub language: Option<String>,
+    pub author: Option<String>,
+    pub published_after: Option<String>,
+    pub published_before: Option<String>,
+    pub scraped_after: Option<String>,
+    pub scraped_before: Option<String>,
+    pub min_views: Option<i64>,
+    pub has_thumbnail: Option<bool>,
+    /// Only read (`true`) or unread (`false`) articles
+    pub read: Option<bool>,
+    /// Show each story cluster once, as its earliest report (default: false)
+    #[serde(default)]
+    pub collapse: bool,
+    /// Defaults to `relevance` for searches and `published` otherwise
+    pub sort: Option<ArticleSort>,
+    pub order: Option<SortOrder>,
+    /// Return highlighted snippets for searches (default: true)
+    pub highlight: Option<bool>,
+    /// Snippet fragments per result, 1-5 (default: 2)
+    pub fragments: Option<i32>,
+    pub highlight_pre: Option<String>,
+    pub highlight_post: Option<String>,
+    /// Typo-tolerant title/tag matching for searches. When unset it is used
+    /// only if the exact search finds nothing.
+    pub fuzzy: Option<bool>,
+    /// Include facet counts for the filter (default: false)
+    #[serde(default)]
+    pub facets: bool,
+    /// Tag and author facet size, 1-50 (default: 10)
+    pub facet_limit: Option<i64>,
+    /// Keyset pagination: pass an empty cursor for the first page, then the
+    /// returned `next_cursor`. `page` is ignored and no exact total is counted.
+    pub cursor: Option<String>,
+    /// With `cursor`, include a planner estimate of the total (default: false)
+    #[serde(default)]
+    pub approx_total: bool,
+}
+
+impl ListArticlesQuery {
+    pub fn filter(&self) -> Result<ArticleFilter, (StatusCode, String)> {
+        let tags = split_list(&[&self.tag, &self.tags]);
+        // Accept source ids ("aibase") as well as stored names ("AIBase")
+        let sources = split_list(&[&self.source, &self.sources])
+            .into_iter()
+            .map(search::source_name)
+            .collect();
+        let languages = split_list(&[&self.language])
+            .into_iter()
+            .map(|language| language.to_lowercase())
+            .collect();
+
+        let mut filter = ArticleFilter {
+            tags,
+            tag_mode: self.tag_mode,
+            sources,
+            languages,
+            author: non_empty(&self.author),
+            published_after: parse_date("published_after", &self.published_after)?,
+            published_before: parse_date("published_before", &self.published_before)?,
+            scraped_after: parse_date("scraped_after", &self.scraped_after)?,
+            scraped_before: parse_date("scraped_before", &self.scraped_before)?,
+            min_views: self.min_views,
+            has_thumbnail: self.has_thumbnail,
+            read: self.read,
+            collapse_clusters: self.collapse,
+            fuzzy: self.fuzzy == Some(true),
+            ..Default::default()
+        };
+
+        if let Some(input) = &self.search {
+            search::parse(input, &mut filter).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+        }
+
+        Ok(filter)
+    }
+
+    pub fn sort(&self, filter: &ArticleFilter) -> (ArticleSort, SortOrder) {
+        let sort = match self.sort {
+            Some(ArticleSort::Relevance) | None if filter.search.is_some() => {
+                ArticleSort::Relevance
+            }
+            Some(ArticleSort::Relevance) | None => ArticleSort::Published,
+            Some(sort) => sort,
+        };
+        (sort, self.order.unwrap_or_else(|| sort.default_order()))
+    }
+
+    pub fn highlight(&self) -> Result<Option<HighlightOptions>, (StatusCode, String)> {
+        if self.highlight == Some(false) {
+            return Ok(None);
+        }
+
+        Ok(Some(HighlightOptions {
+            start_sel: marker("highlight_pre", &self.highlight_pre, "<mark>")?,
+            stop_sel: marker("highlight_post", &self.highlight_post, "</mark>")?,
+            max_fragments: self.fragments.unwrap_or(2).clamp(1, 5),
+        }))
+    }
+}
+
+fn non_empty(value: &Option<String>) -> Option<String> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+fn split_list(values: &[&Option<String>]) -> Vec<String> {
+    let mut items: Vec<String> = Vec::new();
+    for value in values.iter().filter_map(|v| v.as_deref()) {
+        for item in value.split(',').map(str::trim).filter(|i| !i.is_empty()) {
+            if !items.iter().any(|existing| existing == item) {
+                items.push(item.to_string());
+            }
+        }
+    }
+    items
+}
+
+fn marker(
+    name: &str,
+    value: &Option<String>,
+    default: &str,
+) -> Result<String, (StatusCode, String)> {
+    match value.as_deref() {
+        None => Ok(default.to_string()),
+        Some(v) if !v.is_empty() && v.len() <= 32 && !v.contains('"') => Ok(v.to_string()),
+        Some(_) => Err((
+            StatusCode::BAD_REQUEST,
+            format!("Invalid {}: must be 1-32 characters without quotes", name),
+        )),
+    }
+}
+
+fn parse_date(
+    name: &str,
+    value: &Option<String>,
+) -> Result<Option<DateTime<Utc>>, (StatusCode, String)> {
+    match non_empty(value) {
+        None => Ok(None),
+        Some(v) => search::parse_date(&v).map(Some).ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("Invalid {}: expected YYYY-MM-DD or RFC 3339", name),
+            )
+        }),
+    }
 }
 
 fn default_page() -> i64 {
@@ -33,14 +195,39 @@ fn default_per_page() -> i64 {
 pub struct PaginatedResponse<T> {
     pub data: Vec<T>,
     pub pagination: Pagination,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facets: Option<ArticleFacets>,
+    /// Whether results include fuzzy (typo-tolerant) matches
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub fuzzy: bool,
 }
 
 #[derive(Debug, Serialize)]
-pub struct Pagination {
-    pub page: i64,
-    pub per_page: i64,
-    pub total: i64,
-    pub total_pages: i64,
+#[serde(untagged)]
+pub enum Pagination {
+    Pages {
+        page: i64,
+        per_page: i64,
+        total: i64,
+        total_pages: i64,
+    },
+    Cursor {
+        per_page: i64,
+        /// `None` on the last page
+        next_cursor: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        approx_total: Option<i64>,
+    },
+}
+
+/// Decode a client cursor; an empty one means the first page
+pub fn decode_cursor(cursor: &str) -> Result<Option<Cursor>, (StatusCode, String)> {
+    if cursor.is_empty() {
+        return Ok(None);
+    }
+    Cursor::decode(cursor)
+        .map(Some)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))
 }
 
 pub async fn list_articles(
@@ -50,34 +237,149 @@ pub async fn list_articles(
     let per_page = query.per_page.min(100).max(1);
     let page = query.page.max(1);
 
-    let (articles, total) = queries::get_articles(
+    let mut filter = query.filter()?;
+    let (sort, order) = query.sort(&filter);
+    let highlight = query.highlight()?;
+
+    if let Some(cursor) = &query.cursor {
+        return list_articles_after(&state, &query, filter, cursor, highlight).await;
+    }
+
+    let (mut articles, mut total) = queries::get_articles(
         &state.pool,
         page,
         per_page,
-        query.search.as_deref(),
-        query.tag.as_deref(),
-        query.source.as_deref(),
+        &filter,
+        sort,
+        order,
+        highlight.as_ref(),
     )
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
+    // Retry misspelled searches ("Gemeni") with fuzzy matching
+    if total == 0 && filter.search.is_some() && query.fuzzy.is_none() {
+        filter.fuzzy = true;
+        (articles, total) = queries::get_articles(
+            &state.pool,
+            page,
+            per_page,
+            &filter,
+            sort,
+            order,
+            highlight.as_ref(),
+        )
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    }
+
+    let facets = get_facets(&state, &query, &filter).await?;
     let total_pages = (total + per_page - 1) / per_page;
 
     Ok(Json(PaginatedResponse {
         data: articles,
-        pagination: Pagination {
+        pagination: Pagination::Pages {
             page,
             per_page,
             total,
             total_pages,
         },
+        facets,
+        fuzzy: filter.fuzzy && filter.search.is_some(),
+    }))
+}
+
+async fn list_articles_after(
+    state: &AppState,
+    query: &ListArticlesQuery,
+    mut filter: ArticleFilter,
+    cursor: &str,
+    highlight: Option<HighlightOptions>,
+) -> Result<Json<PaginatedResponse<ArticlePreview>>, (StatusCode, String)> {
+    let per_page = query.per_page.clamp(1, 100);
+    let (sort, order) = query.sort(&filter);
+
+    let cursor = decode_cursor(cursor)?;
+    // Later pages keep the fuzzy fallback the first page settled on
+    if cursor.as_ref().is_some_and(|c| c.sort.ends_with(":fuzzy")) {
+        filter.fuzzy = true;
+    }
+    let keyset: Option<Keyset> = cursor
+        .map(|c| filter.keyset(sort, order, &c))
+        .transpose()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let (mut articles, mut next) = queries::get_articles_after(
+        &state.pool,
+        per_page,
+        &filter,
+        sort,
+        order,
+        keyset.as_ref(),
+        highlight.as_ref(),
+    )
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Same fuzzy retry as page-based listing, on the first page only
+    if articles.is_empty() && keyset.is_none() && filter.search.is_some() && query.fuzzy.is_none() {
+        filter.fuzzy = true;
+        (articles, next) = queries::get_articles_after(
+            &state.pool,
+            per_page,
+            &filter,
+            sort,
+            order,
+            None,
+            highlight.as_ref(),
+        )
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    }
+
+    let approx_total = if query.approx_total {
+        Some(
+            queries::estimate_articles(&state.pool, &filter)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?,
+        )
+    } else {
+        None
+    };
+    let facets = get_facets(state, query, &filter).await?;
+
+    Ok(Json(PaginatedResponse {
+        data: articles,
+        pagination: Pagination::Cursor {
+            per_page,
+            next_cursor: next.map(|c| c.encode()),
+            approx_total,
+        },
+        facets,
+        fuzzy: filter.fuzzy && filter.search.is_some(),
     }))
 }
 
+async fn get_facets(
+    state: &AppState,
+    query: &ListArticlesQuery,
+    filter: &ArticleFilter,
+) -> Result<Option<ArticleFacets>, (StatusCode, String)> {
+    if !query.facets {
+        return Ok(None);
+    }
+
+    let limit = query.facet_limit.unwrap_or(10).clamp(1, 50);
+    queries::get_article_facets(&state.pool, filter, limit)
+        .await
+        .map(Some)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
 pub async fn get_article(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
-) -> Result<Json<crate::db::models::Article>, (StatusCode, String)> {
+) -> Result<Json<Article>, (StatusCode, String)> {
     // Try parsing as UUID first, then as external_id
     let article = if let Ok(uuid) = Uuid::parse_str(&id) {
         queries::get_article_by_id(&state.pool, uuid).await
@@ -92,15 +394,160 @@ pub async fn get_article(
     }
 }
 
+/// Correct an article by hand. Edited fields and tags survive re-scrapes.
+pub async fn edit_article(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    Json(mut edit): Json<ArticleEdit>,
+) -> Result<Json<Article>, (StatusCode, String)> {
+    if let Some(title) = &mut edit.title {
+        *title = title.trim().to_string();
+        if title.is_empty() {
+            return Err((StatusCode::BAD_REQUEST, "Title is empty".to_string()));
+        }
+    }
+    // Blank values clear the field
+    for value in [&mut edit.excerpt, &mut edit.author].into_iter().flatten() {
+        *value = value
+            .take()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+    }
+
+    if let Some(field) = edit.fields().into_iter().find(|f| edit.revert.contains(f)) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("{} can't be both edited and reverted", field.as_str()),
+        ));
+    }
+
+    // Tags that don't exist yet are created with the edit, once the article
+    // is known to exist
+    edit.add_tags = edit
+        .add_tags
+        .iter()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect();
+
+    let mut remove_tags = Vec::new();
+    for name in &edit.remove_tags {
+        let tag_id = queries::resolve_tag(&state.pool, name)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        remove_tags.extend(tag_id);
+    }
+
+    for name in &edit.add_tags {
+        let tag_id = queries::resolve_tag(&state.pool, name)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        if tag_id.is_some_and(|id| remove_tags.contains(&id)) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "The same tag can't be added and removed".to_string(),
+            ));
+        }
+    }
+
+    let found = queries::edit_article(&state.pool, id, &edit, &[], &remove_tags)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if !found {
+        return Err((StatusCode::NOT_FOUND, "Article not found".to_string()));
+    }
+
+    let article = queries::get_article_by_id(&state.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, "Article not found".to_string()))?;
+
+    Ok(Json(article))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RelatedQuery {
+    #[serde(default = "default_related_limit")]
+    pub limit: usize,
+}
+
+fn default_related_limit() -> usize {
+    5
+}
+
+/// Articles covering the same story, from any source. `rank` is the
+/// combined similarity score.
+pub async fn related_articles(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(query): Query<RelatedQuery>,
+) -> Result<Json<Vec<ArticlePreview>>, (StatusCode, String)> {
+    let article = if let Ok(uuid) = Uuid::parse_str(&id) {
+        queries::get_article_by_id(&state.pool, uuid).await
+    } else {
+        queries::get_article_by_external_id(&state.pool, &id).await
+    }
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .ok_or((StatusCode::NOT_FOUND, "Article not found".to_string()))?;
+
+    let scored = related::related_articles(
+        &state.pool,
+        &state.semantic,
+        article.id,
+        query.limit.clamp(1, 20),
+    )
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let articles = queries::get_scored_previews(&state.pool, &scored)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(articles))
+}
+
+/// An article's tags with their provenance: scraped, assigned by a tagging
+/// rule or added by hand
+pub async fn article_tags(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<ArticleTag>>, (StatusCode, String)> {
+    queries::get_article_by_id(&state.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, "Article not found".to_string()))?;
+
+    let tags = queries::get_article_tags(&state.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(tags))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteArticleQuery {
+    /// Kept with the tombstone, e.g. "spam"
+    pub reason: Option<String>,
+}
+
+/// Move an article to the trash. It is no longer listed, and scrapes skip
+/// it until it is restored.
 pub async fn delete_article(
     State(state): State<Arc<AppState>>,
     Path(id): Path<Uuid>,
+    Query(query): Query<DeleteArticleQuery>,
 ) -> Result<StatusCode, (StatusCode, String)> {
-    let deleted = queries::delete_article(&state.pool, id)
+    let reason = query
+        .reason
+        .as_deref()
+        .map(str::trim)
+        .filter(|r| !r.is_empty());
+    let deleted = queries::trash_article(&state.pool, id, reason)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
     if deleted {
+        state.semantic.remove(&[id]);
         Ok(StatusCode::NO_CONTENT)
     } else {
         Err((StatusCode::NOT_FOUND, "Article not found".to_string()))
@@ -0,0 +1,38 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use std::sync::Arc;
+
+use crate::db::queries;
+use crate::AppState;
+
+pub async fn get_asset(
+    State(state): State<Arc<AppState>>,
+    Path(hash): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid asset hash".to_string()));
+    }
+
+    let asset = queries::get_asset_by_hash(&state.pool, &hash)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, "Asset not found".to_string()))?;
+
+    let bytes = tokio::fs::read(state.assets.path_for(&asset.hash))
+        .await
+        .map_err(|_| (StatusCode::NOT_FOUND, "Asset file missing".to_string()))?;
+
+    // Content-addressed, so the bytes behind a hash never change
+    Ok((
+        [
+            (header::CONTENT_TYPE, asset.content_type),
+            (header::CACHE_CONTROL, "public, max-age=31536000, immutable".to_string()),
+            // Never let the browser second-guess the stored image type
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+        ],
+        bytes,
+    ))
+}
@@ -0,0 +1,210 @@
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio_util::io::ReaderStream;
+use uuid::Uuid;
+
+use super::articles::ListArticlesQuery;
+use crate::bulk::{self, BulkAction, BulkJob, ResolvedAction};
+use crate::db::{models::NewTag, queries};
+use crate::AppState;
+
+const MAX_IDS: usize = 10_000;
+
+/// An action and the articles to apply it to: either `ids` or a `filter`
+/// taking the same parameters as the article listing
+#[derive(Debug, Deserialize)]
+pub struct BulkRequest {
+    #[serde(flatten)]
+    pub action: BulkAction,
+    pub ids: Option<Vec<Uuid>>,
+    pub filter: Option<ListArticlesQuery>,
+    /// Only count the matching articles
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DryRun {
+    pub action: &'static str,
+    pub total: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum BulkResponse {
+    Started(BulkJob),
+    DryRun(DryRun),
+}
+
+/// Start a bulk job, or with `dry_run`, report how many articles it would
+/// touch
+pub async fn start_bulk(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<BulkRequest>,
+) -> Result<(StatusCode, Json<BulkResponse>), (StatusCode, String)> {
+    let ids = match (request.ids, &request.filter) {
+        (Some(ids), None) => {
+            let mut unique = Vec::with_capacity(ids.len());
+            for id in ids {
+                if !unique.contains(&id) {
+                    unique.push(id);
+                }
+            }
+            unique
+        }
+        (None, Some(query)) => {
+            let filter = query.filter()?;
+            queries::get_article_ids(&state.pool, &filter)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        }
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Pass either ids or filter".to_string(),
+            ))
+        }
+    };
+
+    if ids.len() > MAX_IDS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "{} articles selected, at most {} per job",
+                ids.len(),
+                MAX_IDS
+            ),
+        ));
+    }
+
+    if request.dry_run {
+        return Ok((
+            StatusCode::OK,
+            Json(BulkResponse::DryRun(DryRun {
+                action: request.action.name(),
+                total: ids.len(),
+            })),
+        ));
+    }
+
+    let action = resolve(&state, &request.action).await?;
+    let job = state.bulk.start(&request.action, ids.len());
+    tokio::spawn(bulk::run(state.clone(), job.clone(), action, ids));
+
+    Ok((StatusCode::ACCEPTED, Json(BulkResponse::Started(job))))
+}
+
+/// Running and recent jobs, newest first
+pub async fn list_jobs(State(state): State<Arc<AppState>>) -> Json<Vec<BulkJob>> {
+    Json(state.bulk.list())
+}
+
+pub async fn get_job(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<BulkJob>, (StatusCode, String)> {
+    state
+        .bulk
+        .get(id)
+        .map(Json)
+        .ok_or((StatusCode::NOT_FOUND, "Job not found".to_string()))
+}
+
+pub async fn cancel_job(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    if !state.bulk.cancel(id) {
+        return Err((StatusCode::NOT_FOUND, "No such job running".to_string()));
+    }
+
+    Ok(Json(serde_json::json!({
+        "message": "Job cancelled",
+        "job_id": id
+    })))
+}
+
+/// Articles collected by an `export` job, as a JSON array
+pub async fn download_export(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let path = state
+        .bulk
+        .export(id)
+        .ok_or((StatusCode::NOT_FOUND, "Export not found".to_string()))?;
+    let file = tokio::fs::File::open(path)
+        .await
+        .map_err(|_| (StatusCode::NOT_FOUND, "Export file missing".to_string()))?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/json".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"articles-{}.json\"", id),
+            ),
+        ],
+        Body::from_stream(ReaderStream::new(file)),
+    ))
+}
+
+/// Look up the tags a tag or untag action names. Unknown tags are created
+/// for `tag` and ignored for `untag`.
+async fn resolve(
+    state: &AppState,
+    action: &BulkAction,
+) -> Result<ResolvedAction, (StatusCode, String)> {
+    let names = |tags: &[String]| -> Result<Vec<String>, (StatusCode, String)> {
+        let names: Vec<String> = tags
+            .iter()
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        if names.is_empty() {
+            return Err((StatusCode::BAD_REQUEST, "No tags given".to_string()));
+        }
+        Ok(names)
+    };
+
+    Ok(match action {
+        BulkAction::Delete { reason } => ResolvedAction::Delete(
+            reason
+                .as_deref()
+                .map(str::trim)
+                .filter(|r| !r.is_empty())
+                .map(str::to_string),
+        ),
+        BulkAction::Tag { tags } => {
+            let mut ids = Vec::new();
+            for name in names(tags)? {
+                let id = queries::get_or_create_tag(&state.pool, &NewTag::new(name))
+                    .await
+                    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+                ids.push(id);
+            }
+            ResolvedAction::Tag(ids)
+        }
+        BulkAction::Untag { tags } => {
+            let mut ids = Vec::new();
+            for name in names(tags)? {
+                let id = queries::resolve_tag(&state.pool, &name)
+                    .await
+                    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+                ids.extend(id);
+            }
+            ResolvedAction::Untag(ids)
+        }
+        BulkAction::Refresh => ResolvedAction::Refresh,
+        BulkAction::Export => ResolvedAction::Export,
+        BulkAction::MarkRead => ResolvedAction::MarkRead(true),
+        BulkAction::MarkUnread => ResolvedAction::MarkRead(false),
+    })
+}
@@ -0,0 +1,109 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::articles::{PaginatedResponse, Pagination};
+use crate::db::{
+    models::{StoryCluster, StoryClusterRow},
+    queries,
+};
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct ListClustersQuery {
+    #[serde(default = "default_page")]
+    pub page: i64,
+    #[serde(default = "default_per_page")]
+    pub per_page: i64,
+    /// Minimum number of articles (default: 2)
+    #[serde(default = "default_min_size")]
+    pub min_size: i64,
+}
+
+fn default_page() -> i64 {
+    1
+}
+
+fn default_per_page() -> i64 {
+    20
+}
+
+fn default_min_size() -> i64 {
+    2
+}
+
+pub async fn list_clusters(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListClustersQuery>,
+) -> Result<Json<PaginatedResponse<StoryCluster>>, (StatusCode, String)> {
+    let per_page = query.per_page.clamp(1, 100);
+    let page = query.page.max(1);
+
+    let (rows, total) =
+        queries::get_story_clusters(&state.pool, page, per_page, query.min_size.max(1))
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let clusters = with_articles(&state, rows).await?;
+
+    Ok(Json(PaginatedResponse {
+        data: clusters,
+        pagination: Pagination::Pages {
+            page,
+            per_page,
+            total,
+            total_pages: (total + per_page - 1) / per_page,
+        },
+        facets: None,
+        fuzzy: false,
+    }))
+}
+
+pub async fn get_cluster(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<StoryCluster>, (StatusCode, String)> {
+    let row = queries::get_story_cluster(&state.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, "Cluster not found".to_string()))?;
+
+    let mut clusters = with_articles(&state, vec![row]).await?;
+    Ok(Json(clusters.remove(0)))
+}
+
+/// Attach member previews to cluster rows, two queries for the whole page
+async fn with_articles(
+    state: &AppState,
+    rows: Vec<StoryClusterRow>,
+) -> Result<Vec<StoryCluster>, (StatusCode, String)> {
+    let ids: Vec<Uuid> = rows.iter().map(|row| row.id).collect();
+    let members = queries::get_cluster_members(&state.pool, &ids)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let article_ids: Vec<Uuid> = members.into_iter().map(|(_, id)| id).collect();
+    let previews = queries::get_article_previews(&state.pool, &article_ids)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| StoryCluster {
+            articles: previews
+                .iter()
+                .filter(|preview| preview.cluster_id == Some(row.id))
+                .cloned()
+                .collect(),
+            id: row.id,
+            article_count: row.article_count,
+            sources: row.sources,
+            first_seen: row.first_seen,
+            last_seen: row.last_seen,
+        })
+        .collect())
+}
@@ -0,0 +1,183 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::articles::Pagination;
+use crate::db::{
+    models::{Entity, EntityKind, EntityMention, TimelinePoint},
+    queries,
+};
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct ListEntitiesQuery {
+    pub kind: Option<EntityKind>,
+    /// Substring of the entity name or one of its aliases
+    pub q: Option<String>,
+    /// Count only articles from the last `days` days (default: all time)
+    pub days: Option<i64>,
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+}
+
+fn default_limit() -> i64 {
+    50
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EntityArticlesQuery {
+    /// Only articles from the last `days` days; 0 for all time (default: 90)
+    #[serde(default = "default_days")]
+    pub days: i64,
+    #[serde(default = "default_page")]
+    pub page: i64,
+    #[serde(default = "default_per_page")]
+    pub per_page: i64,
+    /// Timeline bucket: `day` (default), `week` or `month`
+    #[serde(default)]
+    pub interval: TimelineInterval,
+}
+
+fn default_days() -> i64 {
+    90
+}
+
+fn default_page() -> i64 {
+    1
+}
+
+fn default_per_page() -> i64 {
+    20
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimelineInterval {
+    #[default]
+    Day,
+    Week,
+    Month,
+}
+
+impl TimelineInterval {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Day => "day",
+            Self::Week => "week",
+            Self::Month => "month",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct EntityArticlesResponse {
+    pub entity: Entity,
+    /// Article counts per interval over the whole period
+    pub timeline: Vec<TimelinePoint>,
+    pub data: Vec<EntityMention>,
+    pub pagination: Pagination,
+}
+
+pub async fn list_entities(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListEntitiesQuery>,
+) -> Result<Json<Vec<Entity>>, (StatusCode, String)> {
+    let search = query.q.as_deref().map(str::trim).filter(|q| !q.is_empty());
+    let entities = queries::get_entities(
+        &state.pool,
+        query.kind,
+        search,
+        query.days.and_then(since),
+        query.limit.clamp(1, 500),
+    )
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(entities))
+}
+
+/// An entity by id, slug or alias, e.g. `/api/entities/qwen` or
+/// `/api/entities/通义千问`
+pub async fn get_entity(
+    State(state): State<Arc<AppState>>,
+    Path(key): Path<String>,
+) -> Result<Json<Entity>, (StatusCode, String)> {
+    find_entity(&state, &key, None).await.map(Json)
+}
+
+/// Articles mentioning an entity, newest first, with a timeline of how
+/// often it came up
+pub async fn entity_articles(
+    State(state): State<Arc<AppState>>,
+    Path(key): Path<String>,
+    Query(query): Query<EntityArticlesQuery>,
+) -> Result<Json<EntityArticlesResponse>, (StatusCode, String)> {
+    let per_page = query.per_page.clamp(1, 100);
+    let page = query.page.max(1);
+    let since = since(query.days);
+    let entity = find_entity(&state, &key, since).await?;
+
+    let (rows, total) = queries::get_entity_articles(&state.pool, entity.id, since, page, per_page)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let timeline =
+        queries::get_entity_timeline(&state.pool, entity.id, since, query.interval.as_str())
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let ids: Vec<_> = rows.iter().map(|(id, _, _)| *id).collect();
+    let previews = queries::get_article_previews(&state.pool, &ids)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut counts: HashMap<Uuid, (i32, bool)> = rows
+        .into_iter()
+        .map(|(id, mentions, in_title)| (id, (mentions, in_title)))
+        .collect();
+    let data = previews
+        .into_iter()
+        .filter_map(|article| {
+            let (mentions, in_title) = counts.remove(&article.id)?;
+            Some(EntityMention {
+                article,
+                mentions,
+                in_title,
+            })
+        })
+        .collect();
+
+    Ok(Json(EntityArticlesResponse {
+        entity,
+        timeline,
+        data,
+        pagination: Pagination::Pages {
+            page,
+            per_page,
+            total,
+            total_pages: (total + per_page - 1) / per_page,
+        },
+    }))
+}
+
+/// Start of a period of `days` days ending now; none for zero or less
+fn since(days: i64) -> Option<DateTime<Utc>> {
+    (days > 0).then(|| Utc::now() - Duration::days(days.min(36500)))
+}
+
+async fn find_entity(
+    state: &AppState,
+    key: &str,
+    since: Option<DateTime<Utc>>,
+) -> Result<Entity, (StatusCode, String)> {
+    queries::get_entity(&state.pool, key, since)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, "Entity not found".to_string()))
+}
@@ -0,0 +1,102 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::db::{
+    models::{DomainStat, LinkingArticle, UrlStat},
+    queries,
+};
+use crate::scraper::links::{normalize_domain, normalize_url};
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct TopLinksQuery {
+    #[serde(default = "default_days")]
+    pub days: i64,
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    pub domain: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LinkingArticlesQuery {
+    pub url: Option<String>,
+    pub domain: Option<String>,
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+}
+
+fn default_days() -> i64 {
+    7
+}
+
+fn default_limit() -> i64 {
+    20
+}
+
+pub async fn top_domains(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TopLinksQuery>,
+) -> Result<Json<Vec<DomainStat>>, (StatusCode, String)> {
+    let stats = queries::get_top_linked_domains(&state.pool, query.days.max(1), query.limit.clamp(1, 100))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(stats))
+}
+
+pub async fn top_urls(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TopLinksQuery>,
+) -> Result<Json<Vec<UrlStat>>, (StatusCode, String)> {
+    let stats = queries::get_top_linked_urls(
+        &state.pool,
+        query.days.max(1),
+        query.limit.clamp(1, 100),
+        query.domain.as_deref().map(normalize_domain).as_deref(),
+    )
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(stats))
+}
+
+pub async fn linking_articles(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<LinkingArticlesQuery>,
+) -> Result<Json<Vec<LinkingArticle>>, (StatusCode, String)> {
+    if query.url.is_none() && query.domain.is_none() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Either url or domain is required".to_string(),
+        ));
+    }
+
+    // Stored links are normalized, so the filters have to be too
+    let url = query
+        .url
+        .as_deref()
+        .map(|url| {
+            normalize_url(url).ok_or((
+                StatusCode::BAD_REQUEST,
+                "url must be an absolute http(s) URL".to_string(),
+            ))
+        })
+        .transpose()?;
+    let domain = query.domain.as_deref().map(normalize_domain);
+
+    let articles = queries::get_articles_linking_to(
+        &state.pool,
+        url.as_deref(),
+        domain.as_deref(),
+        query.limit.clamp(1, 100),
+    )
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(articles))
+}
@@ -0,0 +1,134 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::articles::{PaginatedResponse, Pagination};
+use crate::db::{models::ArchivedArticle, queries, search};
+use crate::maintenance::{MaintenanceReport, RetentionPolicy};
+use crate::AppState;
+
+#[derive(Debug, Serialize)]
+pub struct MaintenanceStatus {
+    pub policy: RetentionPolicy,
+    /// Days trashed articles are kept; 0 keeps them
+    pub trash_retention_days: i64,
+    pub last_run: Option<MaintenanceReport>,
+    pub next_run_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListArchiveQuery {
+    /// Source id or name
+    pub source: Option<String>,
+    #[serde(default = "default_page")]
+    pub page: i64,
+    #[serde(default = "default_per_page")]
+    pub per_page: i64,
+}
+
+fn default_page() -> i64 {
+    1
+}
+
+fn default_per_page() -> i64 {
+    20
+}
+
+/// The retention policy and the last maintenance run
+pub async fn get_status(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<MaintenanceStatus>, (StatusCode, String)> {
+    let policy = RetentionPolicy::load(&state.pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(MaintenanceStatus {
+        next_run_at: state.maintenance.next_run_at(&policy),
+        policy,
+        trash_retention_days: state.config.trash_retention_days,
+        last_run: state.maintenance.last_run(),
+    }))
+}
+
+/// What a maintenance run would remove right now, without removing it
+pub async fn dry_run(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<MaintenanceReport>, (StatusCode, String)> {
+    run(&state, true).await
+}
+
+/// Run maintenance now instead of waiting for the next scheduled run
+pub async fn run_now(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<MaintenanceReport>, (StatusCode, String)> {
+    run(&state, false).await
+}
+
+async fn run(
+    state: &AppState,
+    dry_run: bool,
+) -> Result<Json<MaintenanceReport>, (StatusCode, String)> {
+    let policy = RetentionPolicy::load(&state.pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    state
+        .maintenance
+        .run(state, &policy, dry_run)
+        .await
+        .map(Json)
+        .ok_or((
+            StatusCode::CONFLICT,
+            "Maintenance is already running".to_string(),
+        ))
+}
+
+pub async fn list_archive(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListArchiveQuery>,
+) -> Result<Json<PaginatedResponse<ArchivedArticle>>, (StatusCode, String)> {
+    let per_page = query.per_page.clamp(1, 100);
+    let page = query.page.max(1);
+    let source = query
+        .source
+        .map(|source| search::source_name(source.trim().to_string()))
+        .filter(|source| !source.is_empty());
+
+    let (data, total) =
+        queries::get_archived_articles(&state.pool, source.as_deref(), page, per_page)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(PaginatedResponse {
+        data,
+        pagination: Pagination::Pages {
+            page,
+            per_page,
+            total,
+            total_pages: (total + per_page - 1) / per_page,
+        },
+        facets: None,
+        fuzzy: false,
+    }))
+}
+
+/// An archived article in full, as it was when archived
+pub async fn get_archived_article(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    queries::get_archived_article(&state.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map(Json)
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            "Archived article not found".to_string(),
+        ))
+}
@@ -1,5 +1,15 @@
 pub mod articles;
+pub mod assets;
+pub mod bulk;
+pub mod clusters;
+pub mod entities;
+pub mod links;
+pub mod maintenance;
 pub mod scraper;
+pub mod search;
 pub mod settings;
 pub mod sources;
 pub mod stats;
+pub mod tag_rules;
+pub mod tags;
+pub mod trash;
@@ -8,11 +8,14 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
+use super::articles::{decode_cursor, Pagination};
 use crate::db::{
-    models::{ScrapeRun, ScrapeStatus, ScrapeType},
+    models::{NewArticle, ScrapeRun, ScrapeStatus, ScrapeType},
     queries,
 };
 use crate::scraper::{
+    assets::AssetStore,
+    tagger::Tagger,
     sources::{aibase::AIBaseScraper, smolai::SmolAIScraper},
     Source, ProgressType, ScrapeProgress,
 };
@@ -116,10 +119,14 @@ pub async fn start_scrape(
     let pool = state.pool.clone();
     let config = state.config.clone();
     let progress_tx = state.progress_tx.clone();
+    let assets = state.assets.clone();
+    let tagger = state.tagger.clone();
 
     tokio::spawn(async move {
         let result = run_source_scrape(
             pool.clone(),
+            assets,
+            tagger,
             config.scraper_rate_limit,
             config.scraper_max_retries,
             run_id,
@@ -231,10 +238,14 @@ pub async fn start_range_scrape(
     let pool = state.pool.clone();
     let config = state.config.clone();
     let progress_tx = state.progress_tx.clone();
+    let assets = state.assets.clone();
+    let tagger = state.tagger.clone();
 
     tokio::spawn(async move {
         let result = run_range_scrape(
             pool.clone(),
+            assets,
+            tagger,
             config.scraper_rate_limit,
             config.scraper_max_retries,
             run_id,
@@ -283,6 +294,8 @@ pub async fn start_range_scrape(
 
 async fn run_range_scrape(
     pool: sqlx::PgPool,
+    assets: Arc<AssetStore>,
+    tagger: Arc<Tagger>,
     rate_limit: u32,
     max_retries: u32,
     run_id: Uuid,
@@ -328,10 +341,12 @@ async fn run_range_scrape(
         let external_id = article_id.to_string();
         processed += 1;
 
+        // Deleted articles stay deleted, even when re-scraping
+        let tombstoned = queries::is_tombstoned(&pool, source_name, &external_id).await?;
         // Check if article already exists
         let exists = queries::article_exists(&pool, source_name, &external_id).await?;
 
-        if exists && !force_rescrape {
+        if tombstoned || (exists && !force_rescrape) {
             articles_skipped += 1;
             // Still send progress updates periodically
             if processed % 100 == 0 {
@@ -344,7 +359,7 @@ async fn run_range_scrape(
                     articles_new,
                     articles_failed,
                     current_article: Some(external_id.clone()),
-                    message: Some(format!("Skipped {} existing", articles_skipped)),
+                    message: Some(format!("Skipped {} existing or deleted", articles_skipped)),
                 });
             }
             continue;
@@ -367,10 +382,11 @@ async fn run_range_scrape(
         match scraper.scrape_article(&external_id).await {
             Ok(article) => {
                 articles_found += 1;
-                if exists {
-                    queries::update_article(&pool, source_name, &external_id, &article).await?;
-                } else {
-                    queries::insert_article(&pool, &article).await?;
+                store_article(
+                    &pool, &assets, &tagger, source_name, &external_id, article, exists,
+                )
+                .await?;
+                if !exists {
                     articles_new += 1;
                 }
 
@@ -430,6 +446,8 @@ async fn run_range_scrape(
 
 async fn run_source_scrape(
     pool: sqlx::PgPool,
+    assets: Arc<AssetStore>,
+    tagger: Arc<Tagger>,
     rate_limit: u32,
     max_retries: u32,
     run_id: Uuid,
@@ -457,14 +475,14 @@ async fn run_source_scrape(
     match source {
         Source::AIBase => {
             run_aibase_scrape(
-                pool, rate_limit, max_retries, run_id, max_pages, stop_on_existing,
-                force_rescrape, progress_tx,
+                pool, assets, tagger, rate_limit, max_retries, run_id, max_pages,
+                stop_on_existing, force_rescrape, progress_tx,
             ).await
         }
         Source::SmolAI => {
             run_smolai_scrape(
-                pool, rate_limit, max_retries, run_id, max_pages, stop_on_existing,
-                force_rescrape, progress_tx,
+                pool, assets, tagger, rate_limit, max_retries, run_id, max_pages,
+                stop_on_existing, force_rescrape, progress_tx,
             ).await
         }
     }
@@ -472,6 +490,8 @@ async fn run_source_scrape(
 
 async fn run_aibase_scrape(
     pool: sqlx::PgPool,
+    assets: Arc<AssetStore>,
+    tagger: Arc<Tagger>,
     rate_limit: u32,
     max_retries: u32,
     run_id: Uuid,
@@ -487,6 +507,7 @@ async fn run_aibase_scrape(
     let mut articles_found = 0;
     let mut articles_new = 0;
     let mut articles_failed = 0;
+    let mut articles_skipped = 0;
 
     for page in 1..=max_pages {
         // Check cancellation
@@ -517,15 +538,32 @@ async fn run_aibase_scrape(
         for (external_id, _title) in &previews {
             articles_found += 1;
 
+            // Deleted articles stay deleted, even when re-scraping
+            let tombstoned = queries::is_tombstoned(&pool, source_name, external_id).await?;
             // Check if article already exists
             let exists = queries::article_exists(&pool, source_name, external_id).await?;
+            // Only stored articles show the page was scraped before; tombstones
+            // also block articles that never were
+            if !exists {
+                all_existing = false;
+            }
 
-            if exists && !force_rescrape {
+            if tombstoned || (exists && !force_rescrape) {
+                articles_skipped += 1;
+                let _ = progress_tx.send(ScrapeProgress {
+                    run_id,
+                    progress_type: ProgressType::Progress,
+                    pages_scraped,
+                    total_pages: Some(max_pages as i32),
+                    articles_found,
+                    articles_new,
+                    articles_failed,
+                    current_article: Some(external_id.clone()),
+                    message: Some(format!("Skipped {} existing or deleted", articles_skipped)),
+                });
                 continue;
             }
 
-            all_existing = false;
-
             // Send progress
             let _ = progress_tx.send(ScrapeProgress {
                 run_id,
@@ -542,10 +580,11 @@ async fn run_aibase_scrape(
             // Scrape article
             match scraper.scrape_article(external_id).await {
                 Ok(article) => {
-                    if exists {
-                        queries::update_article(&pool, source_name, external_id, &article).await?;
-                    } else {
-                        queries::insert_article(&pool, &article).await?;
+                    store_article(
+                        &pool, &assets, &tagger, source_name, external_id, article, exists,
+                    )
+                    .await?;
+                    if !exists {
                         articles_new += 1;
                     }
                 }
@@ -577,8 +616,8 @@ async fn run_aibase_scrape(
     }
 
     tracing::info!(
-        "AIBase scrape complete: {} pages, {} articles found, {} new, {} failed",
-        pages_scraped, articles_found, articles_new, articles_failed
+        "AIBase scrape complete: {} pages, {} articles found, {} new, {} failed, {} skipped",
+        pages_scraped, articles_found, articles_new, articles_failed, articles_skipped
     );
 
     Ok(())
@@ -586,6 +625,8 @@ async fn run_aibase_scrape(
 
 async fn run_smolai_scrape(
     pool: sqlx::PgPool,
+    assets: Arc<AssetStore>,
+    tagger: Arc<Tagger>,
     rate_limit: u32,
     max_retries: u32,
     run_id: Uuid,
@@ -619,6 +660,7 @@ async fn run_smolai_scrape(
     let mut processed = 0;
     let mut articles_new = 0;
     let mut articles_failed = 0;
+    let mut articles_skipped = 0;
 
     for external_id in &article_ids {
         // Check cancellation
@@ -631,15 +673,29 @@ async fn run_smolai_scrape(
 
         processed += 1;
 
+        // Deleted articles stay deleted, even when re-scraping
+        let tombstoned = queries::is_tombstoned(&pool, source_name, external_id).await?;
         // Check if article already exists
         let exists = queries::article_exists(&pool, source_name, external_id).await?;
 
-        if exists && !force_rescrape {
+        if tombstoned || (exists && !force_rescrape) {
             // In incremental mode, stop if we hit existing articles
-            if stop_on_existing {
+            if exists && stop_on_existing {
                 tracing::info!("smol.ai: Found existing article {}, stopping", external_id);
                 break;
             }
+            articles_skipped += 1;
+            let _ = progress_tx.send(ScrapeProgress {
+                run_id,
+                progress_type: ProgressType::Progress,
+                pages_scraped: processed,
+                total_pages: Some(total),
+                articles_found: total,
+                articles_new,
+                articles_failed,
+                current_article: Some(external_id.clone()),
+                message: Some(format!("Skipped {} existing or deleted", articles_skipped)),
+            });
             continue;
         }
 
@@ -659,10 +715,11 @@ async fn run_smolai_scrape(
         // Scrape article
         match scraper.scrape_article(external_id).await {
             Ok(article) => {
-                if exists {
-                    queries::update_article(&pool, source_name, external_id, &article).await?;
-                } else {
-                    queries::insert_article(&pool, &article).await?;
+                store_article(
+                    &pool, &assets, &tagger, source_name, external_id, article, exists,
+                )
+                .await?;
+                if !exists {
                     articles_new += 1;
                 }
 
@@ -706,13 +763,38 @@ async fn run_smolai_scrape(
     .await?;
 
     tracing::info!(
-        "smol.ai scrape complete: {}/{} processed, {} new, {} failed",
-        processed, total, articles_new, articles_failed
+        "smol.ai scrape complete: {}/{} processed, {} new, {} failed, {} skipped",
+        processed, total, articles_new, articles_failed, articles_skipped
     );
 
     Ok(())
 }
 
+/// Mirror an article's images, then insert or update it, record its assets
+/// and apply the tagging rules
+pub async fn store_article(
+    pool: &sqlx::PgPool,
+    assets: &AssetStore,
+    tagger: &Tagger,
+    source_name: &str,
+    external_id: &str,
+    mut article: NewArticle,
+    exists: bool,
+) -> anyhow::Result<()> {
+    let mirrored = assets.mirror_article(pool, &mut article).await;
+
+    let article_id = if exists {
+        queries::update_article(pool, source_name, external_id, &article).await?
+    } else {
+        queries::insert_article(pool, &article).await?
+    };
+
+    queries::replace_article_assets(pool, article_id, &mirrored).await?;
+    tagger
+        .tag_article(pool, article_id, source_name, &article.title, &article.content)
+        .await
+}
+
 pub async fn stop_scrape(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
@@ -753,19 +835,69 @@ pub async fn get_status(
 pub struct ListRunsQuery {
     #[serde(default = "default_limit")]
     pub limit: i64,
+    /// Keyset pagination, as for articles: empty for the first page
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub approx_total: bool,
 }
 
 fn default_limit() -> i64 {
     20
 }
 
+/// A plain list without `cursor`, kept for existing clients
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum RunsResponse {
+    List(Vec<ScrapeRun>),
+    Page {
+        data: Vec<ScrapeRun>,
+        pagination: Pagination,
+    },
+}
+
 pub async fn list_runs(
     State(state): State<Arc<AppState>>,
     Query(query): Query<ListRunsQuery>,
-) -> Result<Json<Vec<ScrapeRun>>, (StatusCode, String)> {
-    let runs = queries::get_scrape_runs(&state.pool, query.limit)
+) -> Result<Json<RunsResponse>, (StatusCode, String)> {
+    let Some(cursor) = &query.cursor else {
+        let runs = queries::get_scrape_runs(&state.pool, query.limit)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        return Ok(Json(RunsResponse::List(runs)));
+    };
+
+    let per_page = query.limit.clamp(1, 100);
+    let after = match decode_cursor(cursor)? {
+        Some(c) if c.sort == queries::RUNS_CURSOR_SORT && c.keys.len() == 1 => {
+            let started_at = serde_json::from_value(c.keys[0].clone())
+                .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid cursor".to_string()))?;
+            Some((started_at, c.id))
+        }
+        Some(_) => return Err((StatusCode::BAD_REQUEST, "Invalid cursor".to_string())),
+        None => None,
+    };
+
+    let (runs, next) = queries::get_scrape_runs_after(&state.pool, per_page, after)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    Ok(Json(runs))
+    let approx_total = if query.approx_total {
+        Some(
+            queries::estimate_scrape_runs(&state.pool)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?,
+        )
+    } else {
+        None
+    };
+
+    Ok(Json(RunsResponse::Page {
+        data: runs,
+        pagination: Pagination::Cursor {
+            per_page,
+            next_cursor: next.map(|c| c.encode()),
+            approx_total,
+        },
+    }))
 }
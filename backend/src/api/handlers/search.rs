@@ -0,0 +1,155 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::db::filter::{ArticleFilter, ArticleSort, SortOrder};
+use crate::db::{
+    models::{ArticlePreview, Suggestions},
+    queries, search,
+};
+use crate::AppState;
+
+/// Reciprocal rank fusion constant; higher values flatten the rank curve
+const RRF_K: f32 = 60.0;
+
+#[derive(Debug, Deserialize)]
+pub struct SuggestQuery {
+    #[serde(default)]
+    pub q: String,
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+}
+
+fn default_limit() -> i64 {
+    5
+}
+
+pub async fn suggest(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SuggestQuery>,
+) -> Result<Json<Suggestions>, (StatusCode, String)> {
+    let q = query.q.trim();
+    if q.is_empty() {
+        return Ok(Json(Suggestions {
+            titles: Vec::new(),
+            tags: Vec::new(),
+            authors: Vec::new(),
+        }));
+    }
+
+    let suggestions = queries::get_suggestions(&state.pool, q, query.limit.clamp(1, 20))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(suggestions))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SemanticQuery {
+    #[serde(default)]
+    pub q: String,
+    #[serde(default = "default_semantic_limit")]
+    pub limit: usize,
+    #[serde(default)]
+    pub mode: SearchMode,
+}
+
+fn default_semantic_limit() -> usize {
+    10
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMode {
+    /// Embedding similarity only
+    #[default]
+    Semantic,
+    /// Embedding similarity fused with full-text relevance
+    Hybrid,
+}
+
+/// Articles closest in meaning to `q`. `rank` is the cosine similarity, or
+/// the fused reciprocal-rank score in hybrid mode.
+pub async fn semantic_search(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SemanticQuery>,
+) -> Result<Json<Vec<ArticlePreview>>, (StatusCode, String)> {
+    let q = query.q.trim();
+    if q.is_empty() {
+        return Ok(Json(Vec::new()));
+    }
+    let limit = query.limit.clamp(1, 50);
+
+    let scored = match query.mode {
+        SearchMode::Semantic => state
+            .semantic
+            .search(q, limit)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?,
+        SearchMode::Hybrid => hybrid_scores(&state, q, limit).await?,
+    };
+
+    let articles = queries::get_scored_previews(&state.pool, &scored)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(articles))
+}
+
+/// Fuse the semantic and full-text rankings with reciprocal rank fusion, so
+/// neither score scale has to be calibrated against the other
+async fn hybrid_scores(
+    state: &AppState,
+    q: &str,
+    limit: usize,
+) -> Result<Vec<(Uuid, f32)>, (StatusCode, String)> {
+    let candidates = (limit * 4).max(50);
+
+    let semantic = state
+        .semantic
+        .search(q, candidates)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut filter = ArticleFilter::default();
+    search::parse(q, &mut filter).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    // Without full-text terms the keyword leg would rank the newest articles,
+    // which say nothing about relevance
+    let keyword = if filter.search.is_none() {
+        Vec::new()
+    } else {
+        queries::get_articles_after(
+            &state.pool,
+            candidates as i64,
+            &filter,
+            ArticleSort::Relevance,
+            SortOrder::Desc,
+            None,
+            None,
+        )
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .0
+    };
+
+    let mut scores: HashMap<Uuid, f32> = HashMap::new();
+    let ranked = semantic
+        .iter()
+        .map(|(id, _)| *id)
+        .enumerate()
+        .chain(keyword.iter().map(|a| a.id).enumerate());
+    for (rank, id) in ranked {
+        *scores.entry(id).or_default() += 1.0 / (RRF_K + rank as f32 + 1.0);
+    }
+
+    let mut scores: Vec<(Uuid, f32)> = scores.into_iter().collect();
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scores.truncate(limit);
+    Ok(scores)
+}
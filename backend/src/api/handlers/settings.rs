@@ -6,6 +6,7 @@ use axum::{
 use std::sync::Arc;
 
 use crate::db::{models::ScraperSetting, queries};
+use crate::maintenance::{RetentionPolicy, LAST_RUN_KEY};
 use crate::AppState;
 
 pub async fn get_settings(
@@ -23,6 +24,17 @@ pub async fn update_setting(
     Path(key): Path<String>,
     Json(value): Json<serde_json::Value>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    if key == LAST_RUN_KEY {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Setting '{}' is written by the maintenance task", key),
+        ));
+    }
+    if key == "retention" {
+        RetentionPolicy::from_value(value.clone())
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid retention policy: {}", e)))?;
+    }
+
     let updated = queries::update_setting(&state.pool, &key, value.clone())
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
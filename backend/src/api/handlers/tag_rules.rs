@@ -0,0 +1,234 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::db::{
+    models::{ArticlePreview, NewTag, NewTagRule, TagRule},
+    queries, search,
+};
+use crate::scraper::tagger::{self, CompiledRule};
+use crate::AppState;
+
+const LANGUAGES: &[&str] = &["en", "zh", "ja", "ko"];
+
+#[derive(Debug, Deserialize)]
+pub struct PreviewRequest {
+    #[serde(flatten)]
+    pub rule: NewTagRule,
+    /// Number of matching articles to return (default: 20)
+    #[serde(default = "default_preview_limit")]
+    pub limit: usize,
+}
+
+fn default_preview_limit() -> usize {
+    20
+}
+
+#[derive(Debug, Serialize)]
+pub struct RulePreview {
+    /// Articles the rule matches in total
+    pub total: usize,
+    pub articles: Vec<PreviewMatch>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PreviewMatch {
+    #[serde(flatten)]
+    pub article: ArticlePreview,
+    /// Text the rule matched first
+    pub matched: String,
+}
+
+pub async fn list_rules(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<TagRule>>, (StatusCode, String)> {
+    let rules = queries::get_tag_rules(&state.pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(rules))
+}
+
+pub async fn get_rule(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> Result<Json<TagRule>, (StatusCode, String)> {
+    find_rule(&state, id).await.map(Json)
+}
+
+pub async fn create_rule(
+    State(state): State<Arc<AppState>>,
+    Json(rule): Json<NewTagRule>,
+) -> Result<(StatusCode, Json<TagRule>), (StatusCode, String)> {
+    let rule = validate(rule)?;
+    let tag_id = queries::get_or_create_tag(&state.pool, &NewTag::new(rule.tag.clone()))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let id = queries::create_tag_rule(&state.pool, tag_id, &rule)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    reload(&state).await?;
+
+    Ok((StatusCode::CREATED, Json(find_rule(&state, id).await?)))
+}
+
+/// Replace a rule. Articles are re-tagged on the next apply.
+pub async fn update_rule(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Json(rule): Json<NewTagRule>,
+) -> Result<Json<TagRule>, (StatusCode, String)> {
+    let rule = validate(rule)?;
+    find_rule(&state, id).await?;
+    let tag_id = queries::get_or_create_tag(&state.pool, &NewTag::new(rule.tag.clone()))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    queries::update_tag_rule(&state.pool, id, tag_id, &rule)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    reload(&state).await?;
+
+    find_rule(&state, id).await.map(Json)
+}
+
+/// Delete a rule and the tags it assigned
+pub async fn delete_rule(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let deleted = queries::delete_tag_rule(&state.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if !deleted {
+        return Err((StatusCode::NOT_FOUND, "Tag rule not found".to_string()));
+    }
+    reload(&state).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Articles a rule would tag, without saving the rule or tagging anything
+pub async fn preview_rule(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<PreviewRequest>,
+) -> Result<Json<RulePreview>, (StatusCode, String)> {
+    let rule = validate(request.rule)?;
+    let compiled =
+        CompiledRule::new(0, 0, &rule).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    let (total, sample) = tagger::preview(&state.pool, &compiled, request.limit.clamp(1, 100))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let ids: Vec<_> = sample.iter().map(|(id, _)| *id).collect();
+    let previews = queries::get_article_previews(&state.pool, &ids)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Articles removed since the scan have no preview
+    let mut matched: HashMap<Uuid, String> = sample.into_iter().collect();
+    let articles = previews
+        .into_iter()
+        .filter_map(|article| {
+            let matched = matched.remove(&article.id)?;
+            Some(PreviewMatch { article, matched })
+        })
+        .collect();
+
+    Ok(Json(RulePreview { total, articles }))
+}
+
+/// Re-tag every article with the current rules in the background
+pub async fn apply_rules(
+    State(state): State<Arc<AppState>>,
+) -> Result<(StatusCode, Json<serde_json::Value>), (StatusCode, String)> {
+    if state.tagger.is_backfilling() {
+        return Err((
+            StatusCode::CONFLICT,
+            "Tag rules are already being applied".to_string(),
+        ));
+    }
+
+    let pool = state.pool.clone();
+    let tagger = state.tagger.clone();
+    tokio::spawn(async move {
+        match tagger.backfill(&pool).await {
+            Ok(Some((processed, tagged))) => tracing::info!(
+                "Applied tag rules to {} articles, {} got rule tags",
+                processed,
+                tagged
+            ),
+            Ok(None) => {}
+            Err(e) => tracing::error!("Applying tag rules failed: {}", e),
+        }
+    });
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(serde_json::json!({ "message": "Applying tag rules to all articles" })),
+    ))
+}
+
+/// Trim the rule, drop blank patterns, map source ids to names and check
+/// that the patterns compile
+fn validate(mut rule: NewTagRule) -> Result<NewTagRule, (StatusCode, String)> {
+    rule.tag = rule.tag.trim().to_string();
+    if rule.tag.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Tag is empty".to_string()));
+    }
+
+    rule.patterns = rule
+        .patterns
+        .iter()
+        .map(|pattern| pattern.trim().to_string())
+        .filter(|pattern| !pattern.is_empty())
+        .collect();
+
+    rule.source = rule
+        .source
+        .map(|source| search::source_name(source.trim().to_string()))
+        .filter(|source| !source.is_empty());
+
+    rule.language = rule
+        .language
+        .map(|language| language.trim().to_lowercase())
+        .filter(|language| !language.is_empty());
+    if let Some(language) = &rule.language {
+        if !LANGUAGES.contains(&language.as_str()) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "Unknown language '{}', expected one of {}",
+                    language,
+                    LANGUAGES.join(", ")
+                ),
+            ));
+        }
+    }
+
+    CompiledRule::new(0, 0, &rule).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    Ok(rule)
+}
+
+async fn reload(state: &AppState) -> Result<(), (StatusCode, String)> {
+    state
+        .tagger
+        .reload(&state.pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+async fn find_rule(state: &AppState, id: i32) -> Result<TagRule, (StatusCode, String)> {
+    queries::get_tag_rule(&state.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, "Tag rule not found".to_string()))
+}
@@ -0,0 +1,188 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::db::{
+    models::{present, Tag, TagCategory},
+    queries,
+};
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct ListTagsQuery {
+    pub category: Option<TagCategory>,
+    /// Substring of the tag name or one of its aliases
+    pub q: Option<String>,
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+}
+
+fn default_limit() -> i64 {
+    100
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTagRequest {
+    pub name: Option<String>,
+    /// `null` clears the category, leaving it out keeps it
+    #[serde(default, deserialize_with = "present")]
+    pub category: Option<Option<TagCategory>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MergeTagRequest {
+    /// Tag that takes over the articles and aliases
+    pub into: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddAliasRequest {
+    pub alias: String,
+}
+
+pub async fn list_tags(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListTagsQuery>,
+) -> Result<Json<Vec<Tag>>, (StatusCode, String)> {
+    let search = query.q.as_deref().map(str::trim).filter(|q| !q.is_empty());
+    let tags = queries::get_tags(
+        &state.pool,
+        query.category,
+        search,
+        query.limit.clamp(1, 1000),
+    )
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(tags))
+}
+
+pub async fn get_tag(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> Result<Json<Tag>, (StatusCode, String)> {
+    find_tag(&state, id).await.map(Json)
+}
+
+pub async fn update_tag(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Json(request): Json<UpdateTagRequest>,
+) -> Result<Json<Tag>, (StatusCode, String)> {
+    find_tag(&state, id).await?;
+
+    if let Some(name) = request.name.as_deref().map(str::trim) {
+        if name.is_empty() {
+            return Err((StatusCode::BAD_REQUEST, "Tag name is empty".to_string()));
+        }
+        let existing = queries::resolve_tag(&state.pool, name)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        if existing.is_some_and(|existing| existing != id) {
+            return Err((
+                StatusCode::CONFLICT,
+                format!(
+                    "'{}' is already a tag or alias; merge the tags instead",
+                    name
+                ),
+            ));
+        }
+        queries::rename_tag(&state.pool, id, name)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    }
+
+    if let Some(category) = request.category {
+        queries::set_tag_category(&state.pool, id, category)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    }
+
+    find_tag(&state, id).await.map(Json)
+}
+
+pub async fn merge_tag(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Json(request): Json<MergeTagRequest>,
+) -> Result<Json<Tag>, (StatusCode, String)> {
+    if id == request.into {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Cannot merge a tag into itself".to_string(),
+        ));
+    }
+    find_tag(&state, id).await?;
+    find_tag(&state, request.into).await?;
+
+    queries::merge_tags(&state.pool, id, request.into)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    find_tag(&state, request.into).await.map(Json)
+}
+
+pub async fn delete_tag(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let deleted = queries::delete_tag(&state.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, "Tag not found".to_string()))
+    }
+}
+
+pub async fn add_alias(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Json(request): Json<AddAliasRequest>,
+) -> Result<Json<Tag>, (StatusCode, String)> {
+    let alias = request.alias.trim();
+    if alias.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Alias is empty".to_string()));
+    }
+    find_tag(&state, id).await?;
+
+    let added = queries::add_tag_alias(&state.pool, id, alias)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if !added {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("'{}' is already a tag; merge the tags instead", alias),
+        ));
+    }
+
+    find_tag(&state, id).await.map(Json)
+}
+
+pub async fn delete_alias(
+    State(state): State<Arc<AppState>>,
+    Path(alias): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let deleted = queries::delete_tag_alias(&state.pool, &alias)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, "Alias not found".to_string()))
+    }
+}
+
+async fn find_tag(state: &AppState, id: i32) -> Result<Tag, (StatusCode, String)> {
+    queries::get_tag(&state.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, "Tag not found".to_string()))
+}
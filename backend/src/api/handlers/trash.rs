@@ -0,0 +1,216 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::articles::{PaginatedResponse, Pagination};
+use crate::db::{
+    models::{Article, Tombstone, TrashedArticle},
+    queries, search,
+};
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct ListTrashQuery {
+    #[serde(default = "default_page")]
+    pub page: i64,
+    #[serde(default = "default_per_page")]
+    pub per_page: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListTombstonesQuery {
+    /// Source id or name
+    pub source: Option<String>,
+    #[serde(default = "default_page")]
+    pub page: i64,
+    #[serde(default = "default_per_page")]
+    pub per_page: i64,
+}
+
+fn default_page() -> i64 {
+    1
+}
+
+fn default_per_page() -> i64 {
+    20
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrashEntry {
+    #[serde(flatten)]
+    pub article: TrashedArticle,
+    /// When the article will be purged; never if retention is disabled
+    pub purge_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewTombstone {
+    pub source: String,
+    pub external_id: String,
+    pub reason: Option<String>,
+}
+
+pub async fn list_trash(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListTrashQuery>,
+) -> Result<Json<PaginatedResponse<TrashEntry>>, (StatusCode, String)> {
+    let per_page = query.per_page.clamp(1, 100);
+    let page = query.page.max(1);
+
+    let (articles, total) = queries::get_trashed_articles(&state.pool, page, per_page)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let retention_days = state.config.trash_retention_days;
+    let data = articles
+        .into_iter()
+        .map(|article| TrashEntry {
+            purge_at: (retention_days > 0)
+                .then(|| article.deleted_at + Duration::days(retention_days)),
+            article,
+        })
+        .collect();
+
+    Ok(Json(PaginatedResponse {
+        data,
+        pagination: Pagination::Pages {
+            page,
+            per_page,
+            total,
+            total_pages: (total + per_page - 1) / per_page,
+        },
+        facets: None,
+        fuzzy: false,
+    }))
+}
+
+/// Take an article out of the trash; scrapes pick it up again
+pub async fn restore_article(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Article>, (StatusCode, String)> {
+    let restored = queries::restore_article(&state.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if !restored {
+        return Err((StatusCode::NOT_FOUND, "Article not in trash".to_string()));
+    }
+    if let Err(e) = state.semantic.restore(&state.pool, id).await {
+        tracing::warn!("Failed to restore embedding of {}: {}", id, e);
+    }
+
+    queries::get_article_by_id(&state.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map(Json)
+        .ok_or((StatusCode::NOT_FOUND, "Article not found".to_string()))
+}
+
+/// Permanently delete a trashed article. Its tombstone stays, so it is not
+/// scraped again.
+pub async fn purge_article(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let purged = queries::purge_article(&state.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if purged {
+        // Trashing already took it out of the index; this covers a purge
+        // racing a restore
+        state.semantic.remove(&[id]);
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, "Article not in trash".to_string()))
+    }
+}
+
+/// Permanently delete everything in the trash
+pub async fn empty_trash(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let purged = queries::purge_trash(&state.pool, None)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(serde_json::json!({ "purged": purged })))
+}
+
+pub async fn list_tombstones(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListTombstonesQuery>,
+) -> Result<Json<PaginatedResponse<Tombstone>>, (StatusCode, String)> {
+    let per_page = query.per_page.clamp(1, 100);
+    let page = query.page.max(1);
+    let source = query
+        .source
+        .map(|source| search::source_name(source.trim().to_string()))
+        .filter(|source| !source.is_empty());
+
+    let (data, total) = queries::get_tombstones(&state.pool, source.as_deref(), page, per_page)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(PaginatedResponse {
+        data,
+        pagination: Pagination::Pages {
+            page,
+            per_page,
+            total,
+            total_pages: (total + per_page - 1) / per_page,
+        },
+        facets: None,
+        fuzzy: false,
+    }))
+}
+
+/// Block an article from being scraped, whether or not it is stored yet.
+/// A stored copy is moved to the trash.
+pub async fn add_tombstone(
+    State(state): State<Arc<AppState>>,
+    Json(tombstone): Json<NewTombstone>,
+) -> Result<(StatusCode, Json<Tombstone>), (StatusCode, String)> {
+    let source = search::source_name(tombstone.source.trim().to_string());
+    let external_id = tombstone.external_id.trim();
+    if source.is_empty() || external_id.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Source and external_id are required".to_string(),
+        ));
+    }
+    let reason = tombstone
+        .reason
+        .as_deref()
+        .map(str::trim)
+        .filter(|r| !r.is_empty());
+
+    let tombstone = queries::add_tombstone(&state.pool, &source, external_id, reason)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok((StatusCode::CREATED, Json(tombstone)))
+}
+
+/// Let an article be scraped again. A trashed copy stays in the trash.
+pub async fn delete_tombstone(
+    State(state): State<Arc<AppState>>,
+    Path((source, external_id)): Path<(String, String)>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let source = search::source_name(source);
+    let deleted = queries::delete_tombstone(&state.pool, &source, &external_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, "Tombstone not found".to_string()))
+    }
+}
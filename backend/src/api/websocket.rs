@@ -7,6 +7,7 @@ use axum::{
 };
 use futures::{SinkExt, StreamExt};
 use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
 
 use crate::AppState;
 
@@ -20,11 +21,25 @@ pub async fn ws_handler(
 async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
     let (mut sender, mut receiver) = socket.split();
     let mut progress_rx = state.progress_tx.subscribe();
+    let mut jobs_rx = state.bulk.subscribe();
 
-    // Task to send progress updates to client
+    // Task to send scrape progress and bulk job updates to client. A client
+    // too slow to keep up misses some updates rather than being dropped.
     let send_task = tokio::spawn(async move {
-        while let Ok(progress) = progress_rx.recv().await {
-            let json = match serde_json::to_string(&progress) {
+        loop {
+            let json = tokio::select! {
+                progress = progress_rx.recv() => match progress {
+                    Ok(progress) => serde_json::to_string(&progress),
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                },
+                job = jobs_rx.recv() => match job {
+                    Ok(job) => serde_json::to_string(&job),
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                },
+            };
+            let json = match json {
                 Ok(j) => j,
                 Err(_) => continue,
             };
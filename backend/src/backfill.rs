@@ -0,0 +1,175 @@
+use anyhow::Result;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db::queries;
+use crate::scraper::content::ArticleBody;
+use crate::scraper::{excerpt, fingerprint};
+use crate::text;
+use crate::scraper::links::extract_links;
+
+const BATCH_SIZE: i64 = 100;
+
+/// One-off data backfills that need Rust-side processing after migrations.
+/// Jobs run in order, since later ones read what earlier ones produce.
+pub async fn run(pool: PgPool) {
+    if let Err(e) = backfill_markdown(&pool).await {
+        tracing::error!("Markdown backfill failed: {}", e);
+    }
+
+    if let Err(e) = backfill_links(&pool).await {
+        tracing::error!("Link backfill failed: {}", e);
+    }
+
+    if let Err(e) = backfill_simhash(&pool).await {
+        tracing::error!("SimHash backfill failed: {}", e);
+    }
+
+    if let Err(e) = backfill_excerpts(&pool).await {
+        tracing::error!("Excerpt backfill failed: {}", e);
+    }
+
+    if let Err(e) = backfill_language(&pool).await {
+        tracing::error!("Language backfill failed: {}", e);
+    }
+}
+
+/// Sanitize HTML bodies and derive plain text and Markdown from them
+async fn backfill_markdown(pool: &PgPool) -> Result<()> {
+    let mut converted = 0;
+
+    loop {
+        let rows = queries::get_articles_missing_markdown(pool, BATCH_SIZE).await?;
+        if rows.is_empty() {
+            break;
+        }
+
+        for (id, url, html) in rows {
+            let body = ArticleBody::from_html(&html, &url);
+            queries::set_article_body(pool, id, &body).await?;
+            converted += 1;
+        }
+    }
+
+    if converted > 0 {
+        tracing::info!("Backfilled Markdown for {} articles", converted);
+    }
+
+    Ok(())
+}
+
+/// Extract outbound links for articles stored before link tracking existed
+async fn backfill_links(pool: &PgPool) -> Result<()> {
+    const NAME: &str = "article_links";
+
+    if queries::backfill_completed(pool, NAME).await? {
+        return Ok(());
+    }
+
+    let mut after = Uuid::nil();
+    let mut processed = 0;
+
+    loop {
+        let rows = queries::get_article_html_after(pool, after, BATCH_SIZE).await?;
+        if rows.is_empty() {
+            break;
+        }
+
+        for (id, url, html) in rows {
+            queries::replace_article_links(pool, id, &extract_links(&html, &url)).await?;
+            after = id;
+            processed += 1;
+        }
+    }
+
+    queries::mark_backfill_completed(pool, NAME).await?;
+    tracing::info!("Extracted links for {} articles", processed);
+
+    Ok(())
+}
+
+/// Fingerprint articles stored before near-duplicate detection existed
+async fn backfill_simhash(pool: &PgPool) -> Result<()> {
+    let mut fingerprinted = 0;
+
+    loop {
+        let rows = queries::get_articles_missing_simhash(pool, BATCH_SIZE).await?;
+        if rows.is_empty() {
+            break;
+        }
+
+        for (id, title, content) in rows {
+            queries::set_article_simhash(pool, id, fingerprint::simhash(&title, &content)).await?;
+            fingerprinted += 1;
+        }
+    }
+
+    if fingerprinted > 0 {
+        tracing::info!("Computed SimHash for {} articles", fingerprinted);
+    }
+
+    Ok(())
+}
+
+/// Regenerate excerpts cut by byte count, which could split characters or
+/// hold raw HTML. The page's meta description isn't stored, so these come
+/// from the content.
+async fn backfill_excerpts(pool: &PgPool) -> Result<()> {
+    const NAME: &str = "article_excerpts";
+
+    if queries::backfill_completed(pool, NAME).await? {
+        return Ok(());
+    }
+
+    let mut after = Uuid::nil();
+    let mut processed = 0;
+
+    loop {
+        let rows = queries::get_article_content_after(pool, after, BATCH_SIZE).await?;
+        if rows.is_empty() {
+            break;
+        }
+
+        for (id, content) in rows {
+            let excerpt = excerpt::from_text(&content);
+            queries::set_article_excerpt(pool, id, excerpt.as_deref()).await?;
+            after = id;
+            processed += 1;
+        }
+    }
+
+    queries::mark_backfill_completed(pool, NAME).await?;
+    tracing::info!("Regenerated excerpts for {} articles", processed);
+
+    Ok(())
+}
+
+/// Detect the language of articles stored before detection existed, and
+/// redo their reading time for it. Storing the language also rebuilds the
+/// search vector with that language's text-search configuration.
+async fn backfill_language(pool: &PgPool) -> Result<()> {
+    let mut detected = 0;
+
+    loop {
+        let rows = queries::get_articles_missing_language(pool, BATCH_SIZE).await?;
+        if rows.is_empty() {
+            break;
+        }
+
+        let detections: Vec<(Uuid, &str, i32)> = rows
+            .into_iter()
+            .map(|(id, title, content)| {
+                let language = text::language(&format!("{}\n{}", title, content));
+                (id, language, text::reading_minutes(&content, language))
+            })
+            .collect();
+        queries::set_articles_language(pool, &detections).await?;
+        detected += detections.len();
+    }
+
+    if detected > 0 {
+        tracing::info!("Detected the language of {} articles", detected);
+    }
+
+    Ok(())
+}
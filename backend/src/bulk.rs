@@ -0,0 +1,436 @@
+//! Bulk article operations, run as background jobs that report progress
+//! over the WebSocket
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::fs::File;
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::api::handlers::scraper::store_article;
+use crate::db::models::{Article, ArticleEdit, ArticleTag};
+use crate::db::queries;
+use crate::scraper::sources::{aibase::AIBaseScraper, smolai::SmolAIScraper};
+use crate::scraper::Source;
+use crate::AppState;
+
+/// Finished jobs kept for status and export downloads
+const MAX_FINISHED_JOBS: usize = 50;
+/// Errors kept per job; the rest are only counted
+const MAX_ERRORS: usize = 20;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum BulkAction {
+    /// Move to the trash
+    Delete {
+        reason: Option<String>,
+    },
+    /// Add manual tags, by name
+    Tag {
+        tags: Vec<String>,
+    },
+    /// Remove tags, by name; tagging rules won't add them back
+    Untag {
+        tags: Vec<String>,
+    },
+    /// Scrape again from the source
+    Refresh,
+    /// Collect as JSON for download
+    Export,
+    MarkRead,
+    MarkUnread,
+}
+
+impl BulkAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Delete { .. } => "delete",
+            Self::Tag { .. } => "tag",
+            Self::Untag { .. } => "untag",
+            Self::Refresh => "refresh",
+            Self::Export => "export",
+            Self::MarkRead => "mark_read",
+            Self::MarkUnread => "mark_unread",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// A bulk job's state, also sent over the WebSocket as it changes
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkJob {
+    pub job_id: Uuid,
+    pub action: &'static str,
+    pub status: JobStatus,
+    pub total: usize,
+    pub processed: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// The first failures, as "<article id>: <error>"
+    pub errors: Vec<String>,
+    /// Whether `/api/bulk/:id/export` has the exported articles
+    pub has_export: bool,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportedArticle {
+    #[serde(flatten)]
+    pub article: Article,
+    pub tags: Vec<ArticleTag>,
+}
+
+/// Running and recently finished bulk jobs
+pub struct BulkJobs {
+    jobs: Mutex<HashMap<Uuid, BulkJob>>,
+    /// Jobs whose export file is complete
+    exports: Mutex<HashSet<Uuid>>,
+    cancelled: Mutex<HashSet<Uuid>>,
+    progress_tx: broadcast::Sender<BulkJob>,
+}
+
+impl Default for BulkJobs {
+    fn default() -> Self {
+        Self {
+            jobs: Mutex::default(),
+            exports: Mutex::default(),
+            cancelled: Mutex::default(),
+            progress_tx: broadcast::channel(100).0,
+        }
+    }
+}
+
+impl BulkJobs {
+    pub fn subscribe(&self) -> broadcast::Receiver<BulkJob> {
+        self.progress_tx.subscribe()
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<BulkJob> {
+        self.jobs.lock().unwrap().get(&id).cloned()
+    }
+
+    /// Jobs, newest first
+    pub fn list(&self) -> Vec<BulkJob> {
+        let mut jobs: Vec<BulkJob> = self.jobs.lock().unwrap().values().cloned().collect();
+        jobs.sort_by_key(|job| std::cmp::Reverse(job.started_at));
+        jobs
+    }
+
+    /// Path of a finished export job's file
+    pub fn export(&self, id: Uuid) -> Option<PathBuf> {
+        self.exports
+            .lock()
+            .unwrap()
+            .contains(&id)
+            .then(|| export_path(id))
+    }
+
+    /// Ask a running job to stop after the current article
+    pub fn cancel(&self, id: Uuid) -> bool {
+        let running = self
+            .get(id)
+            .is_some_and(|job| job.status == JobStatus::Running);
+        if running {
+            self.cancelled.lock().unwrap().insert(id);
+        }
+        running
+    }
+
+    /// Register a job over `total` articles and announce it
+    pub fn start(&self, action: &BulkAction, total: usize) -> BulkJob {
+        let job = BulkJob {
+            job_id: Uuid::new_v4(),
+            action: action.name(),
+            status: JobStatus::Running,
+            total,
+            processed: 0,
+            succeeded: 0,
+            failed: 0,
+            errors: Vec::new(),
+            has_export: false,
+            started_at: Utc::now(),
+            finished_at: None,
+        };
+
+        let mut jobs = self.jobs.lock().unwrap();
+        let mut finished: Vec<(DateTime<Utc>, Uuid)> = jobs
+            .values()
+            .filter(|job| job.status != JobStatus::Running)
+            .map(|job| (job.started_at, job.job_id))
+            .collect();
+        if finished.len() >= MAX_FINISHED_JOBS {
+            finished.sort();
+            let mut exports = self.exports.lock().unwrap();
+            for (_, id) in &finished[..=finished.len() - MAX_FINISHED_JOBS] {
+                jobs.remove(id);
+                if exports.remove(id) {
+                    let _ = std::fs::remove_file(export_path(*id));
+                }
+            }
+        }
+        jobs.insert(job.job_id, job.clone());
+        drop(jobs);
+
+        let _ = self.progress_tx.send(job.clone());
+        job
+    }
+
+    fn update(&self, job: &BulkJob) {
+        self.jobs.lock().unwrap().insert(job.job_id, job.clone());
+        let _ = self.progress_tx.send(job.clone());
+    }
+
+    fn is_cancelled(&self, id: Uuid) -> bool {
+        self.cancelled.lock().unwrap().contains(&id)
+    }
+
+    fn finish(&self, job: &mut BulkJob, status: JobStatus) {
+        job.status = status;
+        job.finished_at = Some(Utc::now());
+        self.cancelled.lock().unwrap().remove(&job.job_id);
+        self.update(job);
+    }
+}
+
+/// Tags resolved to ids before the job starts
+pub enum ResolvedAction {
+    Delete(Option<String>),
+    Tag(Vec<i32>),
+    Untag(Vec<i32>),
+    Refresh,
+    Export,
+    MarkRead(bool),
+}
+
+/// Export files live here, one per job, and are removed with the job
+fn export_dir() -> PathBuf {
+    std::env::temp_dir().join("aibase-exports")
+}
+
+fn export_path(job_id: Uuid) -> PathBuf {
+    export_dir().join(format!("{}.json", job_id))
+}
+
+/// Remove export files left behind by a previous run, whose jobs are gone
+pub async fn clear_exports() {
+    match tokio::fs::remove_dir_all(export_dir()).await {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => tracing::warn!("Failed to clear old exports: {}", e),
+    }
+}
+
+/// A JSON array of exported articles, written to disk as the job goes
+/// so a large export is never held in memory
+struct ExportWriter {
+    file: BufWriter<File>,
+    empty: bool,
+}
+
+impl ExportWriter {
+    async fn create(job_id: Uuid) -> Result<Self> {
+        tokio::fs::create_dir_all(export_dir()).await?;
+        let mut file = BufWriter::new(File::create(export_path(job_id)).await?);
+        file.write_all(b"[").await?;
+        Ok(Self { file, empty: true })
+    }
+
+    async fn push(&mut self, article: &ExportedArticle) -> Result<()> {
+        let json = serde_json::to_vec(article)?;
+        if !self.empty {
+            self.file.write_all(b",").await?;
+        }
+        self.file.write_all(&json).await?;
+        self.empty = false;
+        Ok(())
+    }
+
+    async fn finish(mut self) -> Result<()> {
+        self.file.write_all(b"]").await?;
+        self.file.flush().await?;
+        Ok(())
+    }
+}
+
+/// Apply `action` to each of `ids`, one article at a time
+pub async fn run(state: Arc<AppState>, mut job: BulkJob, action: ResolvedAction, ids: Vec<Uuid>) {
+    let jobs = &state.bulk;
+    let mut scrapers = Scrapers::default();
+    let mut last_update = Instant::now();
+
+    let mut exported = None;
+    if matches!(action, ResolvedAction::Export) {
+        match ExportWriter::create(job.job_id).await {
+            Ok(writer) => exported = Some(writer),
+            Err(e) => {
+                fail_export(jobs, &mut job, e).await;
+                return;
+            }
+        }
+    }
+
+    for id in ids {
+        if jobs.is_cancelled(job.job_id) {
+            if exported.is_some() {
+                let _ = tokio::fs::remove_file(export_path(job.job_id)).await;
+            }
+            jobs.finish(&mut job, JobStatus::Cancelled);
+            return;
+        }
+
+        let result = match &action {
+            ResolvedAction::Delete(reason) => {
+                let result = found(queries::trash_article(&state.pool, id, reason.as_deref()).await);
+                if result.is_ok() {
+                    state.semantic.remove(&[id]);
+                }
+                result
+            }
+            ResolvedAction::Tag(tag_ids) => found(
+                queries::edit_article(&state.pool, id, &ArticleEdit::default(), tag_ids, &[]).await,
+            ),
+            ResolvedAction::Untag(tag_ids) => found(
+                queries::edit_article(&state.pool, id, &ArticleEdit::default(), &[], tag_ids).await,
+            ),
+            ResolvedAction::Refresh => refresh(&state, &mut scrapers, id).await,
+            ResolvedAction::Export => match export(&state, id).await {
+                Ok(article) => {
+                    let writer = exported.as_mut().unwrap();
+                    if let Err(e) = writer.push(&article).await {
+                        fail_export(jobs, &mut job, e).await;
+                        return;
+                    }
+                    Ok(())
+                }
+                Err(e) => Err(e),
+            },
+            ResolvedAction::MarkRead(read) => {
+                found(queries::set_article_read(&state.pool, id, *read).await)
+            }
+        };
+
+        job.processed += 1;
+        match result {
+            Ok(()) => job.succeeded += 1,
+            Err(e) => {
+                job.failed += 1;
+                if job.errors.len() < MAX_ERRORS {
+                    job.errors.push(format!("{}: {}", id, e));
+                }
+            }
+        }
+
+        if last_update.elapsed() >= PROGRESS_INTERVAL {
+            jobs.update(&job);
+            last_update = Instant::now();
+        }
+    }
+
+    if let Some(writer) = exported {
+        match writer.finish().await {
+            Ok(()) => {
+                jobs.exports.lock().unwrap().insert(job.job_id);
+                job.has_export = true;
+            }
+            Err(e) => {
+                fail_export(jobs, &mut job, e).await;
+                return;
+            }
+        }
+    }
+
+    tracing::info!(
+        "Bulk {} finished: {} succeeded, {} failed",
+        job.action,
+        job.succeeded,
+        job.failed
+    );
+    jobs.finish(&mut job, JobStatus::Completed);
+}
+
+async fn fail_export(jobs: &BulkJobs, job: &mut BulkJob, e: anyhow::Error) {
+    tracing::error!("Bulk export {} failed: {}", job.job_id, e);
+    let _ = tokio::fs::remove_file(export_path(job.job_id)).await;
+    jobs.finish(job, JobStatus::Failed);
+}
+
+/// Treat an update that matched no article as a failure
+fn found(result: Result<bool>) -> Result<()> {
+    match result? {
+        true => Ok(()),
+        false => Err(anyhow!("Article not found")),
+    }
+}
+
+async fn export(state: &AppState, id: Uuid) -> Result<ExportedArticle> {
+    let article = queries::get_article_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| anyhow!("Article not found"))?;
+    let tags = queries::get_article_tags(&state.pool, id).await?;
+    Ok(ExportedArticle { article, tags })
+}
+
+/// Scrapers for refreshing, created on first use
+#[derive(Default)]
+struct Scrapers {
+    aibase: Option<AIBaseScraper>,
+    smolai: Option<SmolAIScraper>,
+}
+
+async fn refresh(state: &AppState, scrapers: &mut Scrapers, id: Uuid) -> Result<()> {
+    let article = queries::get_article_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| anyhow!("Article not found"))?;
+    let source_name = article.source.as_deref().unwrap_or("AIBase");
+    let source =
+        Source::from_str(source_name).ok_or_else(|| anyhow!("Unknown source {}", source_name))?;
+
+    let (rate_limit, max_retries) = (
+        state.config.scraper_rate_limit,
+        state.config.scraper_max_retries,
+    );
+    let scraped = match source {
+        Source::AIBase => {
+            if scrapers.aibase.is_none() {
+                scrapers.aibase = Some(AIBaseScraper::new(rate_limit, max_retries)?);
+            }
+            let scraper = scrapers.aibase.as_ref().unwrap();
+            scraper.scrape_article(&article.external_id).await?
+        }
+        Source::SmolAI => {
+            if scrapers.smolai.is_none() {
+                scrapers.smolai = Some(SmolAIScraper::new(rate_limit, max_retries)?);
+            }
+            let scraper = scrapers.smolai.as_ref().unwrap();
+            scraper.scrape_article(&article.external_id).await?
+        }
+    };
+
+    store_article(
+        &state.pool,
+        &state.assets,
+        &state.tagger,
+        source.display_name(),
+        &article.external_id,
+        scraped,
+        true,
+    )
+    .await
+}
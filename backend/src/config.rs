@@ -7,6 +7,15 @@ pub struct Config {
     pub server_port: u16,
     pub scraper_rate_limit: u32,
     pub scraper_max_retries: u32,
+    pub mirror_assets: bool,
+    pub asset_dir: String,
+    pub asset_max_bytes: usize,
+    pub slow_query_ms: u64,
+    pub embedding_url: Option<String>,
+    pub embedding_model: String,
+    pub embedding_api_key: Option<String>,
+    /// Days trashed articles are kept before being purged; 0 keeps them
+    pub trash_retention_days: i64,
 }
 
 impl Config {
@@ -27,6 +36,27 @@ impl Config {
                 .unwrap_or_else(|_| "3".to_string())
                 .parse()
                 .context("SCRAPER_MAX_RETRIES must be a number")?,
+            mirror_assets: std::env::var("MIRROR_ASSETS")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()
+                .context("MIRROR_ASSETS must be true or false")?,
+            asset_dir: std::env::var("ASSET_DIR").unwrap_or_else(|_| "data/assets".to_string()),
+            asset_max_bytes: std::env::var("ASSET_MAX_BYTES")
+                .unwrap_or_else(|_| "5242880".to_string())
+                .parse()
+                .context("ASSET_MAX_BYTES must be a number")?,
+            slow_query_ms: std::env::var("SLOW_QUERY_MS")
+                .unwrap_or_else(|_| "500".to_string())
+                .parse()
+                .context("SLOW_QUERY_MS must be a number")?,
+            embedding_url: std::env::var("EMBEDDING_URL").ok().filter(|v| !v.is_empty()),
+            embedding_model: std::env::var("EMBEDDING_MODEL")
+                .unwrap_or_else(|_| "nomic-embed-text".to_string()),
+            embedding_api_key: std::env::var("EMBEDDING_API_KEY").ok().filter(|v| !v.is_empty()),
+            trash_retention_days: std::env::var("TRASH_RETENTION_DAYS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .context("TRASH_RETENTION_DAYS must be a number")?,
         })
     }
 }
@@ -0,0 +1,576 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use super::models::RankedArticle;
+use super::pagination::Cursor;
+use super::search::SearchQuery;
+
+/// Whether an article must carry any or all of the requested tags
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagMode {
+    #[default]
+    Any,
+    All,
+}
+
+/// Listing order. `Relevance` ranks full-text matches and only applies to
+/// searches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArticleSort {
+    Relevance,
+    Published,
+    Scraped,
+    Views,
+    Title,
+}
+
+impl ArticleSort {
+    pub fn default_order(self) -> SortOrder {
+        match self {
+            ArticleSort::Title => SortOrder::Asc,
+            _ => SortOrder::Desc,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    fn sql(self) -> &'static str {
+        match self {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        }
+    }
+
+    /// Comparison selecting the rows that come after a key in this order
+    fn after(self) -> &'static str {
+        match self {
+            SortOrder::Asc => " > ",
+            SortOrder::Desc => " < ",
+        }
+    }
+}
+
+/// Column of an article ordering; every ordering ends with `a.id`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SortColumn {
+    Rank,
+    Published,
+    Scraped,
+    Views,
+    Title,
+}
+
+/// Sort key value of one row, matching its `SortColumn`
+#[derive(Debug, Clone)]
+enum SortValue {
+    Real(f32),
+    Time(Option<DateTime<Utc>>),
+    Int(Option<i64>),
+    Text(String),
+}
+
+/// Validated cursor position: the sort key values and id of the last row seen
+#[derive(Debug, Clone)]
+pub struct Keyset {
+    values: Vec<SortValue>,
+    id: Uuid,
+}
+
+/// Markers and fragment count for highlighted search snippets
+#[derive(Debug, Clone)]
+pub struct HighlightOptions {
+    pub start_sel: String,
+    pub stop_sel: String,
+    pub max_fragments: i32,
+}
+
+/// Article listing filter. Every field is optional and all set fields must
+/// match; trashed articles never do. Queries using it select from
+/// `articles a`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ArticleFilter {
+    pub search: Option<SearchQuery>,
+    /// Also match titles and tags similar to the search text
+    pub fuzzy: bool,
+    pub tags: Vec<String>,
+    pub tag_mode: TagMode,
+    /// Tags that must all be present, from `tag:` search qualifiers
+    pub required_tags: Vec<String>,
+    pub excluded_tags: Vec<String>,
+    /// Stored source names, e.g. "AIBase"
+    pub sources: Vec<String>,
+    pub excluded_sources: Vec<String>,
+    /// Language codes, e.g. "zh"
+    pub languages: Vec<String>,
+    pub excluded_languages: Vec<String>,
+    pub author: Option<String>,
+    /// Authors to leave out, compared case-insensitively
+    pub excluded_authors: Vec<String>,
+    pub published_after: Option<DateTime<Utc>>,
+    pub published_before: Option<DateTime<Utc>>,
+    pub scraped_after: Option<DateTime<Utc>>,
+    pub scraped_before: Option<DateTime<Utc>>,
+    pub min_views: Option<i64>,
+    pub has_thumbnail: Option<bool>,
+    pub read: Option<bool>,
+    /// Show each story cluster once, as its earliest report
+    pub collapse_clusters: bool,
+}
+
+impl ArticleFilter {
+    /// Append the `WHERE` clause for this filter
+    pub fn push_where(&self, qb: &mut QueryBuilder<'_, Postgres>) {
+        self.push_conditions(qb, &mut Conditions::default());
+    }
+
+    /// `push_where` restricted to rows after `keyset` in the given order
+    pub fn push_where_after(
+        &self,
+        qb: &mut QueryBuilder<'_, Postgres>,
+        sort: ArticleSort,
+        order: SortOrder,
+        keyset: &Keyset,
+    ) {
+        let mut conditions = Conditions::default();
+        self.push_conditions(qb, &mut conditions);
+
+        let columns = self.sort_columns(sort, order);
+        conditions.next(qb);
+        self.push_after(qb, &columns, &keyset.values, keyset.id, columns[0].1);
+    }
+
+    fn push_conditions(&self, qb: &mut QueryBuilder<'_, Postgres>, conditions: &mut Conditions) {
+        self.push_matches(qb, conditions);
+
+        // The earliest report among the articles the rest of the filter
+        // matches, so a story isn't hidden because its earliest report is
+        // filtered out
+        if self.collapse_clusters {
+            conditions.next(qb).push(
+                "(a.cluster_id IS NULL OR NOT EXISTS (SELECT 1 FROM articles o \
+                 WHERE o.cluster_id = a.cluster_id \
+                 AND (COALESCE(o.published_at, o.scraped_at), o.id) \
+                 < (COALESCE(a.published_at, a.scraped_at), a.id) \
+                 AND EXISTS (SELECT 1 FROM articles a",
+            );
+            self.push_matches(qb, &mut Conditions::default());
+            qb.push(" AND a.id = o.id)))");
+        }
+    }
+
+    /// Conditions on the article row itself, everything but `collapse_clusters`
+    fn push_matches(&self, qb: &mut QueryBuilder<'_, Postgres>, conditions: &mut Conditions) {
+        conditions.next(qb).push("a.deleted_at IS NULL");
+
+        if let Some(search) = &self.search {
+            conditions.next(qb);
+            if self.fuzzy {
+                search.push_fuzzy_match(qb);
+            } else {
+                qb.push("a.search_vector @@ ");
+                search.push_tsquery(qb);
+            }
+        }
+
+        if !self.tags.is_empty() {
+            conditions.next(qb);
+            match self.tag_mode {
+                TagMode::Any => {
+                    qb.push(
+                        "EXISTS (SELECT 1 FROM article_tags at \
+                         WHERE at.article_id = a.id AND at.tag_id = ANY(",
+                    );
+                    push_tag_ids(qb, &self.tags);
+                    qb.push("))");
+                }
+                TagMode::All => {
+                    // Unknown names resolve to NULL, which no article matches
+                    qb.push(
+                        "(SELECT COUNT(DISTINCT at.tag_id) FROM article_tags at \
+                         WHERE at.article_id = a.id AND at.tag_id = ANY(",
+                    );
+                    push_tag_ids(qb, &self.tags);
+                    qb.push(")) = cardinality(");
+                    push_tag_ids(qb, &self.tags);
+                    qb.push(")");
+                }
+            }
+        }
+
+        for tag in &self.required_tags {
+            conditions.next(qb).push(
+                "EXISTS (SELECT 1 FROM article_tags at \
+                 WHERE at.article_id = a.id AND at.tag_id = (SELECT resolve_tag(",
+            );
+            qb.push_bind(tag.clone()).push(")))");
+        }
+
+        if !self.excluded_tags.is_empty() {
+            conditions.next(qb).push(
+                "NOT EXISTS (SELECT 1 FROM article_tags at \
+                 WHERE at.article_id = a.id AND at.tag_id = ANY(",
+            );
+            push_tag_ids(qb, &self.excluded_tags);
+            qb.push("))");
+        }
+
+        if !self.sources.is_empty() {
+            conditions.next(qb).push("a.source = ANY(");
+            qb.push_bind(self.sources.clone()).push(")");
+        }
+
+        if !self.excluded_sources.is_empty() {
+            conditions
+                .next(qb)
+                .push("(a.source IS NULL OR a.source <> ALL(");
+            qb.push_bind(self.excluded_sources.clone()).push("))");
+        }
+
+        if !self.languages.is_empty() {
+            conditions.next(qb).push("a.language = ANY(");
+            qb.push_bind(self.languages.clone()).push(")");
+        }
+
+        if !self.excluded_languages.is_empty() {
+            conditions
+                .next(qb)
+                .push("(a.language IS NULL OR a.language <> ALL(");
+            qb.push_bind(self.excluded_languages.clone()).push("))");
+        }
+
+        if let Some(author) = &self.author {
+            conditions.next(qb).push("LOWER(a.author) = LOWER(");
+            qb.push_bind(author.clone()).push(")");
+        }
+
+        if !self.excluded_authors.is_empty() {
+            let authors: Vec<String> = self.excluded_authors.iter().map(|a| a.to_lowercase()).collect();
+            conditions
+                .next(qb)
+                .push("(a.author IS NULL OR LOWER(a.author) <> ALL(");
+            qb.push_bind(authors).push("))");
+        }
+
+        if let Some(after) = self.published_after {
+            conditions
+                .next(qb)
+                .push("a.published_at >= ")
+                .push_bind(after);
+        }
+        if let Some(before) = self.published_before {
+            conditions
+                .next(qb)
+                .push("a.published_at < ")
+                .push_bind(before);
+        }
+        if let Some(after) = self.scraped_after {
+            conditions
+                .next(qb)
+                .push("a.scraped_at >= ")
+                .push_bind(after);
+        }
+        if let Some(before) = self.scraped_before {
+            conditions
+                .next(qb)
+                .push("a.scraped_at < ")
+                .push_bind(before);
+        }
+
+        if let Some(min_views) = self.min_views {
+            conditions
+                .next(qb)
+                .push("a.view_count >= ")
+                .push_bind(min_views);
+        }
+
+        match self.has_thumbnail {
+            Some(true) => {
+                conditions.next(qb).push("a.thumbnail_url IS NOT NULL");
+            }
+            Some(false) => {
+                conditions.next(qb).push("a.thumbnail_url IS NULL");
+            }
+            None => {}
+        }
+
+        match self.read {
+            Some(true) => {
+                conditions.next(qb).push("a.read_at IS NOT NULL");
+            }
+            Some(false) => {
+                conditions.next(qb).push("a.read_at IS NULL");
+            }
+            None => {}
+        }
+    }
+
+    /// Append the `rank` column: the weighted full-text score for searches
+    /// (or title similarity if higher, for fuzzy searches), NULL otherwise
+    pub fn push_rank(&self, qb: &mut QueryBuilder<'_, Postgres>) {
+        if self.search.is_some() {
+            self.push_sort_expr(qb, SortColumn::Rank);
+            qb.push(" AS rank");
+        } else {
+            qb.push("NULL::real AS rank");
+        }
+    }
+
+    /// Append `ORDER BY`, tie-broken on id so pages are stable
+    pub fn push_order_by(
+        &self,
+        qb: &mut QueryBuilder<'_, Postgres>,
+        sort: ArticleSort,
+        order: SortOrder,
+    ) {
+        let columns = self.sort_columns(sort, order);
+
+        qb.push(" ORDER BY ");
+        for (column, order) in &columns {
+            self.push_sort_expr(qb, *column);
+            qb.push(" ").push(order.sql()).push(" NULLS LAST, ");
+        }
+        qb.push("a.id ").push(columns[0].1.sql());
+    }
+
+    /// Identifies the ordering a cursor was issued for
+    fn cursor_sort(&self, sort: ArticleSort, order: SortOrder) -> String {
+        let sort = format!("{:?}:{:?}", sort, order).to_lowercase();
+        if self.fuzzy && self.search.is_some() {
+            format!("{}:fuzzy", sort)
+        } else {
+            sort
+        }
+    }
+
+    /// Cursor pointing after `row`, the last row of a page
+    pub fn cursor_after(&self, sort: ArticleSort, order: SortOrder, row: &RankedArticle) -> Cursor {
+        let article = &row.article;
+        let keys = self
+            .sort_columns(sort, order)
+            .into_iter()
+            .map(|(column, _)| match column {
+                SortColumn::Rank => serde_json::json!(row.rank.unwrap_or_default()),
+                SortColumn::Published => serde_json::json!(article.published_at),
+                SortColumn::Scraped => serde_json::json!(article.scraped_at),
+                SortColumn::Views => serde_json::json!(article.view_count),
+                SortColumn::Title => serde_json::json!(article.title),
+            })
+            .collect();
+
+        Cursor {
+            sort: self.cursor_sort(sort, order),
+            keys,
+            id: article.id,
+        }
+    }
+
+    /// Check that `cursor` was issued for this ordering and decode its keys
+    pub fn keyset(
+        &self,
+        sort: ArticleSort,
+        order: SortOrder,
+        cursor: &Cursor,
+    ) -> Result<Keyset, String> {
+        if cursor.sort != self.cursor_sort(sort, order) {
+            return Err("Cursor does not match the requested sort".to_string());
+        }
+
+        let columns = self.sort_columns(sort, order);
+        if cursor.keys.len() != columns.len() {
+            return Err("Invalid cursor".to_string());
+        }
+
+        let values = columns
+            .iter()
+            .zip(&cursor.keys)
+            .map(|((column, _), key)| {
+                let key = key.clone();
+                match column {
+                    SortColumn::Rank => serde_json::from_value(key).map(SortValue::Real),
+                    SortColumn::Published | SortColumn::Scraped => {
+                        serde_json::from_value(key).map(SortValue::Time)
+                    }
+                    SortColumn::Views => serde_json::from_value(key).map(SortValue::Int),
+                    SortColumn::Title => serde_json::from_value(key).map(SortValue::Text),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| "Invalid cursor".to_string())?;
+
+        Ok(Keyset {
+            values,
+            id: cursor.id,
+        })
+    }
+
+    fn sort_columns(&self, sort: ArticleSort, order: SortOrder) -> Vec<(SortColumn, SortOrder)> {
+        match sort {
+            // Equally relevant results show the newest first
+            ArticleSort::Relevance if self.search.is_some() => vec![
+                (SortColumn::Rank, order),
+                (SortColumn::Published, SortOrder::Desc),
+            ],
+            ArticleSort::Relevance | ArticleSort::Published => vec![(SortColumn::Published, order)],
+            ArticleSort::Scraped => vec![(SortColumn::Scraped, order)],
+            ArticleSort::Views => vec![(SortColumn::Views, order)],
+            ArticleSort::Title => vec![(SortColumn::Title, order)],
+        }
+    }
+
+    fn push_sort_expr(&self, qb: &mut QueryBuilder<'_, Postgres>, column: SortColumn) {
+        match column {
+            SortColumn::Rank => match &self.search {
+                Some(search) if self.fuzzy => {
+                    qb.push("GREATEST(ts_rank(a.search_vector, ");
+                    search.push_tsquery(qb);
+                    qb.push("), ");
+                    search.push_similarity(qb);
+                    qb.push(")");
+                }
+                Some(search) => {
+                    qb.push("ts_rank(a.search_vector, ");
+                    search.push_tsquery(qb);
+                    qb.push(")");
+                }
+                None => {
+                    qb.push("NULL::real");
+                }
+            },
+            SortColumn::Published => {
+                qb.push("a.published_at");
+            }
+            SortColumn::Scraped => {
+                qb.push("a.scraped_at");
+            }
+            SortColumn::Views => {
+                qb.push("a.view_count");
+            }
+            SortColumn::Title => {
+                qb.push("a.title");
+            }
+        }
+    }
+
+    /// Rows after the given key values, with NULLs sorting last:
+    /// `(k > v OR (k = v AND <rest after>) OR k IS NULL)` per key, or
+    /// `(k IS NULL AND <rest after>)` when the key value itself is NULL
+    fn push_after(
+        &self,
+        qb: &mut QueryBuilder<'_, Postgres>,
+        columns: &[(SortColumn, SortOrder)],
+        values: &[SortValue],
+        id: Uuid,
+        id_order: SortOrder,
+    ) {
+        let (Some(((column, order), columns)), Some((value, values))) =
+            (columns.split_first(), values.split_first())
+        else {
+            qb.push("a.id").push(id_order.after()).push_bind(id);
+            return;
+        };
+
+        let is_null = matches!(value, SortValue::Time(None) | SortValue::Int(None));
+        qb.push("(");
+        if is_null {
+            self.push_sort_expr(qb, *column);
+            qb.push(" IS NULL AND ");
+            self.push_after(qb, columns, values, id, id_order);
+        } else {
+            self.push_sort_expr(qb, *column);
+            qb.push(order.after());
+            push_value(qb, value);
+            qb.push(" OR (");
+            self.push_sort_expr(qb, *column);
+            qb.push(" = ");
+            push_value(qb, value);
+            qb.push(" AND ");
+            self.push_after(qb, columns, values, id, id_order);
+            qb.push(") OR ");
+            self.push_sort_expr(qb, *column);
+            qb.push(" IS NULL");
+        }
+        qb.push(")");
+    }
+}
+
+fn push_value(qb: &mut QueryBuilder<'_, Postgres>, value: &SortValue) {
+    match value {
+        SortValue::Real(v) => qb.push_bind(*v),
+        SortValue::Time(v) => qb.push_bind(*v),
+        SortValue::Int(v) => qb.push_bind(*v),
+        SortValue::Text(v) => qb.push_bind(v.clone()),
+    };
+}
+
+/// Emits `WHERE` before the first condition and `AND` before the rest
+#[derive(Default)]
+struct Conditions {
+    started: bool,
+}
+
+impl Conditions {
+    fn next<'q, 'args>(
+        &mut self,
+        qb: &'q mut QueryBuilder<'args, Postgres>,
+    ) -> &'q mut QueryBuilder<'args, Postgres> {
+        let keyword = if self.started { " AND " } else { " WHERE " };
+        self.started = true;
+        qb.push(keyword)
+    }
+}
+
+/// Distinct ids of tags named (or aliased) by `names`, resolved once per query
+fn push_tag_ids(qb: &mut QueryBuilder<'_, Postgres>, names: &[String]) {
+    qb.push("ARRAY(SELECT DISTINCT resolve_tag(n) FROM unnest(");
+    qb.push_bind(names.to_vec());
+    qb.push("::text[]) n)");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collapse_compares_reports_matching_the_filter() {
+        let filter = ArticleFilter {
+            sources: vec!["smol.ai".to_string()],
+            collapse_clusters: true,
+            ..Default::default()
+        };
+        let mut qb = QueryBuilder::new("SELECT a.id FROM articles a");
+        filter.push_where(&mut qb);
+        let sql = qb.sql();
+
+        // An earlier AIBase report in the cluster doesn't hide a smol.ai one
+        let (outer, collapse) = sql.split_at(sql.find("NOT EXISTS").unwrap());
+        assert!(outer.contains("a.source = ANY($1)"));
+        assert!(collapse.contains("a.source = ANY($2)"));
+        assert!(collapse.contains("a.deleted_at IS NULL"));
+        assert!(collapse.ends_with("AND a.id = o.id)))"));
+    }
+
+    #[test]
+    fn no_collapse_without_flag() {
+        let filter = ArticleFilter {
+            sources: vec!["smol.ai".to_string()],
+            ..Default::default()
+        };
+        let mut qb = QueryBuilder::new("SELECT a.id FROM articles a");
+        filter.push_where(&mut qb);
+        assert_eq!(
+            qb.sql(),
+            "SELECT a.id FROM articles a WHERE a.deleted_at IS NULL AND a.source = ANY($1)"
+        );
+    }
+}
@@ -1,3 +1,6 @@
+pub mod filter;
 pub mod models;
+pub mod pagination;
 pub mod pool;
 pub mod queries;
+pub mod search;
@@ -1,5 +1,5 @@
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
@@ -20,6 +20,119 @@ pub struct Article {
     pub scraped_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub content_hash: Option<String>,
+    pub content_html: Option<String>,
+    pub content_markdown: Option<String>,
+    pub content_format: ContentFormat,
+    /// Story cluster shared with other reports of the same story
+    pub cluster_id: Option<Uuid>,
+    /// Fields edited by hand, which re-scraping leaves alone
+    pub manual_fields: Vec<String>,
+    pub edited_at: Option<DateTime<Utc>>,
+    /// Set while the article is in the trash
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub read_at: Option<DateTime<Utc>>,
+    /// `en`, `zh`, `ja` or `ko`, detected when scraped
+    pub language: Option<String>,
+}
+
+/// Manual changes to an article. Omitted fields are left as they are;
+/// `excerpt`, `author` and `published_at` can be cleared with `null`.
+#[derive(Debug, Default, Deserialize)]
+pub struct ArticleEdit {
+    pub title: Option<String>,
+    #[serde(default, deserialize_with = "present")]
+    pub excerpt: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub author: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub published_at: Option<Option<DateTime<Utc>>>,
+    #[serde(default)]
+    pub add_tags: Vec<String>,
+    /// Removed tags are not added back by tagging rules
+    #[serde(default)]
+    pub remove_tags: Vec<String>,
+    /// Fields to hand back to the scraper: the next re-scrape overwrites them
+    #[serde(default)]
+    pub revert: Vec<EditableField>,
+}
+
+impl ArticleEdit {
+    /// Fields this edit sets
+    pub fn fields(&self) -> Vec<EditableField> {
+        [
+            (self.title.is_some(), EditableField::Title),
+            (self.excerpt.is_some(), EditableField::Excerpt),
+            (self.author.is_some(), EditableField::Author),
+            (self.published_at.is_some(), EditableField::PublishedAt),
+        ]
+        .into_iter()
+        .filter_map(|(set, field)| set.then_some(field))
+        .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EditableField {
+    Title,
+    Excerpt,
+    Author,
+    PublishedAt,
+}
+
+impl EditableField {
+    /// Name stored in `articles.manual_fields`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Title => "title",
+            Self::Excerpt => "excerpt",
+            Self::Author => "author",
+            Self::PublishedAt => "published_at",
+        }
+    }
+}
+
+/// Deserialize a field so that `null` (`Some(None)`) is distinguished from
+/// a missing field (`None`)
+pub fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TrashedArticle {
+    pub id: Uuid,
+    pub external_id: String,
+    pub source: Option<String>,
+    pub url: String,
+    pub title: String,
+    pub deleted_at: DateTime<Utc>,
+    /// Why it was deleted, from its tombstone
+    pub reason: Option<String>,
+}
+
+/// An article moved out of `articles` by a retention rule
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ArchivedArticle {
+    pub id: Uuid,
+    pub source: String,
+    pub external_id: String,
+    pub title: String,
+    pub published_at: Option<DateTime<Utc>>,
+    pub archived_at: DateTime<Utc>,
+}
+
+/// A (source, external id) pair that scrapes skip
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Tombstone {
+    pub source: String,
+    pub external_id: String,
+    pub title: Option<String>,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +146,64 @@ pub struct ArticlePreview {
     pub view_count: Option<i64>,
     pub tags: Vec<String>,
     pub source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cluster_id: Option<Uuid>,
+    /// Full-text relevance score, only set for searches
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rank: Option<f32>,
+    /// Content fragments around the search matches, with matches marked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+    /// Title with matches marked, only set when the title matched
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title_highlight: Option<String>,
+}
+
+/// Article row with the listing's aggregated `tags` and `rank` columns
+#[derive(Debug, Clone, FromRow)]
+pub struct RankedArticle {
+    #[sqlx(flatten)]
+    pub article: Article,
+    pub tags: Vec<String>,
+    pub rank: Option<f32>,
+}
+
+/// An article sharing tags with, or textually close to, another article
+#[derive(Debug, Clone, FromRow)]
+pub struct RelatedCandidate {
+    pub id: Uuid,
+    pub shared_tags: i64,
+    /// Distinct tags of both articles together
+    pub tag_union: i64,
+    /// Days between the two publication (or scrape) dates
+    pub days_apart: Option<f64>,
+}
+
+/// A story cluster with its members, earliest report first
+#[derive(Debug, Clone, Serialize)]
+pub struct StoryCluster {
+    pub id: Uuid,
+    pub article_count: i64,
+    pub sources: Vec<String>,
+    pub first_seen: Option<DateTime<Utc>>,
+    pub last_seen: Option<DateTime<Utc>>,
+    pub articles: Vec<ArticlePreview>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct StoryClusterRow {
+    pub id: Uuid,
+    pub article_count: i64,
+    pub sources: Vec<String>,
+    pub first_seen: Option<DateTime<Utc>>,
+    pub last_seen: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct SearchHighlight {
+    pub id: Uuid,
+    pub snippet: Option<String>,
+    pub title: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,15 +218,166 @@ pub struct NewArticle {
     pub published_at: Option<DateTime<Utc>>,
     pub view_count: Option<i64>,
     pub read_time_minutes: Option<i32>,
+    /// Detected language code, see `text::language`
+    pub language: String,
     pub thumbnail_url: Option<String>,
     pub content_hash: String,
-    pub tags: Vec<String>,
+    /// Near-duplicate fingerprint, see `scraper::fingerprint`
+    pub simhash: i64,
+    pub content_html: Option<String>,
+    pub content_markdown: Option<String>,
+    pub content_format: ContentFormat,
+    pub tags: Vec<NewTag>,
+    pub links: Vec<NewLink>,
+}
+
+/// Format the article body was captured in. `Html` bodies also carry
+/// `content_html` and a normalized `content_markdown`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "content_format", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ContentFormat {
+    Text,
+    Html,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Tag {
     pub id: i32,
     pub name: String,
+    /// Normalized name: trimmed, single-spaced and lowercased
+    pub slug: String,
+    pub category: Option<TagCategory>,
+    pub article_count: i64,
+    /// Other names that resolve to this tag, as slugs
+    pub aliases: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "tag_category", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum TagCategory {
+    Company,
+    Topic,
+    Model,
+    Person,
+}
+
+/// A tag as scraped, resolved to a normalized tag when stored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewTag {
+    pub name: String,
+    pub category: Option<TagCategory>,
+}
+
+impl NewTag {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            category: None,
+        }
+    }
+}
+
+/// Where an article's tag came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "tag_source", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum TagSource {
+    Scraped,
+    Rule,
+    Manual,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ArticleTag {
+    pub tag_id: i32,
+    pub name: String,
+    pub source: TagSource,
+    /// Rule that assigned the tag, for `rule` tags
+    pub rule_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "tag_rule_kind", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum TagRuleKind {
+    /// Whole words, e.g. `GPT-4o`; CJK keywords match anywhere
+    Keyword,
+    /// Words in order, separated by any whitespace or hyphens
+    Phrase,
+    Regex,
+}
+
+/// An auto-tagging rule as created or edited
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct NewTagRule {
+    /// Tag name, created if it doesn't exist
+    pub tag: String,
+    pub kind: TagRuleKind,
+    pub patterns: Vec<String>,
+    /// Only articles from this source
+    pub source: Option<String>,
+    /// Only articles in this language (`en`, `zh`, `ja`, `ko`)
+    pub language: Option<String>,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TagRule {
+    pub id: i32,
+    pub tag_id: i32,
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub rule: NewTagRule,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "entity_kind", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum EntityKind {
+    Company,
+    Model,
+    Product,
+    Person,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Entity {
+    pub id: i32,
+    pub slug: String,
+    pub name: String,
+    pub kind: EntityKind,
+    pub aliases: Vec<String>,
+    /// Articles mentioning the entity, within the requested period if any
+    pub article_count: i64,
+    /// Publication date of the latest such article
+    pub last_seen: Option<DateTime<Utc>>,
+}
+
+/// An article mentioning an entity
+#[derive(Debug, Clone, Serialize)]
+pub struct EntityMention {
+    #[serde(flatten)]
+    pub article: ArticlePreview,
+    pub mentions: i32,
+    pub in_title: bool,
+}
+
+/// Number of articles in the period starting at `date`
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TimelinePoint {
+    pub date: DateTime<Utc>,
+    pub count: i64,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type)]
@@ -105,12 +427,115 @@ pub struct Stats {
     pub total_articles: i64,
     pub articles_today: i64,
     pub articles_this_week: i64,
+    /// Articles in the trash, not counted in the totals above
+    pub trashed_articles: i64,
+    /// Articles moved to the archive by retention rules
+    pub archived_articles: i64,
     pub last_scrape: Option<DateTime<Utc>>,
     pub total_scrape_runs: i64,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: i64,
+}
+
+/// Article counts for the current filter, grouped by field
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArticleFacets {
+    pub sources: Vec<FacetCount>,
+    pub tags: Vec<FacetCount>,
+    pub authors: Vec<FacetCount>,
+    /// `YYYY-MM` of `published_at`, newest first
+    pub months: Vec<FacetCount>,
+    pub languages: Vec<FacetCount>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TitleSuggestion {
+    pub id: Uuid,
+    pub title: String,
+    pub view_count: Option<i64>,
+}
+
+/// Completions for a partially typed search, most popular first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Suggestions {
+    pub titles: Vec<TitleSuggestion>,
+    pub tags: Vec<FacetCount>,
+    pub authors: Vec<FacetCount>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TagStat {
     pub name: String,
     pub count: i64,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "asset_kind", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum AssetKind {
+    Thumbnail,
+    Inline,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ArticleAsset {
+    pub id: i32,
+    pub article_id: Uuid,
+    pub kind: AssetKind,
+    pub original_url: String,
+    pub hash: String,
+    pub content_type: String,
+    pub byte_size: i32,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Image downloaded during a scrape, recorded once the article row exists
+#[derive(Debug, Clone)]
+pub struct NewAsset {
+    pub kind: AssetKind,
+    pub original_url: String,
+    pub hash: String,
+    pub content_type: String,
+    pub byte_size: i32,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewLink {
+    pub url: String,
+    pub domain: String,
+    pub anchor_text: Option<String>,
+    pub position: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DomainStat {
+    pub domain: String,
+    pub link_count: i64,
+    pub article_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct UrlStat {
+    pub url: String,
+    pub domain: String,
+    pub link_count: i64,
+    pub article_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct LinkingArticle {
+    pub article_id: Uuid,
+    pub title: String,
+    pub source: String,
+    pub published_at: Option<DateTime<Utc>>,
+    pub url: String,
+    pub anchor_text: Option<String>,
+}
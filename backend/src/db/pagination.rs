@@ -0,0 +1,45 @@
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+/// Position after the last row of a page: its sort key values and id. Sent
+/// to clients as an opaque base64 string.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cursor {
+    /// Sort the cursor was issued for; it is only valid for the same sort
+    pub sort: String,
+    pub keys: Vec<serde_json::Value>,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(cursor)
+            .context("Invalid cursor encoding")?;
+        serde_json::from_slice(&bytes).context("Invalid cursor")
+    }
+}
+
+/// Planner row estimate for a query. Cheap, but only as accurate as the
+/// table statistics.
+pub async fn estimate_rows(pool: &PgPool, mut query: QueryBuilder<'_, Postgres>) -> Result<i64> {
+    let plan: Vec<(String,)> = query.build_query_as().fetch_all(pool).await?;
+
+    // "Seq Scan on articles a  (cost=0.00..1.09 rows=7 width=4)"
+    let rows = plan
+        .first()
+        .and_then(|(line,)| line.split("rows=").nth(1))
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|n| n.parse::<i64>().ok())
+        .context("Unexpected EXPLAIN output")?;
+
+    Ok(rows)
+}
@@ -1,11 +1,32 @@
+use std::time::Duration;
+
 use anyhow::Result;
-use sqlx::postgres::PgPoolOptions;
-use sqlx::PgPool;
+use log::LevelFilter;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{ConnectOptions, PgPool};
+
+use super::search::FUZZY_THRESHOLD;
+
+/// `slow_query_ms` is the threshold for logging a statement as slow, at
+/// warn level under the `sqlx::query` target; 0 disables the slow-query log.
+pub async fn create_pool(database_url: &str, slow_query_ms: u64) -> Result<PgPool> {
+    let threshold = FUZZY_THRESHOLD.to_string();
+    let options: PgConnectOptions = database_url.parse()?;
+    // Thresholds of the pg_trgm `%` and `<%` operators, which unlike the
+    // similarity functions can use the trigram indexes
+    let options = options.options([
+        ("pg_trgm.similarity_threshold", threshold.as_str()),
+        ("pg_trgm.word_similarity_threshold", threshold.as_str()),
+    ]);
+    let options = if slow_query_ms > 0 {
+        options.log_slow_statements(LevelFilter::Warn, Duration::from_millis(slow_query_ms))
+    } else {
+        options.log_slow_statements(LevelFilter::Off, Duration::default())
+    };
 
-pub async fn create_pool(database_url: &str) -> Result<PgPool> {
     let pool = PgPoolOptions::new()
         .max_connections(10)
-        .connect(database_url)
+        .connect_with(options)
         .await?;
 
     Ok(pool)
@@ -1,188 +1,204 @@
 use anyhow::Result;
-use chrono::{Duration, Utc};
-use sqlx::PgPool;
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{PgPool, QueryBuilder};
+use std::collections::HashMap;
 use uuid::Uuid;
 
+use super::filter::{ArticleFilter, ArticleSort, HighlightOptions, Keyset, SortOrder, TagMode};
+use super::pagination::{estimate_rows, Cursor};
 use super::models::{
-    Article, ArticlePreview, NewArticle, ScrapeRun, ScrapeStatus, ScrapeType, ScraperSetting,
-    Stats, TagStat,
+    ArchivedArticle, Article, ArticleAsset, ArticleEdit, ArticleFacets, ArticlePreview, ArticleTag,
+    DomainStat, Entity, EntityKind, FacetCount, LinkingArticle, NewArticle, NewAsset, NewLink,
+    NewTag, NewTagRule, RankedArticle, RelatedCandidate, ScrapeRun, ScrapeStatus, ScrapeType,
+    ScraperSetting, SearchHighlight, Stats, StoryClusterRow, Suggestions, Tag, TagCategory, TagRule,
+    TagStat, TimelinePoint, TitleSuggestion, Tombstone, TrashedArticle, UrlStat,
 };
+use crate::entities::{GazetteerEntry, Mentions};
+use crate::scraper::content::ArticleBody;
 use crate::scraper::{Source, SourceInfo};
 
 // Article queries
 
+/// Article columns plus the article's tag names, aggregated in the same
+/// query so listings don't fetch tags per row. Followed by the rank column.
+const ARTICLE_ROW_SELECT: &str = "SELECT a.*, ARRAY(SELECT t.name FROM article_tags at \
+    JOIN tags t ON t.id = at.tag_id WHERE at.article_id = a.id) AS tags, ";
+
 pub async fn get_articles(
     pool: &PgPool,
     page: i64,
     per_page: i64,
-    search: Option<&str>,
-    tag: Option<&str>,
-    source: Option<&str>,
+    filter: &ArticleFilter,
+    sort: ArticleSort,
+    order: SortOrder,
+    highlight: Option<&HighlightOptions>,
 ) -> Result<(Vec<ArticlePreview>, i64)> {
     let offset = (page - 1) * per_page;
 
-    // Build dynamic query based on filters
-    let (count, articles) = match (search, tag, source) {
-        (Some(search_term), _, Some(src)) => {
-            let count: (i64,) = sqlx::query_as(
-                "SELECT COUNT(*) FROM articles WHERE search_vector @@ plainto_tsquery('english', $1) AND source = $2",
-            )
-            .bind(search_term)
-            .bind(src)
-            .fetch_one(pool)
-            .await?;
+    let mut count_query = QueryBuilder::new("SELECT COUNT(*) FROM articles a");
+    filter.push_where(&mut count_query);
+    let (count,): (i64,) = count_query.build_query_as().fetch_one(pool).await?;
+
+    let mut query = QueryBuilder::new(ARTICLE_ROW_SELECT);
+    filter.push_rank(&mut query);
+    query.push(" FROM articles a");
+    filter.push_where(&mut query);
+    filter.push_order_by(&mut query, sort, order);
+    query
+        .push(" LIMIT ")
+        .push_bind(per_page)
+        .push(" OFFSET ")
+        .push_bind(offset);
+    let articles: Vec<RankedArticle> = query.build_query_as().fetch_all(pool).await?;
+
+    let previews = article_previews(pool, articles, filter, highlight).await?;
+    Ok((previews, count))
+}
 
-            let articles: Vec<Article> = sqlx::query_as(
-                r#"
-                SELECT * FROM articles
-                WHERE search_vector @@ plainto_tsquery('english', $1) AND source = $2
-                ORDER BY published_at DESC NULLS LAST
-                LIMIT $3 OFFSET $4
-                "#,
-            )
-            .bind(search_term)
-            .bind(src)
-            .bind(per_page)
-            .bind(offset)
-            .fetch_all(pool)
-            .await?;
+/// Keyset-paginated listing: the page after `after` (or the first page) and
+/// a cursor for the next one, if any. Unlike `get_articles` it never counts.
+pub async fn get_articles_after(
+    pool: &PgPool,
+    per_page: i64,
+    filter: &ArticleFilter,
+    sort: ArticleSort,
+    order: SortOrder,
+    after: Option<&Keyset>,
+    highlight: Option<&HighlightOptions>,
+) -> Result<(Vec<ArticlePreview>, Option<Cursor>)> {
+    let mut query = QueryBuilder::new(ARTICLE_ROW_SELECT);
+    filter.push_rank(&mut query);
+    query.push(" FROM articles a");
+    match after {
+        Some(keyset) => filter.push_where_after(&mut query, sort, order, keyset),
+        None => filter.push_where(&mut query),
+    }
+    filter.push_order_by(&mut query, sort, order);
+    // One extra row tells whether there is a next page
+    query.push(" LIMIT ").push_bind(per_page + 1);
+    let mut articles: Vec<RankedArticle> = query.build_query_as().fetch_all(pool).await?;
+
+    let next = if articles.len() as i64 > per_page {
+        articles.truncate(per_page as usize);
+        articles.last().map(|row| filter.cursor_after(sort, order, row))
+    } else {
+        None
+    };
 
-            (count.0, articles)
-        }
-        (Some(search_term), _, None) => {
-            let count: (i64,) = sqlx::query_as(
-                "SELECT COUNT(*) FROM articles WHERE search_vector @@ plainto_tsquery('english', $1)",
-            )
-            .bind(search_term)
-            .fetch_one(pool)
-            .await?;
+    let previews = article_previews(pool, articles, filter, highlight).await?;
+    Ok((previews, next))
+}
 
-            let articles: Vec<Article> = sqlx::query_as(
-                r#"
-                SELECT * FROM articles
-                WHERE search_vector @@ plainto_tsquery('english', $1)
-                ORDER BY published_at DESC NULLS LAST
-                LIMIT $2 OFFSET $3
-                "#,
-            )
-            .bind(search_term)
-            .bind(per_page)
-            .bind(offset)
-            .fetch_all(pool)
-            .await?;
+/// Ids of every article matching `filter`, ordered by id
+pub async fn get_article_ids(pool: &PgPool, filter: &ArticleFilter) -> Result<Vec<Uuid>> {
+    let mut query = QueryBuilder::new("SELECT a.id FROM articles a");
+    filter.push_where(&mut query);
+    query.push(" ORDER BY a.id");
+    let ids: Vec<(Uuid,)> = query.build_query_as().fetch_all(pool).await?;
 
-            (count.0, articles)
-        }
-        (None, Some(tag_name), Some(src)) => {
-            let count: (i64,) = sqlx::query_as(
-                r#"
-                SELECT COUNT(DISTINCT a.id) FROM articles a
-                JOIN article_tags at ON a.id = at.article_id
-                JOIN tags t ON at.tag_id = t.id
-                WHERE t.name = $1 AND a.source = $2
-                "#,
-            )
-            .bind(tag_name)
-            .bind(src)
-            .fetch_one(pool)
-            .await?;
+    Ok(ids.into_iter().map(|(id,)| id).collect())
+}
 
-            let articles: Vec<Article> = sqlx::query_as(
-                r#"
-                SELECT DISTINCT a.* FROM articles a
-                JOIN article_tags at ON a.id = at.article_id
-                JOIN tags t ON at.tag_id = t.id
-                WHERE t.name = $1 AND a.source = $2
-                ORDER BY a.published_at DESC NULLS LAST
-                LIMIT $3 OFFSET $4
-                "#,
-            )
-            .bind(tag_name)
-            .bind(src)
-            .bind(per_page)
-            .bind(offset)
-            .fetch_all(pool)
-            .await?;
+/// Planner estimate of the number of articles matching `filter`
+pub async fn estimate_articles(pool: &PgPool, filter: &ArticleFilter) -> Result<i64> {
+    let mut query = QueryBuilder::new("EXPLAIN SELECT 1 FROM articles a");
+    filter.push_where(&mut query);
+    estimate_rows(pool, query).await
+}
 
-            (count.0, articles)
+/// Previews of the given articles in the order of `ids`. Unknown and
+/// trashed ids are skipped.
+pub async fn get_article_previews(pool: &PgPool, ids: &[Uuid]) -> Result<Vec<ArticlePreview>> {
+    let mut query = QueryBuilder::new(ARTICLE_ROW_SELECT);
+    query.push("NULL::real AS rank FROM articles a WHERE a.deleted_at IS NULL AND a.id = ANY(");
+    query.push_bind(ids.to_vec());
+    query.push(")");
+    let mut rows: Vec<RankedArticle> = query.build_query_as().fetch_all(pool).await?;
+
+    let mut ordered = Vec::with_capacity(rows.len());
+    for id in ids {
+        if let Some(i) = rows.iter().position(|row| row.article.id == *id) {
+            ordered.push(rows.swap_remove(i));
         }
-        (None, Some(tag_name), None) => {
-            let count: (i64,) = sqlx::query_as(
-                r#"
-                SELECT COUNT(DISTINCT a.id) FROM articles a
-                JOIN article_tags at ON a.id = at.article_id
-                JOIN tags t ON at.tag_id = t.id
-                WHERE t.name = $1
-                "#,
-            )
-            .bind(tag_name)
-            .fetch_one(pool)
-            .await?;
-
-            let articles: Vec<Article> = sqlx::query_as(
-                r#"
-                SELECT DISTINCT a.* FROM articles a
-                JOIN article_tags at ON a.id = at.article_id
-                JOIN tags t ON at.tag_id = t.id
-                WHERE t.name = $1
-                ORDER BY a.published_at DESC NULLS LAST
-                LIMIT $2 OFFSET $3
-                "#,
-            )
-            .bind(tag_name)
-            .bind(per_page)
-            .bind(offset)
-            .fetch_all(pool)
-            .await?;
+    }
 
-            (count.0, articles)
-        }
-        (None, None, Some(src)) => {
-            let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM articles WHERE source = $1")
-                .bind(src)
-                .fetch_one(pool)
-                .await?;
+    article_previews(pool, ordered, &ArticleFilter::default(), None).await
+}
 
-            let articles: Vec<Article> = sqlx::query_as(
-                r#"
-                SELECT * FROM articles
-                WHERE source = $1
-                ORDER BY published_at DESC NULLS LAST
-                LIMIT $2 OFFSET $3
-                "#,
-            )
-            .bind(src)
-            .bind(per_page)
-            .bind(offset)
-            .fetch_all(pool)
-            .await?;
+/// `get_article_previews` with `rank` set to each article's score
+pub async fn get_scored_previews(
+    pool: &PgPool,
+    scored: &[(Uuid, f32)],
+) -> Result<Vec<ArticlePreview>> {
+    let ids: Vec<Uuid> = scored.iter().map(|(id, _)| *id).collect();
+    let mut previews = get_article_previews(pool, &ids).await?;
+    for preview in &mut previews {
+        preview.rank = scored
+            .iter()
+            .find(|(id, _)| *id == preview.id)
+            .map(|(_, score)| *score);
+    }
 
-            (count.0, articles)
-        }
-        (None, None, None) => {
-            let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM articles")
-                .fetch_one(pool)
-                .await?;
+    Ok(previews)
+}
 
-            let articles: Vec<Article> = sqlx::query_as(
-                r#"
-                SELECT * FROM articles
-                ORDER BY published_at DESC NULLS LAST
-                LIMIT $1 OFFSET $2
-                "#,
-            )
-            .bind(per_page)
-            .bind(offset)
-            .fetch_all(pool)
-            .await?;
+/// Articles sharing at least one tag with `id`, plus `extra_ids`, with the
+/// inputs of the related-article score
+pub async fn get_related_candidates(
+    pool: &PgPool,
+    id: Uuid,
+    extra_ids: &[Uuid],
+) -> Result<Vec<RelatedCandidate>> {
+    let candidates = sqlx::query_as::<_, RelatedCandidate>(
+        r#"
+        WITH target AS (
+            SELECT COALESCE(published_at, scraped_at) AS at,
+                   (SELECT COUNT(*) FROM article_tags WHERE article_id = $1) AS tag_count
+            FROM articles WHERE id = $1
+        ),
+        candidates AS (
+            SELECT other.article_id AS id, COUNT(*) AS shared_tags
+            FROM article_tags own
+            JOIN article_tags other ON other.tag_id = own.tag_id AND other.article_id <> $1
+            WHERE own.article_id = $1
+            GROUP BY other.article_id
+            ORDER BY shared_tags DESC
+            LIMIT 200
+        )
+        SELECT
+            b.id,
+            COALESCE(c.shared_tags, 0) AS shared_tags,
+            t.tag_count + (SELECT COUNT(*) FROM article_tags WHERE article_id = b.id)
+                - COALESCE(c.shared_tags, 0) AS tag_union,
+            (ABS(EXTRACT(EPOCH FROM COALESCE(b.published_at, b.scraped_at) - t.at)) / 86400)::float8
+                AS days_apart
+        FROM articles b
+        CROSS JOIN target t
+        LEFT JOIN candidates c ON c.id = b.id
+        WHERE b.id <> $1 AND b.deleted_at IS NULL AND (c.id IS NOT NULL OR b.id = ANY($2))
+        "#,
+    )
+    .bind(id)
+    .bind(extra_ids)
+    .fetch_all(pool)
+    .await?;
 
-            (count.0, articles)
-        }
-    };
+    Ok(candidates)
+}
 
+async fn article_previews(
+    pool: &PgPool,
+    articles: Vec<RankedArticle>,
+    filter: &ArticleFilter,
+    highlight: Option<&HighlightOptions>,
+) -> Result<Vec<ArticlePreview>> {
     let mut previews = Vec::new();
-    for article in articles {
-        let tags = get_article_tags(pool, article.id).await?;
+    for RankedArticle {
+        article,
+        tags,
+        rank,
+    } in articles
+    {
         previews.push(ArticlePreview {
             id: article.id,
             external_id: article.external_id,
@@ -193,14 +209,31 @@ pub async fn get_articles(
             view_count: article.view_count,
             tags,
             source: article.source.unwrap_or_else(|| "AIBase".to_string()),
+            cluster_id: article.cluster_id,
+            rank,
+            snippet: None,
+            title_highlight: None,
         });
     }
 
-    Ok((previews, count))
+    let highlight_text = filter.search.as_ref().map(|s| s.positive_text());
+    if let (Some(text), Some(options)) = (highlight_text.filter(|t| !t.is_empty()), highlight) {
+        let ids: Vec<Uuid> = previews.iter().map(|p| p.id).collect();
+        let highlights = get_search_highlights(pool, &ids, &text, options).await?;
+        for preview in &mut previews {
+            if let Some(h) = highlights.iter().find(|h| h.id == preview.id) {
+                preview.snippet = h.snippet.clone();
+                preview.title_highlight = h.title.clone();
+            }
+        }
+    }
+
+    Ok(previews)
 }
 
 pub async fn get_article_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Article>> {
-    let article = sqlx::query_as::<_, Article>("SELECT * FROM articles WHERE id = $1")
+    let article =
+        sqlx::query_as::<_, Article>("SELECT * FROM articles WHERE id = $1 AND deleted_at IS NULL")
         .bind(id)
         .fetch_optional(pool)
         .await?;
@@ -209,7 +242,9 @@ pub async fn get_article_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Article
 }
 
 pub async fn get_article_by_external_id(pool: &PgPool, external_id: &str) -> Result<Option<Article>> {
-    let article = sqlx::query_as::<_, Article>("SELECT * FROM articles WHERE external_id = $1")
+    let article = sqlx::query_as::<_, Article>(
+        "SELECT * FROM articles WHERE external_id = $1 AND deleted_at IS NULL",
+    )
         .bind(external_id)
         .fetch_optional(pool)
         .await?;
@@ -233,9 +268,10 @@ pub async fn insert_article(pool: &PgPool, article: &NewArticle) -> Result<Uuid>
         r#"
         INSERT INTO articles (
             external_id, url, title, content, excerpt, author, source,
-            published_at, view_count, read_time_minutes, thumbnail_url, content_hash
+            published_at, view_count, read_time_minutes, thumbnail_url, content_hash,
+            content_html, content_markdown, content_format, simhash, language
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
         RETURNING id
         "#,
     )
@@ -251,12 +287,17 @@ pub async fn insert_article(pool: &PgPool, article: &NewArticle) -> Result<Uuid>
     .bind(article.read_time_minutes)
     .bind(&article.thumbnail_url)
     .bind(&article.content_hash)
+    .bind(&article.content_html)
+    .bind(&article.content_markdown)
+    .bind(article.content_format)
+    .bind(article.simhash)
+    .bind(&article.language)
     .fetch_one(pool)
     .await?;
 
     // Insert tags
-    for tag_name in &article.tags {
-        let tag_id = get_or_create_tag(pool, tag_name).await?;
+    for tag in &article.tags {
+        let tag_id = get_or_create_tag(pool, tag).await?;
         sqlx::query("INSERT INTO article_tags (article_id, tag_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
             .bind(id.0)
             .bind(tag_id)
@@ -264,17 +305,34 @@ pub async fn insert_article(pool: &PgPool, article: &NewArticle) -> Result<Uuid>
             .await?;
     }
 
+    replace_article_links(pool, id.0, &article.links).await?;
+
     Ok(id.0)
 }
 
-pub async fn update_article(pool: &PgPool, source: &str, external_id: &str, article: &NewArticle) -> Result<()> {
-    sqlx::query(
+/// Refresh a re-scraped article. Fields in `manual_fields` keep their edited
+/// values.
+pub async fn update_article(pool: &PgPool, source: &str, external_id: &str, article: &NewArticle) -> Result<Uuid> {
+    let id: (Uuid,) = sqlx::query_as(
         r#"
         UPDATE articles SET
-            title = $3, content = $4, excerpt = $5, author = $6,
-            published_at = $7, view_count = $8, read_time_minutes = $9,
-            thumbnail_url = $10, content_hash = $11
+            title = CASE WHEN 'title' = ANY(manual_fields) THEN title ELSE $3 END,
+            content = $4,
+            excerpt = CASE WHEN 'excerpt' = ANY(manual_fields) THEN excerpt ELSE $5 END,
+            author = CASE WHEN 'author' = ANY(manual_fields) THEN author ELSE $6 END,
+            published_at = CASE
+                WHEN 'published_at' = ANY(manual_fields) THEN published_at ELSE $7
+            END,
+            view_count = $8, read_time_minutes = $9,
+            thumbnail_url = $10, content_hash = $11,
+            content_html = $12, content_markdown = $13, content_format = $14,
+            simhash = $15, language = $16,
+            cluster_checked = cluster_checked AND content_hash IS NOT DISTINCT FROM $11,
+            entities_version = CASE
+                WHEN content_hash IS NOT DISTINCT FROM $11 THEN entities_version
+            END
         WHERE source = $1 AND external_id = $2
+        RETURNING id
         "#,
     )
     .bind(source)
@@ -288,194 +346,2206 @@ pub async fn update_article(pool: &PgPool, source: &str, external_id: &str, arti
     .bind(article.read_time_minutes)
     .bind(&article.thumbnail_url)
     .bind(&article.content_hash)
-    .execute(pool)
+    .bind(&article.content_html)
+    .bind(&article.content_markdown)
+    .bind(article.content_format)
+    .bind(article.simhash)
+    .bind(&article.language)
+    .fetch_one(pool)
     .await?;
 
-    Ok(())
+    replace_article_links(pool, id.0, &article.links).await?;
+
+    Ok(id.0)
 }
 
-pub async fn delete_article(pool: &PgPool, id: Uuid) -> Result<bool> {
-    let result = sqlx::query("DELETE FROM articles WHERE id = $1")
+/// Apply a manual edit in one transaction: set the given fields and mark them
+/// as edited, add `add_tags` and the tags named in `edit.add_tags` (created if
+/// they don't exist) as manual tags, and remove `remove_tags` for good.
+/// Returns `false`, changing nothing, if the article doesn't exist or is trashed.
+pub async fn edit_article(
+    pool: &PgPool,
+    id: Uuid,
+    edit: &ArticleEdit,
+    add_tags: &[i32],
+    remove_tags: &[i32],
+) -> Result<bool> {
+    let edited: Vec<&str> = edit.fields().iter().map(|field| field.as_str()).collect();
+    let reverted: Vec<&str> = edit.revert.iter().map(|field| field.as_str()).collect();
+    let mut tx = pool.begin().await?;
+
+    let exists = sqlx::query("SELECT 1 FROM articles WHERE id = $1 AND deleted_at IS NULL FOR UPDATE")
         .bind(id)
-        .execute(pool)
+        .fetch_optional(&mut *tx)
+        .await?
+        .is_some();
+    if !exists {
+        return Ok(false);
+    }
+
+    let mut add_tags = add_tags.to_vec();
+    if !edit.add_tags.is_empty() {
+        let created: Vec<(i32,)> = sqlx::query_as(
+            r#"
+            WITH resolved AS (
+                SELECT DISTINCT ON (tag_slug(n)) n, resolve_tag(n) AS id
+                FROM unnest($1::text[]) n
+            ), created AS (
+                INSERT INTO tags (name, slug)
+                SELECT tag_name(n), tag_slug(n) FROM resolved WHERE id IS NULL
+                ON CONFLICT (slug) DO UPDATE SET slug = EXCLUDED.slug
+                RETURNING id
+            )
+            SELECT id FROM resolved WHERE id IS NOT NULL
+            UNION
+            SELECT id FROM created
+            "#,
+        )
+        .bind(&edit.add_tags)
+        .fetch_all(&mut *tx)
         .await?;
+        add_tags.extend(created.into_iter().map(|(id,)| id));
+    }
 
-    Ok(result.rows_affected() > 0)
-}
+    let mut query = QueryBuilder::new("UPDATE articles SET edited_at = NOW()");
+    if let Some(title) = &edit.title {
+        query.push(", title = ").push_bind(title);
+    }
+    if let Some(excerpt) = &edit.excerpt {
+        query.push(", excerpt = ").push_bind(excerpt);
+    }
+    if let Some(author) = &edit.author {
+        query.push(", author = ").push_bind(author);
+    }
+    if let Some(published_at) = &edit.published_at {
+        query.push(", published_at = ").push_bind(published_at);
+    }
+    query
+        .push(", manual_fields = ARRAY(SELECT DISTINCT f FROM unnest(manual_fields || ")
+        .push_bind(&edited)
+        .push("::text[]) f WHERE f <> ALL(")
+        .push_bind(&reverted)
+        .push("::text[]) ORDER BY f) WHERE id = ")
+        .push_bind(id);
+    query.build().execute(&mut *tx).await?;
 
-// Tag queries
+    sqlx::query(
+        r#"
+        INSERT INTO article_tags (article_id, tag_id, source)
+        SELECT $1, tag_id, 'manual' FROM unnest($2::int[]) tag_id
+        ON CONFLICT (article_id, tag_id) DO UPDATE SET source = 'manual', rule_id = NULL
+        "#,
+    )
+    .bind(id)
+    .bind(&add_tags)
+    .execute(&mut *tx)
+    .await?;
 
-pub async fn get_or_create_tag(pool: &PgPool, name: &str) -> Result<i32> {
-    let existing: Option<(i32,)> = sqlx::query_as("SELECT id FROM tags WHERE name = $1")
-        .bind(name)
-        .fetch_optional(pool)
+    sqlx::query("DELETE FROM article_tag_removals WHERE article_id = $1 AND tag_id = ANY($2)")
+        .bind(id)
+        .bind(&add_tags)
+        .execute(&mut *tx)
         .await?;
 
-    if let Some((id,)) = existing {
-        return Ok(id);
-    }
-
-    let id: (i32,) = sqlx::query_as("INSERT INTO tags (name) VALUES ($1) RETURNING id")
-        .bind(name)
-        .fetch_one(pool)
+    sqlx::query("DELETE FROM article_tags WHERE article_id = $1 AND tag_id = ANY($2)")
+        .bind(id)
+        .bind(remove_tags)
+        .execute(&mut *tx)
         .await?;
 
-    Ok(id.0)
+    sqlx::query(
+        r#"
+        INSERT INTO article_tag_removals (article_id, tag_id)
+        SELECT $1, tag_id FROM unnest($2::int[]) tag_id
+        ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(id)
+    .bind(remove_tags)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(true)
 }
 
-pub async fn get_article_tags(pool: &PgPool, article_id: Uuid) -> Result<Vec<String>> {
-    let tags: Vec<(String,)> = sqlx::query_as(
+/// Mark an article read, keeping the time it was first read, or unread
+pub async fn set_article_read(pool: &PgPool, id: Uuid, read: bool) -> Result<bool> {
+    let result = sqlx::query(
         r#"
-        SELECT t.name FROM tags t
-        JOIN article_tags at ON t.id = at.tag_id
-        WHERE at.article_id = $1
+        UPDATE articles SET read_at = CASE WHEN $2 THEN COALESCE(read_at, NOW()) END
+        WHERE id = $1 AND deleted_at IS NULL
         "#,
     )
-    .bind(article_id)
-    .fetch_all(pool)
+    .bind(id)
+    .bind(read)
+    .execute(pool)
     .await?;
 
-    Ok(tags.into_iter().map(|(name,)| name).collect())
+    Ok(result.rows_affected() > 0)
 }
 
-// Scrape run queries
-
-pub async fn create_scrape_run(
+/// Articles captured as HTML whose body has not been sanitized and converted to Markdown yet
+pub async fn get_articles_missing_markdown(
     pool: &PgPool,
-    scrape_type: ScrapeType,
-    config: Option<serde_json::Value>,
-) -> Result<Uuid> {
-    let id: (Uuid,) = sqlx::query_as(
-        "INSERT INTO scrape_runs (scrape_type, config) VALUES ($1, $2) RETURNING id",
+    limit: i64,
+) -> Result<Vec<(Uuid, String, String)>> {
+    let rows: Vec<(Uuid, String, String)> = sqlx::query_as(
+        r#"
+        SELECT id, url, content_html FROM articles
+        WHERE content_format = 'html' AND content_html IS NOT NULL AND content_markdown IS NULL
+        LIMIT $1
+        "#,
     )
-    .bind(scrape_type)
-    .bind(config)
-    .fetch_one(pool)
+    .bind(limit)
+    .fetch_all(pool)
     .await?;
 
-    Ok(id.0)
+    Ok(rows)
 }
 
-pub async fn update_scrape_run_progress(
-    pool: &PgPool,
-    id: Uuid,
-    pages_scraped: i32,
-    articles_found: i32,
-    articles_new: i32,
-    articles_failed: i32,
-) -> Result<()> {
+pub async fn set_article_body(pool: &PgPool, id: Uuid, body: &ArticleBody) -> Result<()> {
     sqlx::query(
         r#"
-        UPDATE scrape_runs SET
-            pages_scraped = $2, articles_found = $3, articles_new = $4, articles_failed = $5
+        UPDATE articles SET
+            content = $2, content_html = $3, content_markdown = $4, content_format = $5
         WHERE id = $1
         "#,
     )
     .bind(id)
-    .bind(pages_scraped)
-    .bind(articles_found)
-    .bind(articles_new)
-    .bind(articles_failed)
+    .bind(&body.text)
+    .bind(&body.html)
+    .bind(&body.markdown)
+    .bind(body.format)
     .execute(pool)
     .await?;
 
     Ok(())
 }
 
-pub async fn complete_scrape_run(pool: &PgPool, id: Uuid, status: ScrapeStatus) -> Result<()> {
-    sqlx::query("UPDATE scrape_runs SET status = $2, completed_at = NOW() WHERE id = $1")
-        .bind(id)
-        .bind(status)
-        .execute(pool)
-        .await?;
-
-    Ok(())
-}
-
-pub async fn get_scrape_runs(pool: &PgPool, limit: i64) -> Result<Vec<ScrapeRun>> {
-    let runs = sqlx::query_as::<_, ScrapeRun>(
-        "SELECT * FROM scrape_runs ORDER BY started_at DESC LIMIT $1",
+/// Page through articles that have an HTML body, ordered by id
+pub async fn get_article_html_after(
+    pool: &PgPool,
+    after: Uuid,
+    limit: i64,
+) -> Result<Vec<(Uuid, String, String)>> {
+    let rows: Vec<(Uuid, String, String)> = sqlx::query_as(
+        r#"
+        SELECT id, url, content_html FROM articles
+        WHERE content_html IS NOT NULL AND id > $1
+        ORDER BY id
+        LIMIT $2
+        "#,
     )
+    .bind(after)
     .bind(limit)
     .fetch_all(pool)
     .await?;
 
-    Ok(runs)
-}
-
-pub async fn get_running_scrape(pool: &PgPool) -> Result<Option<ScrapeRun>> {
-    let run = sqlx::query_as::<_, ScrapeRun>(
-        "SELECT * FROM scrape_runs WHERE status = 'running' ORDER BY started_at DESC LIMIT 1",
-    )
-    .fetch_optional(pool)
-    .await?;
-
-    Ok(run)
+    Ok(rows)
 }
 
-// Stats queries
+// Trash queries
 
-pub async fn get_stats(pool: &PgPool) -> Result<Stats> {
-    let total: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM articles")
-        .fetch_one(pool)
-        .await?;
+/// Move an article to the trash and tombstone its source and external id
+pub async fn trash_article(pool: &PgPool, id: Uuid, reason: Option<&str>) -> Result<bool> {
+    let mut tx = pool.begin().await?;
 
-    let today = Utc::now().date_naive();
-    let today_count: (i64,) = sqlx::query_as(
-        "SELECT COUNT(*) FROM articles WHERE DATE(scraped_at) = $1",
+    let trashed: Option<(String, String, String)> = sqlx::query_as(
+        r#"
+        UPDATE articles SET deleted_at = NOW()
+        WHERE id = $1 AND deleted_at IS NULL
+        RETURNING COALESCE(source, 'AIBase'), external_id, title
+        "#,
     )
-    .bind(today)
-    .fetch_one(pool)
+    .bind(id)
+    .fetch_optional(&mut *tx)
     .await?;
 
-    let week_ago = Utc::now() - Duration::days(7);
-    let week_count: (i64,) = sqlx::query_as(
-        "SELECT COUNT(*) FROM articles WHERE scraped_at >= $1",
+    let Some((source, external_id, title)) = trashed else {
+        return Ok(false);
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO article_tombstones (source, external_id, title, reason)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (source, external_id) DO UPDATE SET
+            title = EXCLUDED.title,
+            reason = COALESCE(EXCLUDED.reason, article_tombstones.reason)
+        "#,
     )
-    .bind(week_ago)
-    .fetch_one(pool)
+    .bind(&source)
+    .bind(&external_id)
+    .bind(&title)
+    .bind(reason)
+    .execute(&mut *tx)
     .await?;
 
-    let last_scrape: Option<(chrono::DateTime<Utc>,)> = sqlx::query_as(
-        "SELECT started_at FROM scrape_runs WHERE status = 'completed' ORDER BY started_at DESC LIMIT 1",
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Take an article out of the trash and lift its tombstone
+pub async fn restore_article(pool: &PgPool, id: Uuid) -> Result<bool> {
+    let mut tx = pool.begin().await?;
+
+    let restored: Option<(String, String)> = sqlx::query_as(
+        r#"
+        UPDATE articles SET deleted_at = NULL
+        WHERE id = $1 AND deleted_at IS NOT NULL
+        RETURNING COALESCE(source, 'AIBase'), external_id
+        "#,
     )
-    .fetch_optional(pool)
+    .bind(id)
+    .fetch_optional(&mut *tx)
     .await?;
 
-    let total_runs: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM scrape_runs")
-        .fetch_one(pool)
+    let Some((source, external_id)) = restored else {
+        return Ok(false);
+    };
+
+    sqlx::query("DELETE FROM article_tombstones WHERE source = $1 AND external_id = $2")
+        .bind(&source)
+        .bind(&external_id)
+        .execute(&mut *tx)
         .await?;
 
-    Ok(Stats {
-        total_articles: total.0,
-        articles_today: today_count.0,
-        articles_this_week: week_count.0,
-        last_scrape: last_scrape.map(|(dt,)| dt),
-        total_scrape_runs: total_runs.0,
-    })
+    tx.commit().await?;
+    Ok(true)
 }
 
-pub async fn get_tag_stats(pool: &PgPool, limit: i64) -> Result<Vec<TagStat>> {
-    let stats: Vec<(String, i64)> = sqlx::query_as(
+/// Trashed articles, most recently deleted first
+pub async fn get_trashed_articles(
+    pool: &PgPool,
+    page: i64,
+    per_page: i64,
+) -> Result<(Vec<TrashedArticle>, i64)> {
+    let (total,): (i64,) =
+        sqlx::query_as("SELECT COUNT(*) FROM articles WHERE deleted_at IS NOT NULL")
+            .fetch_one(pool)
+            .await?;
+
+    let articles = sqlx::query_as::<_, TrashedArticle>(
         r#"
-        SELECT t.name, COUNT(*) as count FROM tags t
-        JOIN article_tags at ON t.id = at.tag_id
-        GROUP BY t.name
-        ORDER BY count DESC
-        LIMIT $1
+        SELECT a.id, a.external_id, a.source, a.url, a.title, a.deleted_at, t.reason
+        FROM articles a
+        LEFT JOIN article_tombstones t
+            ON t.source = COALESCE(a.source, 'AIBase') AND t.external_id = a.external_id
+        WHERE a.deleted_at IS NOT NULL
+        ORDER BY a.deleted_at DESC, a.id
+        LIMIT $1 OFFSET $2
         "#,
     )
-    .bind(limit)
+    .bind(per_page)
+    .bind((page - 1) * per_page)
     .fetch_all(pool)
     .await?;
 
-    Ok(stats
-        .into_iter()
-        .map(|(name, count)| TagStat { name, count })
-        .collect())
+    Ok((articles, total))
 }
 
-// Settings queries
+/// Permanently delete a trashed article. Its tombstone stays.
+pub async fn purge_article(pool: &PgPool, id: Uuid) -> Result<bool> {
+    let result = sqlx::query("DELETE FROM articles WHERE id = $1 AND deleted_at IS NOT NULL")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Permanently delete articles trashed before `before`, or the whole trash
+pub async fn purge_trash(pool: &PgPool, before: Option<DateTime<Utc>>) -> Result<u64> {
+    let result = sqlx::query(
+        r#"
+        DELETE FROM articles
+        WHERE deleted_at IS NOT NULL AND ($1::timestamptz IS NULL OR deleted_at < $1)
+        "#,
+    )
+    .bind(before)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+pub async fn is_tombstoned(pool: &PgPool, source: &str, external_id: &str) -> Result<bool> {
+    let result: (bool,) = sqlx::query_as(
+        "SELECT EXISTS(SELECT 1 FROM article_tombstones WHERE source = $1 AND external_id = $2)",
+    )
+    .bind(source)
+    .bind(external_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(result.0)
+}
+
+/// Tombstones, newest first, optionally for one source
+pub async fn get_tombstones(
+    pool: &PgPool,
+    source: Option<&str>,
+    page: i64,
+    per_page: i64,
+) -> Result<(Vec<Tombstone>, i64)> {
+    let (total,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM article_tombstones WHERE ($1::text IS NULL OR source = $1)",
+    )
+    .bind(source)
+    .fetch_one(pool)
+    .await?;
+
+    let tombstones = sqlx::query_as::<_, Tombstone>(
+        r#"
+        SELECT source, external_id, title, reason, created_at
+        FROM article_tombstones
+        WHERE ($1::text IS NULL OR source = $1)
+        ORDER BY created_at DESC, source, external_id
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(source)
+    .bind(per_page)
+    .bind((page - 1) * per_page)
+    .fetch_all(pool)
+    .await?;
+
+    Ok((tombstones, total))
+}
+
+/// Block a (source, external_id) pair from being scraped. A stored article
+/// with that id is moved to the trash.
+pub async fn add_tombstone(
+    pool: &PgPool,
+    source: &str,
+    external_id: &str,
+    reason: Option<&str>,
+) -> Result<Tombstone> {
+    let mut tx = pool.begin().await?;
+
+    let title: Option<(String,)> = sqlx::query_as(
+        r#"
+        UPDATE articles SET deleted_at = COALESCE(deleted_at, NOW())
+        WHERE source = $1 AND external_id = $2
+        RETURNING title
+        "#,
+    )
+    .bind(source)
+    .bind(external_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let tombstone = sqlx::query_as::<_, Tombstone>(
+        r#"
+        INSERT INTO article_tombstones (source, external_id, title, reason)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (source, external_id) DO UPDATE SET
+            title = COALESCE(EXCLUDED.title, article_tombstones.title),
+            reason = COALESCE(EXCLUDED.reason, article_tombstones.reason)
+        RETURNING source, external_id, title, reason, created_at
+        "#,
+    )
+    .bind(source)
+    .bind(external_id)
+    .bind(title.map(|(title,)| title))
+    .bind(reason)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(tombstone)
+}
+
+/// Let a (source, external_id) pair be scraped again. A trashed article with
+/// that id stays in the trash.
+pub async fn delete_tombstone(pool: &PgPool, source: &str, external_id: &str) -> Result<bool> {
+    let result = sqlx::query("DELETE FROM article_tombstones WHERE source = $1 AND external_id = $2")
+        .bind(source)
+        .bind(external_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// Retention queries
+
+/// Clear the HTML of `source`'s articles scraped before `before`, keeping
+/// their Markdown. With `dry_run`, only count them.
+pub async fn purge_raw_html(
+    pool: &PgPool,
+    source: &str,
+    before: DateTime<Utc>,
+    dry_run: bool,
+) -> Result<u64> {
+    const EXPIRED: &str = "source = $1 AND scraped_at < $2 \
+        AND content_html IS NOT NULL AND content_markdown IS NOT NULL";
+
+    if dry_run {
+        let (count,): (i64,) =
+            sqlx::query_as(&format!("SELECT COUNT(*) FROM articles WHERE {}", EXPIRED))
+                .bind(source)
+                .bind(before)
+                .fetch_one(pool)
+                .await?;
+        return Ok(count as u64);
+    }
+
+    let result = sqlx::query(&format!("UPDATE articles SET content_html = NULL WHERE {}", EXPIRED))
+        .bind(source)
+        .bind(before)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+const ARCHIVE_EXPIRED: &str =
+    "a.source = $1 AND a.deleted_at IS NULL AND COALESCE(a.published_at, a.scraped_at) < $2";
+
+/// Articles of `source` that `archive_articles` would archive
+pub async fn count_archivable(pool: &PgPool, source: &str, before: DateTime<Utc>) -> Result<u64> {
+    let (count,): (i64,) =
+        sqlx::query_as(&format!("SELECT COUNT(*) FROM articles a WHERE {}", ARCHIVE_EXPIRED))
+            .bind(source)
+            .bind(before)
+            .fetch_one(pool)
+            .await?;
+
+    Ok(count as u64)
+}
+
+/// Move `source`'s articles published (or, undated, scraped) before `before`
+/// to `archived_articles` with their tag names, and tombstone them. Trashed
+/// articles are left to the trash. Returns the ids of the archived articles.
+pub async fn archive_articles(
+    pool: &PgPool,
+    source: &str,
+    before: DateTime<Utc>,
+) -> Result<Vec<Uuid>> {
+    let ids: Vec<(Uuid,)> = sqlx::query_as(&format!(
+        r#"
+        WITH archived AS (
+            INSERT INTO archived_articles (id, source, external_id, title, published_at, data)
+            SELECT a.id, a.source, a.external_id, a.title, a.published_at,
+                to_jsonb(a) - 'search_vector' || jsonb_build_object('tags', ARRAY(
+                    SELECT t.name FROM article_tags at
+                    JOIN tags t ON t.id = at.tag_id
+                    WHERE at.article_id = a.id
+                    ORDER BY t.name
+                ))
+            FROM articles a
+            WHERE {}
+            RETURNING id, source, external_id, title
+        ), tombstoned AS (
+            INSERT INTO article_tombstones (source, external_id, title, reason)
+            SELECT source, external_id, title, 'archived' FROM archived
+            ON CONFLICT (source, external_id) DO NOTHING
+        )
+        DELETE FROM articles WHERE id IN (SELECT id FROM archived)
+        RETURNING id
+        "#,
+        ARCHIVE_EXPIRED
+    ))
+    .bind(source)
+    .bind(before)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ids.into_iter().map(|(id,)| id).collect())
+}
+
+/// Articles trashed before `before`, as `purge_trash` would delete them
+pub async fn count_trash(pool: &PgPool, before: DateTime<Utc>) -> Result<u64> {
+    let (count,): (i64,) =
+        sqlx::query_as("SELECT COUNT(*) FROM articles WHERE deleted_at < $1")
+            .bind(before)
+            .fetch_one(pool)
+            .await?;
+
+    Ok(count as u64)
+}
+
+/// Delete finished scrape runs started before `before`, with their article
+/// logs. With `dry_run`, only count them.
+pub async fn prune_scrape_runs(pool: &PgPool, before: DateTime<Utc>, dry_run: bool) -> Result<u64> {
+    const EXPIRED: &str = "status <> 'running' AND started_at < $1";
+
+    if dry_run {
+        let (count,): (i64,) =
+            sqlx::query_as(&format!("SELECT COUNT(*) FROM scrape_runs WHERE {}", EXPIRED))
+                .bind(before)
+                .fetch_one(pool)
+                .await?;
+        return Ok(count as u64);
+    }
+
+    let result = sqlx::query(&format!("DELETE FROM scrape_runs WHERE {}", EXPIRED))
+        .bind(before)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Delete scrape article logs written before `before`. With `dry_run`, only
+/// count them.
+pub async fn prune_article_logs(
+    pool: &PgPool,
+    before: DateTime<Utc>,
+    dry_run: bool,
+) -> Result<u64> {
+    if dry_run {
+        let (count,): (i64,) =
+            sqlx::query_as("SELECT COUNT(*) FROM scrape_article_logs WHERE scraped_at < $1")
+                .bind(before)
+                .fetch_one(pool)
+                .await?;
+        return Ok(count as u64);
+    }
+
+    let result = sqlx::query("DELETE FROM scrape_article_logs WHERE scraped_at < $1")
+        .bind(before)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Archived articles, most recently archived first, optionally for one source
+pub async fn get_archived_articles(
+    pool: &PgPool,
+    source: Option<&str>,
+    page: i64,
+    per_page: i64,
+) -> Result<(Vec<ArchivedArticle>, i64)> {
+    let (total,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM archived_articles WHERE ($1::text IS NULL OR source = $1)",
+    )
+    .bind(source)
+    .fetch_one(pool)
+    .await?;
+
+    let articles = sqlx::query_as::<_, ArchivedArticle>(
+        r#"
+        SELECT id, source, external_id, title, published_at, archived_at
+        FROM archived_articles
+        WHERE ($1::text IS NULL OR source = $1)
+        ORDER BY archived_at DESC, published_at DESC NULLS LAST, id
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(source)
+    .bind(per_page)
+    .bind((page - 1) * per_page)
+    .fetch_all(pool)
+    .await?;
+
+    Ok((articles, total))
+}
+
+/// An archived article as it was stored, with `tags` and `archived_at`
+pub async fn get_archived_article(pool: &PgPool, id: Uuid) -> Result<Option<serde_json::Value>> {
+    let article: Option<(serde_json::Value,)> = sqlx::query_as(
+        r#"
+        SELECT data || jsonb_build_object('archived_at', archived_at)
+        FROM archived_articles WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(article.map(|(data,)| data))
+}
+
+// Tag queries
+
+/// Resolve a scraped tag by normalized name or alias, creating it if it is
+/// new. A known category is recorded on tags that don't have one yet.
+pub async fn get_or_create_tag(pool: &PgPool, tag: &NewTag) -> Result<i32> {
+    if let Some(id) = resolve_tag(pool, &tag.name).await? {
+        if tag.category.is_some() {
+            sqlx::query("UPDATE tags SET category = $2 WHERE id = $1 AND category IS NULL")
+                .bind(id)
+                .bind(tag.category)
+                .execute(pool)
+                .await?;
+        }
+        return Ok(id);
+    }
+
+    let id: (i32,) = sqlx::query_as(
+        r#"
+        INSERT INTO tags (name, slug, category)
+        VALUES (tag_name($1), tag_slug($1), $2)
+        ON CONFLICT (slug) DO UPDATE SET category = COALESCE(tags.category, EXCLUDED.category)
+        RETURNING id
+        "#,
+    )
+    .bind(&tag.name)
+    .bind(tag.category)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(id.0)
+}
+
+const TAG_SELECT: &str = r#"
+    SELECT
+        t.id, t.name, t.slug, t.category,
+        (SELECT COUNT(*) FROM article_tags at JOIN articles a ON a.id = at.article_id
+         WHERE at.tag_id = t.id AND a.deleted_at IS NULL) AS article_count,
+        ARRAY(SELECT alias FROM tag_aliases al WHERE al.tag_id = t.id ORDER BY alias) AS aliases
+    FROM tags t
+"#;
+
+/// Tags with their article counts, most used first, optionally limited to a
+/// category or to names and aliases containing `search`
+pub async fn get_tags(
+    pool: &PgPool,
+    category: Option<TagCategory>,
+    search: Option<&str>,
+    limit: i64,
+) -> Result<Vec<Tag>> {
+    let pattern = search.map(|q| format!("%{}%", escape_like(&q.to_lowercase())));
+    let tags = sqlx::query_as::<_, Tag>(&format!(
+        r#"{}
+        WHERE ($1::tag_category IS NULL OR t.category = $1)
+          AND ($2::text IS NULL OR t.slug LIKE $2
+               OR EXISTS (SELECT 1 FROM tag_aliases al WHERE al.tag_id = t.id AND al.alias LIKE $2))
+        ORDER BY article_count DESC, t.name
+        LIMIT $3
+        "#,
+        TAG_SELECT
+    ))
+    .bind(category)
+    .bind(pattern)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(tags)
+}
+
+pub async fn get_tag(pool: &PgPool, id: i32) -> Result<Option<Tag>> {
+    let tag = sqlx::query_as::<_, Tag>(&format!("{} WHERE t.id = $1", TAG_SELECT))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(tag)
+}
+
+/// Tag whose name or alias normalizes like `name`, if any
+pub async fn resolve_tag(pool: &PgPool, name: &str) -> Result<Option<i32>> {
+    let (id,): (Option<i32>,) = sqlx::query_as("SELECT resolve_tag($1)")
+        .bind(name)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(id)
+}
+
+/// Rename a tag, keeping its old name as an alias. Fails with a unique
+/// violation if another tag already has the new name.
+pub async fn rename_tag(pool: &PgPool, id: i32, name: &str) -> Result<bool> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO tag_aliases (alias, tag_id)
+        SELECT slug, id FROM tags WHERE id = $1 AND slug <> tag_slug($2)
+        ON CONFLICT (alias) DO UPDATE SET tag_id = EXCLUDED.tag_id
+        "#,
+    )
+    .bind(id)
+    .bind(name)
+    .execute(&mut *tx)
+    .await?;
+
+    // The new name is the tag itself now, not an alias
+    sqlx::query("DELETE FROM tag_aliases WHERE alias = tag_slug($1)")
+        .bind(name)
+        .execute(&mut *tx)
+        .await?;
+
+    let result =
+        sqlx::query("UPDATE tags SET name = tag_name($2), slug = tag_slug($2) WHERE id = $1")
+            .bind(id)
+            .bind(name)
+            .execute(&mut *tx)
+            .await?;
+
+    tx.commit().await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn set_tag_category(
+    pool: &PgPool,
+    id: i32,
+    category: Option<TagCategory>,
+) -> Result<()> {
+    sqlx::query("UPDATE tags SET category = $2 WHERE id = $1")
+        .bind(id)
+        .bind(category)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Move all articles and aliases of `from` to `into` and delete `from`, in
+/// one statement
+pub async fn merge_tags(pool: &PgPool, from: i32, into: i32) -> Result<()> {
+    sqlx::query("SELECT merge_tag($1, $2)")
+        .bind(from)
+        .bind(into)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Delete a tag; its article links and aliases go with it
+pub async fn delete_tag(pool: &PgPool, id: i32) -> Result<bool> {
+    let result = sqlx::query("DELETE FROM tags WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Add an alias for a tag. Returns false if the alias is already the name
+/// of a tag, which should be merged instead.
+pub async fn add_tag_alias(pool: &PgPool, id: i32, alias: &str) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO tag_aliases (alias, tag_id)
+        SELECT tag_slug($2), $1
+        WHERE NOT EXISTS (SELECT 1 FROM tags WHERE slug = tag_slug($2))
+        ON CONFLICT (alias) DO UPDATE SET tag_id = EXCLUDED.tag_id
+        "#,
+    )
+    .bind(id)
+    .bind(alias)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn delete_tag_alias(pool: &PgPool, alias: &str) -> Result<bool> {
+    let result = sqlx::query("DELETE FROM tag_aliases WHERE alias = tag_slug($1)")
+        .bind(alias)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Facet counts for the articles matching `filter`. Tags and authors are
+/// limited to the `limit` most common values.
+///
+/// Each facet is counted with its own selection left out of the filter, so
+/// picking a source still shows how many articles the other sources have.
+/// Exclusions and `tag_mode=all` tags narrow their own facet as usual.
+pub async fn get_article_facets(
+    pool: &PgPool,
+    filter: &ArticleFilter,
+    limit: i64,
+) -> Result<ArticleFacets> {
+    let without = |clear: fn(&mut ArticleFilter)| {
+        let mut filter = filter.clone();
+        clear(&mut filter);
+        filter
+    };
+
+    let mut sources =
+        QueryBuilder::new("SELECT a.source AS value, COUNT(*) AS count FROM articles a");
+    without(|f| f.sources.clear()).push_where(&mut sources);
+    sources.push(" GROUP BY 1 ORDER BY count DESC, value");
+
+    let mut tags = QueryBuilder::new(
+        "SELECT tg.name AS value, COUNT(*) AS count FROM articles a \
+         JOIN article_tags atg ON atg.article_id = a.id JOIN tags tg ON tg.id = atg.tag_id",
+    );
+    without(|f| {
+        if f.tag_mode == TagMode::Any {
+            f.tags.clear()
+        }
+    })
+    .push_where(&mut tags);
+    tags.push(" GROUP BY 1 ORDER BY count DESC, value LIMIT ")
+        .push_bind(limit);
+
+    let mut authors =
+        QueryBuilder::new("SELECT a.author AS value, COUNT(*) AS count FROM articles a");
+    without(|f| f.author = None).push_where(&mut authors);
+    authors
+        .push(" GROUP BY 1 ORDER BY count DESC, value LIMIT ")
+        .push_bind(limit);
+
+    let mut months = QueryBuilder::new(
+        "SELECT to_char(date_trunc('month', a.published_at), 'YYYY-MM') AS value, COUNT(*) AS count \
+         FROM articles a",
+    );
+    without(|f| {
+        f.published_after = None;
+        f.published_before = None;
+    })
+    .push_where(&mut months);
+    months.push(" GROUP BY 1 ORDER BY value DESC NULLS LAST");
+
+    let mut languages =
+        QueryBuilder::new("SELECT a.language AS value, COUNT(*) AS count FROM articles a");
+    without(|f| f.languages.clear()).push_where(&mut languages);
+    languages.push(" GROUP BY 1 ORDER BY count DESC, value");
+
+    // Articles without a source, author, publication date or detected language
+    // have no facet value
+    type Row = (Option<String>, i64);
+    let present = |rows: Vec<Row>| -> Vec<FacetCount> {
+        rows.into_iter()
+            .filter_map(|(value, count)| value.map(|value| FacetCount { value, count }))
+            .collect()
+    };
+
+    let (sources, tags, authors, months, languages) = tokio::try_join!(
+        sources.build_query_as::<Row>().fetch_all(pool),
+        tags.build_query_as::<FacetCount>().fetch_all(pool),
+        authors.build_query_as::<Row>().fetch_all(pool),
+        months.build_query_as::<Row>().fetch_all(pool),
+        languages.build_query_as::<Row>().fetch_all(pool),
+    )?;
+
+    Ok(ArticleFacets {
+        sources: present(sources),
+        tags,
+        authors: present(authors),
+        months: present(months),
+        languages: present(languages),
+    })
+}
+
+/// Highlighted snippets are computed for one page of results at a time, as
+/// ts_headline has to re-parse the whole document
+pub async fn get_search_highlights(
+    pool: &PgPool,
+    ids: &[Uuid],
+    search: &str,
+    options: &HighlightOptions,
+) -> Result<Vec<SearchHighlight>> {
+    let highlights: Vec<SearchHighlight> = sqlx::query_as(
+        r#"
+        SELECT
+            id,
+            search_headline(search_config(language), content, $2, $3, $4, $5) AS snippet,
+            search_headline(search_config(language), title, $2, $3, $4, 0) AS title
+        FROM articles
+        WHERE id = ANY($1)
+        "#,
+    )
+    .bind(ids)
+    .bind(search)
+    .bind(&options.start_sel)
+    .bind(&options.stop_sel)
+    .bind(options.max_fragments)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(highlights)
+}
+
+// Tag rule queries
+
+const TAG_RULE_SELECT: &str = r#"
+    SELECT
+        r.id, r.tag_id, t.name AS tag, r.kind, r.patterns, r.source, r.language,
+        r.case_sensitive, r.enabled, r.created_at, r.updated_at
+    FROM tag_rules r
+    JOIN tags t ON t.id = r.tag_id
+"#;
+
+pub async fn get_tag_rules(pool: &PgPool) -> Result<Vec<TagRule>> {
+    let rules =
+        sqlx::query_as::<_, TagRule>(&format!("{} ORDER BY t.name, r.id", TAG_RULE_SELECT))
+            .fetch_all(pool)
+            .await?;
+
+    Ok(rules)
+}
+
+pub async fn get_tag_rule(pool: &PgPool, id: i32) -> Result<Option<TagRule>> {
+    let rule = sqlx::query_as::<_, TagRule>(&format!("{} WHERE r.id = $1", TAG_RULE_SELECT))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(rule)
+}
+
+pub async fn create_tag_rule(pool: &PgPool, tag_id: i32, rule: &NewTagRule) -> Result<i32> {
+    let id: (i32,) = sqlx::query_as(
+        r#"
+        INSERT INTO tag_rules (tag_id, kind, patterns, source, language, case_sensitive, enabled)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id
+        "#,
+    )
+    .bind(tag_id)
+    .bind(rule.kind)
+    .bind(&rule.patterns)
+    .bind(&rule.source)
+    .bind(&rule.language)
+    .bind(rule.case_sensitive)
+    .bind(rule.enabled)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(id.0)
+}
+
+/// Replace a rule's definition. Tags it assigned before stay until rules are
+/// applied again.
+pub async fn update_tag_rule(
+    pool: &PgPool,
+    id: i32,
+    tag_id: i32,
+    rule: &NewTagRule,
+) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE tag_rules SET
+            tag_id = $2, kind = $3, patterns = $4, source = $5, language = $6,
+            case_sensitive = $7, enabled = $8, updated_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(tag_id)
+    .bind(rule.kind)
+    .bind(&rule.patterns)
+    .bind(&rule.source)
+    .bind(&rule.language)
+    .bind(rule.case_sensitive)
+    .bind(rule.enabled)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Delete a rule along with the tags it assigned
+pub async fn delete_tag_rule(pool: &PgPool, id: i32) -> Result<bool> {
+    let result = sqlx::query("DELETE FROM tag_rules WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Swap an article's rule tags for `matches` (`(rule_id, tag_id)` pairs).
+/// Tags the article already has from scraping or by hand keep that source,
+/// and tags removed by hand stay removed.
+pub async fn replace_rule_tags(
+    pool: &PgPool,
+    article_id: Uuid,
+    matches: &[(i32, i32)],
+) -> Result<()> {
+    let (rule_ids, tag_ids): (Vec<i32>, Vec<i32>) = matches.iter().copied().unzip();
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM article_tags WHERE article_id = $1 AND source = 'rule'")
+        .bind(article_id)
+        .execute(&mut *tx)
+        .await?;
+
+    if !matches.is_empty() {
+        sqlx::query(
+            r#"
+            INSERT INTO article_tags (article_id, tag_id, source, rule_id)
+            SELECT $1, m.tag_id, 'rule', m.rule_id
+            FROM unnest($2::int[], $3::int[]) AS m(rule_id, tag_id)
+            WHERE NOT EXISTS (
+                SELECT 1 FROM article_tag_removals r
+                WHERE r.article_id = $1 AND r.tag_id = m.tag_id
+            )
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(article_id)
+        .bind(&rule_ids)
+        .bind(&tag_ids)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Page through the source, title and plain-text content of all articles
+/// outside the trash, ordered by id
+pub async fn get_article_text_after(
+    pool: &PgPool,
+    after: Uuid,
+    limit: i64,
+) -> Result<Vec<(Uuid, String, String, String)>> {
+    let rows: Vec<(Uuid, String, String, String)> = sqlx::query_as(
+        r#"
+        SELECT id, COALESCE(source, ''), title, content FROM articles
+        WHERE id > $1 AND deleted_at IS NULL
+        ORDER BY id
+        LIMIT $2
+        "#,
+    )
+    .bind(after)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// An article's tags with where each came from
+pub async fn get_article_tags(pool: &PgPool, article_id: Uuid) -> Result<Vec<ArticleTag>> {
+    let tags: Vec<ArticleTag> = sqlx::query_as(
+        r#"
+        SELECT at.tag_id, t.name, at.source, at.rule_id
+        FROM article_tags at
+        JOIN tags t ON t.id = at.tag_id
+        WHERE at.article_id = $1
+        ORDER BY t.name
+        "#,
+    )
+    .bind(article_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(tags)
+}
+
+// Entity queries
+
+/// Upsert the gazetteer's entities and delete those no longer in it.
+/// Returns entity ids by slug.
+pub async fn sync_entities(
+    pool: &PgPool,
+    entries: &[GazetteerEntry],
+) -> Result<HashMap<String, i32>> {
+    let mut tx = pool.begin().await?;
+    let mut ids = HashMap::new();
+
+    for entry in entries {
+        let (id,): (i32,) = sqlx::query_as(
+            r#"
+            INSERT INTO entities (slug, name, kind, aliases)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (slug) DO UPDATE SET
+                name = EXCLUDED.name, kind = EXCLUDED.kind, aliases = EXCLUDED.aliases,
+                updated_at = CASE
+                    WHEN (entities.name, entities.kind, entities.aliases)
+                        IS DISTINCT FROM (EXCLUDED.name, EXCLUDED.kind, EXCLUDED.aliases)
+                    THEN NOW() ELSE entities.updated_at
+                END
+            RETURNING id
+            "#,
+        )
+        .bind(&entry.slug)
+        .bind(&entry.name)
+        .bind(entry.kind)
+        .bind(&entry.aliases)
+        .fetch_one(&mut *tx)
+        .await?;
+        ids.insert(entry.slug.clone(), id);
+    }
+
+    let slugs: Vec<&str> = entries.iter().map(|entry| entry.slug.as_str()).collect();
+    sqlx::query("DELETE FROM entities WHERE slug <> ALL($1)")
+        .bind(&slugs)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(ids)
+}
+
+/// Articles not yet checked against this gazetteer version, or changed since
+pub async fn get_articles_pending_entities(
+    pool: &PgPool,
+    version: &str,
+    limit: i64,
+) -> Result<Vec<(Uuid, String, String)>> {
+    let rows: Vec<(Uuid, String, String)> = sqlx::query_as(
+        r#"
+        SELECT id, title, content FROM articles
+        WHERE entities_version IS DISTINCT FROM $1
+        LIMIT $2
+        "#,
+    )
+    .bind(version)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Replace an article's entity mentions and mark it checked against `version`
+pub async fn replace_article_entities(
+    pool: &PgPool,
+    article_id: Uuid,
+    mentions: &Mentions,
+    version: &str,
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM article_entities WHERE article_id = $1")
+        .bind(article_id)
+        .execute(&mut *tx)
+        .await?;
+
+    if !mentions.is_empty() {
+        let entity_ids: Vec<i32> = mentions.iter().map(|(id, _, _)| *id).collect();
+        let counts: Vec<i32> = mentions.iter().map(|(_, count, _)| *count).collect();
+        let in_title: Vec<bool> = mentions.iter().map(|(_, _, in_title)| *in_title).collect();
+
+        sqlx::query(
+            r#"
+            INSERT INTO article_entities (article_id, entity_id, mentions, in_title)
+            SELECT $1, m.entity_id, m.mentions, m.in_title
+            FROM unnest($2::int[], $3::int[], $4::bool[]) AS m(entity_id, mentions, in_title)
+            "#,
+        )
+        .bind(article_id)
+        .bind(&entity_ids)
+        .bind(&counts)
+        .bind(&in_title)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    sqlx::query("UPDATE articles SET entities_version = $2 WHERE id = $1")
+        .bind(article_id)
+        .bind(version)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+const ENTITY_SELECT: &str = r#"
+    SELECT
+        e.id, e.slug, e.name, e.kind, e.aliases,
+        COUNT(a.id) AS article_count,
+        MAX(COALESCE(a.published_at, a.scraped_at)) AS last_seen
+    FROM entities e
+    LEFT JOIN article_entities ae ON ae.entity_id = e.id
+    LEFT JOIN articles a ON a.id = ae.article_id AND a.deleted_at IS NULL
+        AND ($1::timestamptz IS NULL OR COALESCE(a.published_at, a.scraped_at) >= $1)
+"#;
+
+/// Entities with the number of articles mentioning them since `since`, most
+/// mentioned first, optionally limited to a kind or to names and aliases
+/// containing `search`
+pub async fn get_entities(
+    pool: &PgPool,
+    kind: Option<EntityKind>,
+    search: Option<&str>,
+    since: Option<DateTime<Utc>>,
+    limit: i64,
+) -> Result<Vec<Entity>> {
+    let pattern = search.map(|q| format!("%{}%", escape_like(q)));
+    let entities = sqlx::query_as::<_, Entity>(&format!(
+        r#"{}
+        WHERE ($2::entity_kind IS NULL OR e.kind = $2)
+          AND ($3::text IS NULL OR e.name ILIKE $3
+               OR EXISTS (SELECT 1 FROM unnest(e.aliases) alias WHERE alias ILIKE $3))
+        GROUP BY e.id
+        ORDER BY article_count DESC, e.name
+        LIMIT $4
+        "#,
+        ENTITY_SELECT
+    ))
+    .bind(since)
+    .bind(kind)
+    .bind(pattern)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(entities)
+}
+
+/// An entity by id, slug or alias (ignoring case and a trailing `*`), with
+/// its article count since `since`
+pub async fn get_entity(
+    pool: &PgPool,
+    key: &str,
+    since: Option<DateTime<Utc>>,
+) -> Result<Option<Entity>> {
+    let entity = sqlx::query_as::<_, Entity>(&format!(
+        r#"{}
+        WHERE e.id::text = $2 OR e.slug = lower($2)
+           OR EXISTS (SELECT 1 FROM unnest(e.aliases) alias
+                      WHERE lower(rtrim(alias, '*')) = lower($2))
+        GROUP BY e.id
+        ORDER BY e.id::text = $2 DESC, e.slug = lower($2) DESC
+        LIMIT 1
+        "#,
+        ENTITY_SELECT
+    ))
+    .bind(since)
+    .bind(key)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(entity)
+}
+
+/// Articles mentioning an entity since `since`, newest first: ids with the
+/// mention count and whether the title mentions it, and the total
+pub async fn get_entity_articles(
+    pool: &PgPool,
+    entity_id: i32,
+    since: Option<DateTime<Utc>>,
+    page: i64,
+    per_page: i64,
+) -> Result<(Vec<(Uuid, i32, bool)>, i64)> {
+    let offset = (page - 1) * per_page;
+
+    let rows: Vec<(Uuid, i32, bool)> = sqlx::query_as(
+        r#"
+        SELECT a.id, ae.mentions, ae.in_title
+        FROM article_entities ae
+        JOIN articles a ON a.id = ae.article_id
+        WHERE ae.entity_id = $1 AND a.deleted_at IS NULL
+          AND ($2::timestamptz IS NULL OR COALESCE(a.published_at, a.scraped_at) >= $2)
+        ORDER BY COALESCE(a.published_at, a.scraped_at) DESC, a.id
+        LIMIT $3 OFFSET $4
+        "#,
+    )
+    .bind(entity_id)
+    .bind(since)
+    .bind(per_page)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    let total: (i64,) = sqlx::query_as(
+        r#"
+        SELECT COUNT(*)
+        FROM article_entities ae
+        JOIN articles a ON a.id = ae.article_id
+        WHERE ae.entity_id = $1 AND a.deleted_at IS NULL
+          AND ($2::timestamptz IS NULL OR COALESCE(a.published_at, a.scraped_at) >= $2)
+        "#,
+    )
+    .bind(entity_id)
+    .bind(since)
+    .fetch_one(pool)
+    .await?;
+
+    Ok((rows, total.0))
+}
+
+/// Articles mentioning an entity per `interval` ('day', 'week' or 'month'),
+/// oldest first. Periods without articles are left out.
+pub async fn get_entity_timeline(
+    pool: &PgPool,
+    entity_id: i32,
+    since: Option<DateTime<Utc>>,
+    interval: &str,
+) -> Result<Vec<TimelinePoint>> {
+    let points: Vec<TimelinePoint> = sqlx::query_as(
+        r#"
+        SELECT date_trunc($3, COALESCE(a.published_at, a.scraped_at)) AS date, COUNT(*) AS count
+        FROM article_entities ae
+        JOIN articles a ON a.id = ae.article_id
+        WHERE ae.entity_id = $1 AND a.deleted_at IS NULL
+          AND ($2::timestamptz IS NULL OR COALESCE(a.published_at, a.scraped_at) >= $2)
+        GROUP BY 1
+        ORDER BY 1
+        "#,
+    )
+    .bind(entity_id)
+    .bind(since)
+    .bind(interval)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(points)
+}
+
+// Search queries
+
+/// Titles, tags and authors containing `q` or similar to it. Matches that
+/// start with `q` come first, then the most viewed or most used.
+pub async fn get_suggestions(pool: &PgPool, q: &str, limit: i64) -> Result<Suggestions> {
+    let escaped = escape_like(q);
+    let contains = format!("%{}%", escaped);
+    let prefix = format!("{}%", escaped);
+
+    let titles = sqlx::query_as::<_, TitleSuggestion>(
+        r#"
+        SELECT id, title, view_count FROM articles
+        WHERE deleted_at IS NULL
+          AND (title ILIKE $1 OR $2 <% cjk_strip(title))
+        ORDER BY title ILIKE $3 DESC, view_count DESC NULLS LAST,
+            word_similarity($2, cjk_strip(title)) DESC
+        LIMIT $4
+        "#,
+    )
+    .bind(&contains)
+    .bind(q)
+    .bind(&prefix)
+    .bind(limit)
+    .fetch_all(pool);
+
+    // Tag names are short, so each word of `q` is compared on its own
+    let mut tags = QueryBuilder::new(
+        r#"
+        SELECT t.name AS value, COUNT(at.article_id) AS count FROM tags t
+        JOIN article_tags at ON at.tag_id = t.id
+        JOIN articles a ON a.id = at.article_id AND a.deleted_at IS NULL
+        WHERE t.name ILIKE "#,
+    );
+    tags.push_bind(&contains);
+    for word in q.split_whitespace() {
+        tags.push(" OR t.name % ").push_bind(word);
+    }
+    tags.push(" GROUP BY t.name ORDER BY t.name ILIKE ")
+        .push_bind(&prefix)
+        .push(" DESC, count DESC, similarity(t.name, ")
+        .push_bind(q)
+        .push(") DESC LIMIT ")
+        .push_bind(limit);
+    let tags = tags.build_query_as::<FacetCount>().fetch_all(pool);
+
+    let authors = sqlx::query_as::<_, FacetCount>(
+        r#"
+        SELECT author AS value, COUNT(*) AS count FROM articles
+        WHERE deleted_at IS NULL AND (author ILIKE $1 OR author % $2)
+        GROUP BY author
+        ORDER BY author ILIKE $3 DESC, count DESC, similarity(author, $2) DESC
+        LIMIT $4
+        "#,
+    )
+    .bind(&contains)
+    .bind(q)
+    .bind(&prefix)
+    .bind(limit)
+    .fetch_all(pool);
+
+    let (titles, tags, authors) = tokio::try_join!(titles, tags, authors)?;
+
+    Ok(Suggestions {
+        titles,
+        tags,
+        authors,
+    })
+}
+
+/// Escape `LIKE` wildcards in user input
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+// Embedding queries
+
+/// Articles without an embedding from `model` for their current content:
+/// (id, title, content, content_hash)
+pub async fn get_articles_missing_embedding(
+    pool: &PgPool,
+    model: &str,
+    limit: i64,
+) -> Result<Vec<(Uuid, String, String, Option<String>)>> {
+    let rows = sqlx::query_as(
+        r#"
+        SELECT a.id, a.title, a.content, a.content_hash
+        FROM articles a
+        LEFT JOIN article_embeddings e ON e.article_id = a.id
+        WHERE a.deleted_at IS NULL
+          AND (e.article_id IS NULL
+               OR e.model <> $1
+               OR e.content_hash IS DISTINCT FROM a.content_hash)
+        ORDER BY a.scraped_at DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(model)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+pub async fn upsert_article_embedding(
+    pool: &PgPool,
+    article_id: Uuid,
+    model: &str,
+    content_hash: Option<&str>,
+    embedding: &[f32],
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO article_embeddings (article_id, model, content_hash, embedding)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (article_id) DO UPDATE SET
+            model = EXCLUDED.model,
+            content_hash = EXCLUDED.content_hash,
+            embedding = EXCLUDED.embedding,
+            created_at = NOW()
+        "#,
+    )
+    .bind(article_id)
+    .bind(model)
+    .bind(content_hash)
+    .bind(embedding)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Embeddings of every article outside the trash
+pub async fn get_article_embeddings(pool: &PgPool, model: &str) -> Result<Vec<(Uuid, Vec<f32>)>> {
+    let rows = sqlx::query_as(
+        r#"
+        SELECT e.article_id, e.embedding
+        FROM article_embeddings e
+        JOIN articles a ON a.id = e.article_id AND a.deleted_at IS NULL
+        WHERE e.model = $1
+        "#,
+    )
+    .bind(model)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+pub async fn get_article_embedding(pool: &PgPool, id: Uuid, model: &str) -> Result<Option<Vec<f32>>> {
+    let row: Option<(Vec<f32>,)> =
+        sqlx::query_as("SELECT embedding FROM article_embeddings WHERE article_id = $1 AND model = $2")
+            .bind(id)
+            .bind(model)
+            .fetch_optional(pool)
+            .await?;
+
+    Ok(row.map(|(embedding,)| embedding))
+}
+
+// Cluster queries
+
+/// Articles not yet compared against others for clustering, oldest first
+pub async fn get_articles_pending_cluster(pool: &PgPool, limit: i64) -> Result<Vec<Uuid>> {
+    let ids: Vec<(Uuid,)> = sqlx::query_as(
+        r#"
+        SELECT id FROM articles
+        WHERE NOT cluster_checked AND simhash IS NOT NULL
+        ORDER BY scraped_at
+        LIMIT $1
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ids.into_iter().map(|(id,)| id).collect())
+}
+
+/// Articles outside the trash within `max_distance` SimHash bits of `id`,
+/// published within `window_days` of it
+pub async fn get_near_duplicates(
+    pool: &PgPool,
+    id: Uuid,
+    max_distance: i32,
+    window_days: i32,
+) -> Result<Vec<Uuid>> {
+    let ids: Vec<(Uuid,)> = sqlx::query_as(
+        r#"
+        SELECT b.id
+        FROM articles a
+        JOIN articles b ON b.id <> a.id
+            AND b.simhash IS NOT NULL
+            AND b.deleted_at IS NULL
+            AND COALESCE(b.published_at, b.scraped_at)
+                BETWEEN COALESCE(a.published_at, a.scraped_at) - make_interval(days => $3)
+                    AND COALESCE(a.published_at, a.scraped_at) + make_interval(days => $3)
+        WHERE a.id = $1
+          AND bit_count((a.simhash # b.simhash)::bit(64)) <= $2
+        "#,
+    )
+    .bind(id)
+    .bind(max_distance)
+    .bind(window_days)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ids.into_iter().map(|(id,)| id).collect())
+}
+
+/// Put `id` and `matches` in one cluster, merging any clusters they already
+/// belong to into the oldest of them, and mark `id` as checked
+pub async fn merge_story_cluster(pool: &PgPool, id: Uuid, matches: &[Uuid]) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    if !matches.is_empty() {
+        let mut members = matches.to_vec();
+        members.push(id);
+
+        let existing: Vec<(Uuid,)> = sqlx::query_as(
+            r#"
+            SELECT c.id FROM story_clusters c
+            WHERE EXISTS (SELECT 1 FROM articles a WHERE a.cluster_id = c.id AND a.id = ANY($1))
+            ORDER BY c.created_at, c.id
+            "#,
+        )
+        .bind(&members)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let cluster_id = match existing.first() {
+            Some((cluster_id,)) => *cluster_id,
+            None => {
+                let (cluster_id,): (Uuid,) =
+                    sqlx::query_as("INSERT INTO story_clusters DEFAULT VALUES RETURNING id")
+                        .fetch_one(&mut *tx)
+                        .await?;
+                cluster_id
+            }
+        };
+        let merged: Vec<Uuid> = existing.iter().skip(1).map(|(id,)| *id).collect();
+
+        sqlx::query("UPDATE articles SET cluster_id = $1 WHERE id = ANY($2) OR cluster_id = ANY($3)")
+            .bind(cluster_id)
+            .bind(&members)
+            .bind(&merged)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM story_clusters WHERE id = ANY($1)")
+            .bind(&merged)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE story_clusters SET updated_at = NOW() WHERE id = $1")
+            .bind(cluster_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    sqlx::query("UPDATE articles SET cluster_checked = TRUE WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Clusters with at least `min_size` articles, most recently active first
+pub async fn get_story_clusters(
+    pool: &PgPool,
+    page: i64,
+    per_page: i64,
+    min_size: i64,
+) -> Result<(Vec<StoryClusterRow>, i64)> {
+    let (total,): (i64,) = sqlx::query_as(
+        r#"
+        SELECT COUNT(*) FROM (
+            SELECT cluster_id FROM articles WHERE cluster_id IS NOT NULL AND deleted_at IS NULL
+            GROUP BY cluster_id HAVING COUNT(*) >= $1
+        ) clusters
+        "#,
+    )
+    .bind(min_size)
+    .fetch_one(pool)
+    .await?;
+
+    let clusters = sqlx::query_as::<_, StoryClusterRow>(
+        r#"
+        SELECT
+            a.cluster_id AS id,
+            COUNT(*) AS article_count,
+            array_agg(DISTINCT COALESCE(a.source, 'AIBase')) AS sources,
+            MIN(COALESCE(a.published_at, a.scraped_at)) AS first_seen,
+            MAX(COALESCE(a.published_at, a.scraped_at)) AS last_seen
+        FROM articles a
+        WHERE a.cluster_id IS NOT NULL AND a.deleted_at IS NULL
+        GROUP BY a.cluster_id
+        HAVING COUNT(*) >= $1
+        ORDER BY last_seen DESC, a.cluster_id
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(min_size)
+    .bind(per_page)
+    .bind((page - 1) * per_page)
+    .fetch_all(pool)
+    .await?;
+
+    Ok((clusters, total))
+}
+
+pub async fn get_story_cluster(pool: &PgPool, id: Uuid) -> Result<Option<StoryClusterRow>> {
+    let cluster = sqlx::query_as::<_, StoryClusterRow>(
+        r#"
+        SELECT
+            a.cluster_id AS id,
+            COUNT(*) AS article_count,
+            array_agg(DISTINCT COALESCE(a.source, 'AIBase')) AS sources,
+            MIN(COALESCE(a.published_at, a.scraped_at)) AS first_seen,
+            MAX(COALESCE(a.published_at, a.scraped_at)) AS last_seen
+        FROM articles a
+        WHERE a.cluster_id = $1 AND a.deleted_at IS NULL
+        GROUP BY a.cluster_id
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(cluster)
+}
+
+/// Article ids of the given clusters, each cluster's earliest report first
+pub async fn get_cluster_members(pool: &PgPool, cluster_ids: &[Uuid]) -> Result<Vec<(Uuid, Uuid)>> {
+    let members = sqlx::query_as(
+        r#"
+        SELECT cluster_id, id FROM articles
+        WHERE cluster_id = ANY($1) AND deleted_at IS NULL
+        ORDER BY cluster_id, COALESCE(published_at, scraped_at), id
+        "#,
+    )
+    .bind(cluster_ids)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(members)
+}
+
+/// Members of the clusters the given articles belong to, in the order of
+/// `get_cluster_members`
+pub async fn get_clusters_containing(pool: &PgPool, article_ids: &[Uuid]) -> Result<Vec<(Uuid, Uuid)>> {
+    let members = sqlx::query_as(
+        r#"
+        SELECT cluster_id, id FROM articles
+        WHERE cluster_id IN (SELECT cluster_id FROM articles WHERE id = ANY($1))
+          AND deleted_at IS NULL
+        ORDER BY cluster_id, COALESCE(published_at, scraped_at), id
+        "#,
+    )
+    .bind(article_ids)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(members)
+}
+
+// Link queries
+
+pub async fn replace_article_links(pool: &PgPool, article_id: Uuid, links: &[NewLink]) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM article_links WHERE article_id = $1")
+        .bind(article_id)
+        .execute(&mut *tx)
+        .await?;
+
+    for link in links {
+        sqlx::query(
+            r#"
+            INSERT INTO article_links (article_id, url, domain, anchor_text, position)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+        )
+        .bind(article_id)
+        .bind(&link.url)
+        .bind(&link.domain)
+        .bind(&link.anchor_text)
+        .bind(link.position)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Most-linked domains across articles published in the last `days` days
+pub async fn get_top_linked_domains(pool: &PgPool, days: i64, limit: i64) -> Result<Vec<DomainStat>> {
+    let since = Utc::now() - Duration::days(days);
+    let stats = sqlx::query_as::<_, DomainStat>(
+        r#"
+        SELECT l.domain, COUNT(*) AS link_count, COUNT(DISTINCT l.article_id) AS article_count
+        FROM article_links l
+        JOIN articles a ON a.id = l.article_id AND a.deleted_at IS NULL
+        WHERE COALESCE(a.published_at, a.scraped_at) >= $1
+        GROUP BY l.domain
+        ORDER BY article_count DESC, link_count DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(since)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(stats)
+}
+
+/// Most-linked URLs across articles published in the last `days` days
+pub async fn get_top_linked_urls(
+    pool: &PgPool,
+    days: i64,
+    limit: i64,
+    domain: Option<&str>,
+) -> Result<Vec<UrlStat>> {
+    let since = Utc::now() - Duration::days(days);
+    let stats = sqlx::query_as::<_, UrlStat>(
+        r#"
+        SELECT l.url, MIN(l.domain) AS domain, COUNT(*) AS link_count,
+               COUNT(DISTINCT l.article_id) AS article_count
+        FROM article_links l
+        JOIN articles a ON a.id = l.article_id AND a.deleted_at IS NULL
+        WHERE COALESCE(a.published_at, a.scraped_at) >= $1
+          AND ($3::text IS NULL OR l.domain = $3)
+        GROUP BY l.url
+        ORDER BY article_count DESC, link_count DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(since)
+    .bind(limit)
+    .bind(domain)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(stats)
+}
+
+/// Articles linking to an exact URL or to anything on a domain
+pub async fn get_articles_linking_to(
+    pool: &PgPool,
+    url: Option<&str>,
+    domain: Option<&str>,
+    limit: i64,
+) -> Result<Vec<LinkingArticle>> {
+    let articles = sqlx::query_as::<_, LinkingArticle>(
+        r#"
+        SELECT * FROM (
+            SELECT DISTINCT ON (a.id)
+                a.id AS article_id, a.title, a.source, a.published_at, l.url, l.anchor_text
+            FROM article_links l
+            JOIN articles a ON a.id = l.article_id AND a.deleted_at IS NULL
+            WHERE ($1::text IS NULL OR l.url = $1)
+              AND ($2::text IS NULL OR l.domain = $2)
+            ORDER BY a.id, l.position
+        ) linking
+        ORDER BY published_at DESC NULLS LAST
+        LIMIT $3
+        "#,
+    )
+    .bind(url)
+    .bind(domain)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(articles)
+}
+
+// Asset queries
+
+/// Record the assets an article's current content uses, dropping rows for
+/// images it no longer references
+pub async fn replace_article_assets(pool: &PgPool, article_id: Uuid, assets: &[NewAsset]) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    let urls: Vec<&str> = assets.iter().map(|a| a.original_url.as_str()).collect();
+    sqlx::query("DELETE FROM article_assets WHERE article_id = $1 AND original_url <> ALL($2)")
+        .bind(article_id)
+        .bind(&urls)
+        .execute(&mut *tx)
+        .await?;
+
+    for asset in assets {
+        sqlx::query(
+            r#"
+            INSERT INTO article_assets (
+                article_id, kind, original_url, hash, content_type, byte_size, width, height
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (article_id, original_url) DO UPDATE SET
+                kind = EXCLUDED.kind, hash = EXCLUDED.hash, content_type = EXCLUDED.content_type,
+                byte_size = EXCLUDED.byte_size, width = EXCLUDED.width, height = EXCLUDED.height
+            "#,
+        )
+        .bind(article_id)
+        .bind(asset.kind)
+        .bind(&asset.original_url)
+        .bind(&asset.hash)
+        .bind(&asset.content_type)
+        .bind(asset.byte_size)
+        .bind(asset.width)
+        .bind(asset.height)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Most recent mirror of a remote URL, used to skip re-downloading on rescrape
+pub async fn get_asset_by_original_url(pool: &PgPool, url: &str) -> Result<Option<ArticleAsset>> {
+    let asset = sqlx::query_as::<_, ArticleAsset>(
+        "SELECT * FROM article_assets WHERE original_url = $1 ORDER BY created_at DESC LIMIT 1",
+    )
+    .bind(url)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(asset)
+}
+
+/// Hashes of every recorded asset, to tell which mirrored files are still used
+pub async fn get_asset_hashes(pool: &PgPool) -> Result<Vec<String>> {
+    let hashes: Vec<(String,)> = sqlx::query_as("SELECT DISTINCT hash FROM article_assets")
+        .fetch_all(pool)
+        .await?;
+
+    Ok(hashes.into_iter().map(|(hash,)| hash).collect())
+}
+
+pub async fn get_asset_by_hash(pool: &PgPool, hash: &str) -> Result<Option<ArticleAsset>> {
+    let asset = sqlx::query_as::<_, ArticleAsset>("SELECT * FROM article_assets WHERE hash = $1 LIMIT 1")
+        .bind(hash)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(asset)
+}
+
+// Scrape run queries
+
+pub async fn create_scrape_run(
+    pool: &PgPool,
+    scrape_type: ScrapeType,
+    config: Option<serde_json::Value>,
+) -> Result<Uuid> {
+    let id: (Uuid,) = sqlx::query_as(
+        "INSERT INTO scrape_runs (scrape_type, config) VALUES ($1, $2) RETURNING id",
+    )
+    .bind(scrape_type)
+    .bind(config)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(id.0)
+}
+
+pub async fn update_scrape_run_progress(
+    pool: &PgPool,
+    id: Uuid,
+    pages_scraped: i32,
+    articles_found: i32,
+    articles_new: i32,
+    articles_failed: i32,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE scrape_runs SET
+            pages_scraped = $2, articles_found = $3, articles_new = $4, articles_failed = $5
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(pages_scraped)
+    .bind(articles_found)
+    .bind(articles_new)
+    .bind(articles_failed)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn complete_scrape_run(pool: &PgPool, id: Uuid, status: ScrapeStatus) -> Result<()> {
+    sqlx::query("UPDATE scrape_runs SET status = $2, completed_at = NOW() WHERE id = $1")
+        .bind(id)
+        .bind(status)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn get_scrape_runs(pool: &PgPool, limit: i64) -> Result<Vec<ScrapeRun>> {
+    let runs = sqlx::query_as::<_, ScrapeRun>(
+        "SELECT * FROM scrape_runs ORDER BY started_at DESC LIMIT $1",
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(runs)
+}
+
+/// Sort tag of scrape run cursors
+pub const RUNS_CURSOR_SORT: &str = "started:desc";
+
+/// Keyset-paginated runs, newest first, after the run at (`started_at`, `id`)
+pub async fn get_scrape_runs_after(
+    pool: &PgPool,
+    limit: i64,
+    after: Option<(DateTime<Utc>, Uuid)>,
+) -> Result<(Vec<ScrapeRun>, Option<Cursor>)> {
+    let mut runs = match after {
+        Some((started_at, id)) => {
+            sqlx::query_as::<_, ScrapeRun>(
+                r#"
+                SELECT * FROM scrape_runs
+                WHERE (started_at, id) < ($1, $2)
+                ORDER BY started_at DESC, id DESC
+                LIMIT $3
+                "#,
+            )
+            .bind(started_at)
+            .bind(id)
+            .bind(limit + 1)
+            .fetch_all(pool)
+            .await?
+        }
+        None => {
+            sqlx::query_as::<_, ScrapeRun>(
+                "SELECT * FROM scrape_runs ORDER BY started_at DESC, id DESC LIMIT $1",
+            )
+            .bind(limit + 1)
+            .fetch_all(pool)
+            .await?
+        }
+    };
+
+    let next = if runs.len() as i64 > limit {
+        runs.truncate(limit as usize);
+        runs.last().map(|run| Cursor {
+            sort: RUNS_CURSOR_SORT.to_string(),
+            keys: vec![serde_json::json!(run.started_at)],
+            id: run.id,
+        })
+    } else {
+        None
+    };
+
+    Ok((runs, next))
+}
+
+/// Planner estimate of the number of scrape runs
+pub async fn estimate_scrape_runs(pool: &PgPool) -> Result<i64> {
+    estimate_rows(pool, QueryBuilder::new("EXPLAIN SELECT 1 FROM scrape_runs")).await
+}
+
+pub async fn get_running_scrape(pool: &PgPool) -> Result<Option<ScrapeRun>> {
+    let run = sqlx::query_as::<_, ScrapeRun>(
+        "SELECT * FROM scrape_runs WHERE status = 'running' ORDER BY started_at DESC LIMIT 1",
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(run)
+}
+
+// Stats queries
+
+pub async fn get_stats(pool: &PgPool) -> Result<Stats> {
+    let today = Utc::now().date_naive();
+    let week_ago = Utc::now() - Duration::days(7);
+
+    let (
+        total_articles,
+        articles_today,
+        articles_this_week,
+        trashed_articles,
+        archived_articles,
+        last_scrape,
+        total_scrape_runs,
+    ): (i64, i64, i64, i64, i64, Option<DateTime<Utc>>, i64) = sqlx::query_as(
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM articles WHERE deleted_at IS NULL),
+            (SELECT COUNT(*) FROM articles WHERE deleted_at IS NULL AND DATE(scraped_at) = $1),
+            (SELECT COUNT(*) FROM articles WHERE deleted_at IS NULL AND scraped_at >= $2),
+            (SELECT COUNT(*) FROM articles WHERE deleted_at IS NOT NULL),
+            (SELECT COUNT(*) FROM archived_articles),
+            (SELECT started_at FROM scrape_runs WHERE status = 'completed'
+             ORDER BY started_at DESC LIMIT 1),
+            (SELECT COUNT(*) FROM scrape_runs)
+        "#,
+    )
+    .bind(today)
+    .bind(week_ago)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(Stats {
+        total_articles,
+        articles_today,
+        articles_this_week,
+        trashed_articles,
+        archived_articles,
+        last_scrape,
+        total_scrape_runs,
+    })
+}
+
+pub async fn get_tag_stats(pool: &PgPool, limit: i64) -> Result<Vec<TagStat>> {
+    let stats: Vec<(String, i64)> = sqlx::query_as(
+        r#"
+        SELECT t.name, COUNT(*) as count FROM tags t
+        JOIN article_tags at ON t.id = at.tag_id
+        JOIN articles a ON a.id = at.article_id AND a.deleted_at IS NULL
+        GROUP BY t.name
+        ORDER BY count DESC
+        LIMIT $1
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(stats
+        .into_iter()
+        .map(|(name, count)| TagStat { name, count })
+        .collect())
+}
+
+// Backfill queries
+
+pub async fn backfill_completed(pool: &PgPool, name: &str) -> Result<bool> {
+    let result: (bool,) = sqlx::query_as("SELECT EXISTS(SELECT 1 FROM backfills WHERE name = $1)")
+        .bind(name)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(result.0)
+}
+
+pub async fn mark_backfill_completed(pool: &PgPool, name: &str) -> Result<()> {
+    sqlx::query("INSERT INTO backfills (name) VALUES ($1) ON CONFLICT (name) DO UPDATE SET completed_at = NOW()")
+        .bind(name)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn get_articles_missing_simhash(
+    pool: &PgPool,
+    limit: i64,
+) -> Result<Vec<(Uuid, String, String)>> {
+    let rows = sqlx::query_as("SELECT id, title, content FROM articles WHERE simhash IS NULL LIMIT $1")
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows)
+}
+
+pub async fn set_article_simhash(pool: &PgPool, id: Uuid, simhash: i64) -> Result<()> {
+    sqlx::query("UPDATE articles SET simhash = $2 WHERE id = $1")
+        .bind(id)
+        .bind(simhash)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Page through the plain-text content of articles whose excerpt wasn't
+/// edited by hand, ordered by id
+pub async fn get_article_content_after(
+    pool: &PgPool,
+    after: Uuid,
+    limit: i64,
+) -> Result<Vec<(Uuid, String)>> {
+    let rows = sqlx::query_as(
+        r#"
+        SELECT id, content FROM articles
+        WHERE id > $1 AND NOT 'excerpt' = ANY(manual_fields)
+        ORDER BY id
+        LIMIT $2
+        "#,
+    )
+    .bind(after)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Replace a generated excerpt; a hand-edited one is kept
+pub async fn set_article_excerpt(pool: &PgPool, id: Uuid, excerpt: Option<&str>) -> Result<()> {
+    sqlx::query(
+        "UPDATE articles SET excerpt = $2 WHERE id = $1 AND NOT 'excerpt' = ANY(manual_fields)",
+    )
+    .bind(id)
+    .bind(excerpt)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_articles_missing_language(
+    pool: &PgPool,
+    limit: i64,
+) -> Result<Vec<(Uuid, String, String)>> {
+    let rows =
+        sqlx::query_as("SELECT id, title, content FROM articles WHERE language IS NULL LIMIT $1")
+            .bind(limit)
+            .fetch_all(pool)
+            .await?;
+
+    Ok(rows)
+}
+
+/// Store detected languages and the reading times estimated for them,
+/// without bumping `updated_at`
+pub async fn set_articles_language(pool: &PgPool, rows: &[(Uuid, &str, i32)]) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("ALTER TABLE articles DISABLE TRIGGER articles_updated_at")
+        .execute(&mut *tx)
+        .await?;
+
+    for (id, language, read_time_minutes) in rows {
+        sqlx::query("UPDATE articles SET language = $2, read_time_minutes = $3 WHERE id = $1")
+            .bind(id)
+            .bind(language)
+            .bind(read_time_minutes)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    sqlx::query("ALTER TABLE articles ENABLE TRIGGER articles_updated_at")
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+// Settings queries
 
 pub async fn get_all_settings(pool: &PgPool) -> Result<Vec<ScraperSetting>> {
     let settings = sqlx::query_as::<_, ScraperSetting>("SELECT * FROM scraper_settings")
@@ -508,6 +2578,22 @@ pub async fn update_setting(pool: &PgPool, key: &str, value: serde_json::Value)
     Ok(result.rows_affected() > 0)
 }
 
+/// Store a setting, creating it if it doesn't exist yet
+pub async fn set_setting(pool: &PgPool, key: &str, value: serde_json::Value) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO scraper_settings (key, value) VALUES ($1, $2)
+        ON CONFLICT (key) DO UPDATE SET value = EXCLUDED.value, updated_at = NOW()
+        "#,
+    )
+    .bind(key)
+    .bind(value)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
 // Source queries
 
 pub fn get_sources() -> Vec<SourceInfo> {
@@ -0,0 +1,432 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Postgres, QueryBuilder};
+
+use super::filter::ArticleFilter;
+use crate::scraper::Source;
+
+/// Minimum pg_trgm similarity for a fuzzy title or tag match. Common typos
+/// ("Gemeni", "Lama") score 0.4-0.6 against the intended word. Set as the
+/// threshold of the `%` and `<%` operators on every connection.
+pub const FUZZY_THRESHOLD: f32 = 0.4;
+
+/// A single word, `"quoted phrase"` or `prefix*` of a search
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchTerm {
+    pub text: String,
+    pub phrase: bool,
+    pub prefix: bool,
+    pub negated: bool,
+}
+
+/// Parsed full-text part of a search: every clause must match, and a clause
+/// matches if any of its terms (joined with `OR`) does
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchQuery {
+    pub clauses: Vec<Vec<SearchTerm>>,
+}
+
+impl SearchQuery {
+    pub fn is_empty(&self) -> bool {
+        self.clauses.is_empty()
+    }
+
+    /// Append this query as a tsquery expression. Term text is always bound,
+    /// never spliced into the SQL.
+    pub fn push_tsquery(&self, qb: &mut QueryBuilder<'_, Postgres>) {
+        qb.push("(");
+        for (i, clause) in self.clauses.iter().enumerate() {
+            if i > 0 {
+                qb.push(" && ");
+            }
+            qb.push("(");
+            for (j, term) in clause.iter().enumerate() {
+                if j > 0 {
+                    qb.push(" || ");
+                }
+                if term.negated {
+                    qb.push("!!");
+                }
+                push_term_query(qb, term);
+            }
+            qb.push(")");
+        }
+        qb.push(")");
+    }
+
+    /// Append a condition that also accepts titles and tags similar to the
+    /// query text, for typo-tolerant searches. Negated terms still exclude.
+    pub fn push_fuzzy_match(&self, qb: &mut QueryBuilder<'_, Postgres>) {
+        qb.push("(a.search_vector @@ ");
+        self.push_tsquery(qb);
+
+        let text = self.positive_text();
+        if !text.is_empty() {
+            // Titles against the whole query, tags against each term
+            qb.push(" OR ((");
+            qb.push_bind(text);
+            qb.push(
+                " <% cjk_strip(a.title) OR EXISTS (SELECT 1 FROM article_tags at \
+                 JOIN tags t ON t.id = at.tag_id WHERE at.article_id = a.id AND (",
+            );
+            for (i, term) in self.positive_terms().enumerate() {
+                if i > 0 {
+                    qb.push(" OR ");
+                }
+                qb.push("t.name % ");
+                qb.push_bind(term.to_string());
+            }
+            qb.push(")))");
+
+            let negated: Vec<&SearchTerm> = self
+                .clauses
+                .iter()
+                .flatten()
+                .filter(|t| t.negated)
+                .collect();
+            if !negated.is_empty() {
+                qb.push(" AND NOT a.search_vector @@ (");
+                for (i, term) in negated.into_iter().enumerate() {
+                    if i > 0 {
+                        qb.push(" || ");
+                    }
+                    push_term_query(qb, term);
+                }
+                qb.push(")");
+            }
+            qb.push(")");
+        }
+
+        qb.push(")");
+    }
+
+    /// Title similarity to the query text, used to rank fuzzy matches
+    pub fn push_similarity(&self, qb: &mut QueryBuilder<'_, Postgres>) {
+        qb.push("word_similarity(");
+        qb.push_bind(self.positive_text());
+        qb.push(", cjk_strip(a.title))");
+    }
+
+    /// Text of all non-negated terms, for highlighting and similarity
+    pub fn positive_text(&self) -> String {
+        self.positive_terms().collect::<Vec<_>>().join(" ")
+    }
+
+    fn positive_terms(&self) -> impl Iterator<Item = &str> {
+        self.clauses
+            .iter()
+            .flatten()
+            .filter(|term| !term.negated)
+            .map(|term| term.text.as_str())
+    }
+}
+
+fn push_term_query(qb: &mut QueryBuilder<'_, Postgres>, term: &SearchTerm) {
+    qb.push("search_term_query(");
+    qb.push_bind(term.text.clone());
+    qb.push(", ");
+    qb.push_bind(term.phrase);
+    qb.push(", ");
+    qb.push_bind(term.prefix);
+    qb.push(")");
+}
+
+enum Token {
+    Or,
+    Term(SearchTerm),
+    Qualifier {
+        key: String,
+        value: String,
+        negated: bool,
+    },
+}
+
+const QUALIFIERS: &[&str] = &["tag", "source", "lang", "author", "before", "after"];
+
+/// Parse a search string into `filter`. The full-text part becomes
+/// `filter.search` and qualifiers narrow the other filter fields:
+///
+/// - `"exact phrase"`, `gpt*` (prefix), `-term` (exclude), `a OR b`
+/// - `tag:llm`, `-tag:llm`, `source:smolai`, `-source:aibase`, `author:"Jane Doe"`,
+///   `-author:"Jane Doe"`
+/// - `lang:zh`, `-lang:en` (detected language)
+/// - `before:2025-01-01`, `after:2024-06-01` (publication date); `-before:` and
+///   `-after:` flip the comparison
+pub fn parse(input: &str, filter: &mut ArticleFilter) -> Result<(), String> {
+    let mut query = SearchQuery::default();
+    let mut pending_or = false;
+
+    for token in tokenize(input) {
+        match token {
+            Token::Or => pending_or = !query.clauses.is_empty(),
+            Token::Term(term) => {
+                match query.clauses.last_mut() {
+                    Some(clause) if pending_or => clause.push(term),
+                    _ => query.clauses.push(vec![term]),
+                }
+                pending_or = false;
+            }
+            Token::Qualifier {
+                key,
+                value,
+                negated,
+            } => {
+                apply_qualifier(filter, &key, value, negated)?;
+                pending_or = false;
+            }
+        }
+    }
+
+    filter.search = (!query.is_empty()).then_some(query);
+    Ok(())
+}
+
+fn apply_qualifier(
+    filter: &mut ArticleFilter,
+    key: &str,
+    value: String,
+    negated: bool,
+) -> Result<(), String> {
+    match (key, negated) {
+        ("tag", false) => filter.required_tags.push(value),
+        ("tag", true) => filter.excluded_tags.push(value),
+        ("source", false) => filter.sources.push(source_name(value)),
+        ("source", true) => filter.excluded_sources.push(source_name(value)),
+        ("lang", false) => filter.languages.push(value.to_lowercase()),
+        ("lang", true) => filter.excluded_languages.push(value.to_lowercase()),
+        ("author", false) => filter.author = Some(value),
+        ("author", true) => filter.excluded_authors.push(value),
+        // `-before:` is the same as `after:`, and `-after:` as `before:`
+        ("before", _) | ("after", _) => {
+            let date = parse_date(&value).ok_or_else(|| format!("Invalid {}: {}", key, value))?;
+            if (key == "before") != negated {
+                filter.published_before = Some(filter.published_before.map_or(date, |d| d.min(date)));
+            } else {
+                filter.published_after = Some(filter.published_after.map_or(date, |d| d.max(date)));
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Map source ids ("aibase") to stored source names ("AIBase")
+pub fn source_name(value: String) -> String {
+    match Source::from_str(&value) {
+        Some(source) => source.display_name().to_string(),
+        None => value,
+    }
+}
+
+/// Accepts RFC 3339 timestamps or plain `YYYY-MM-DD` dates (midnight UTC)
+pub fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|dt| dt.and_utc())
+}
+
+fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let negated = c == '-';
+        if negated {
+            chars.next();
+        }
+
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            let text: String = chars.by_ref().take_while(|&c| c != '"').collect();
+            if let Some(term) = term(&text, true, negated) {
+                tokens.push(Token::Term(term));
+            }
+            continue;
+        }
+
+        let mut word = String::new();
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() {
+                break;
+            }
+            word.push(c);
+            chars.next();
+
+            // Qualifier values may be quoted: author:"Jane Doe"
+            if c == ':' && chars.peek() == Some(&'"') && is_qualifier(&word[..word.len() - 1]) {
+                chars.next();
+                word.extend(chars.by_ref().take_while(|&c| c != '"'));
+                break;
+            }
+        }
+
+        if !negated && (word == "OR" || word == "|") {
+            tokens.push(Token::Or);
+            continue;
+        }
+
+        if let Some((key, value)) = word.split_once(':') {
+            let key = key.to_lowercase();
+            let value = value.trim();
+            if is_qualifier(&key) && !value.is_empty() {
+                tokens.push(Token::Qualifier {
+                    key,
+                    value: value.to_string(),
+                    negated,
+                });
+                continue;
+            }
+        }
+
+        let prefix = word.ends_with('*');
+        if let Some(term) = term(word.trim_end_matches('*'), false, negated) {
+            tokens.push(Token::Term(SearchTerm { prefix, ..term }));
+        }
+    }
+
+    tokens
+}
+
+fn is_qualifier(key: &str) -> bool {
+    QUALIFIERS.contains(&key.to_lowercase().as_str())
+}
+
+fn term(text: &str, phrase: bool, negated: bool) -> Option<SearchTerm> {
+    let text = text.trim();
+    if !text.chars().any(char::is_alphanumeric) {
+        return None;
+    }
+    Some(SearchTerm {
+        text: text.to_string(),
+        phrase,
+        prefix: false,
+        negated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(input: &str) -> ArticleFilter {
+        let mut filter = ArticleFilter::default();
+        parse(input, &mut filter).unwrap();
+        filter
+    }
+
+    fn term(text: &str) -> SearchTerm {
+        SearchTerm {
+            text: text.to_string(),
+            phrase: false,
+            prefix: false,
+            negated: false,
+        }
+    }
+
+    #[test]
+    fn parses_terms_phrases_and_prefixes() {
+        let filter = parsed(r#"llama "open weights" gpt-4* -rumor"#);
+        assert_eq!(
+            filter.search.unwrap().clauses,
+            vec![
+                vec![term("llama")],
+                vec![SearchTerm { phrase: true, ..term("open weights") }],
+                vec![SearchTerm { prefix: true, ..term("gpt-4") }],
+                vec![SearchTerm { negated: true, ..term("rumor") }],
+            ]
+        );
+    }
+
+    #[test]
+    fn groups_or_terms_into_one_clause() {
+        let filter = parsed("llama OR gemini | claude model");
+        assert_eq!(
+            filter.search.unwrap().clauses,
+            vec![
+                vec![term("llama"), term("gemini"), term("claude")],
+                vec![term("model")],
+            ]
+        );
+        // A leading OR has nothing to join
+        assert_eq!(parsed("OR llama").search.unwrap().clauses, vec![vec![term("llama")]]);
+    }
+
+    #[test]
+    fn skips_terms_without_letters_or_digits() {
+        assert!(parsed(r#"- * "" ..."#).search.is_none());
+    }
+
+    #[test]
+    fn applies_qualifiers() {
+        let filter = parsed(
+            r#"tag:LLM -tag:rumor source:smolai -source:aibase lang:ZH author:"Jane Doe" model"#,
+        );
+        assert_eq!(filter.required_tags, ["LLM"]);
+        assert_eq!(filter.excluded_tags, ["rumor"]);
+        assert_eq!(filter.sources, ["smol.ai"]);
+        assert_eq!(filter.excluded_sources, ["AIBase"]);
+        assert_eq!(filter.languages, ["zh"]);
+        assert_eq!(filter.author.as_deref(), Some("Jane Doe"));
+        assert_eq!(filter.search.unwrap().clauses, vec![vec![term("model")]]);
+    }
+
+    #[test]
+    fn qualifiers_alone_leave_no_search() {
+        let filter = parsed("tag:LLM after:2025-01-01");
+        assert!(filter.search.is_none());
+        assert_eq!(filter.required_tags, ["LLM"]);
+    }
+
+    #[test]
+    fn negated_author_is_excluded() {
+        let filter = parsed(r#"-author:"Jane Doe""#);
+        assert_eq!(filter.author, None);
+        assert_eq!(filter.excluded_authors, ["Jane Doe"]);
+    }
+
+    #[test]
+    fn negated_dates_flip_the_comparison() {
+        let date = |s: &str| parse_date(s).unwrap();
+
+        let filter = parsed("-before:2025-01-01");
+        assert_eq!(filter.published_after, Some(date("2025-01-01")));
+        assert_eq!(filter.published_before, None);
+
+        let filter = parsed("-after:2025-01-01");
+        assert_eq!(filter.published_before, Some(date("2025-01-01")));
+        assert_eq!(filter.published_after, None);
+
+        // Several bounds narrow the range
+        let filter = parsed("after:2024-01-01 -before:2024-06-01 before:2025-01-01 -after:2024-12-01");
+        assert_eq!(filter.published_after, Some(date("2024-06-01")));
+        assert_eq!(filter.published_before, Some(date("2024-12-01")));
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        let mut filter = ArticleFilter::default();
+        assert!(parse("before:yesterday", &mut filter).is_err());
+        assert!(parse("-after:2025-13-01", &mut filter).is_err());
+    }
+
+    #[test]
+    fn parses_dates() {
+        assert_eq!(
+            parse_date("2025-03-04").unwrap().to_rfc3339(),
+            "2025-03-04T00:00:00+00:00"
+        );
+        assert_eq!(
+            parse_date("2025-03-04T10:00:00+02:00").unwrap().to_rfc3339(),
+            "2025-03-04T08:00:00+00:00"
+        );
+    }
+}
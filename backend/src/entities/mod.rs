@@ -0,0 +1,250 @@
+//! Named entities (companies, models, products, people) recognized by
+//! matching the aliases of a local gazetteer
+
+use aho_corasick::{AhoCorasick, AhoCorasickBuilder, MatchKind};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::db::models::EntityKind;
+use crate::db::queries;
+use crate::text::{fnv1a, inside_word};
+
+/// Maintained by hand. Aliases match whole words, case-insensitively unless
+/// the entry is `case_sensitive`; a trailing `*` also matches longer words
+/// (`Qwen*` matches "Qwen2.5"). CJK aliases match anywhere.
+const GAZETTEER: &str = include_str!("gazetteer.json");
+
+const BATCH_SIZE: i64 = 100;
+const SYNC_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Deserialize)]
+pub struct GazetteerEntry {
+    pub slug: String,
+    pub name: String,
+    pub kind: EntityKind,
+    pub aliases: Vec<String>,
+    #[serde(default)]
+    pub case_sensitive: bool,
+}
+
+/// Entities mentioned in an article: `(entity_id, mentions, in_title)`
+pub type Mentions = Vec<(i32, i32, bool)>;
+
+struct Alias {
+    entity_id: i32,
+    text: String,
+    prefix: bool,
+    case_sensitive: bool,
+}
+
+pub struct Extractor {
+    matcher: AhoCorasick,
+    aliases: Vec<Alias>,
+    /// Stored with each checked article; changes with the gazetteer
+    version: String,
+}
+
+impl Extractor {
+    /// `ids` maps gazetteer slugs to entity ids
+    fn new(entries: &[GazetteerEntry], ids: &HashMap<String, i32>) -> Result<Self> {
+        let aliases: Vec<Alias> = entries
+            .iter()
+            .filter_map(|entry| ids.get(&entry.slug).map(|id| (entry, *id)))
+            .flat_map(|(entry, entity_id)| {
+                entry.aliases.iter().map(move |alias| {
+                    let prefix = alias.ends_with('*');
+                    Alias {
+                        entity_id,
+                        text: alias.trim_end_matches('*').to_string(),
+                        prefix,
+                        case_sensitive: entry.case_sensitive,
+                    }
+                })
+            })
+            .collect();
+
+        let matcher = AhoCorasickBuilder::new()
+            .ascii_case_insensitive(true)
+            .match_kind(MatchKind::LeftmostLongest)
+            .build(aliases.iter().map(|alias| &alias.text))
+            .context("Failed to build entity matcher")?;
+
+        Ok(Self {
+            matcher,
+            aliases,
+            version: format!("{:016x}", fnv1a(GAZETTEER.as_bytes())),
+        })
+    }
+
+    pub fn extract(&self, title: &str, content: &str) -> Mentions {
+        let mut mentions: Vec<(i32, i32, bool)> = Vec::new();
+        let text = format!("{}\n{}", title, content);
+
+        for m in self.matcher.find_iter(&text) {
+            let alias = &self.aliases[m.pattern().as_usize()];
+            let found = &text[m.start()..m.end()];
+            if alias.case_sensitive && found != alias.text {
+                continue;
+            }
+
+            if inside_word(&text, m.start()) || (!alias.prefix && inside_word(&text, m.end())) {
+                continue;
+            }
+
+            let in_title = m.end() <= title.len();
+            match mentions
+                .iter_mut()
+                .find(|(id, _, _)| *id == alias.entity_id)
+            {
+                Some((_, count, title_mention)) => {
+                    *count += 1;
+                    *title_mention |= in_title;
+                }
+                None => mentions.push((alias.entity_id, 1, in_title)),
+            }
+        }
+
+        mentions
+    }
+}
+
+fn gazetteer() -> Result<Vec<GazetteerEntry>> {
+    serde_json::from_str(GAZETTEER).context("Invalid entity gazetteer")
+}
+
+/// Sync the gazetteer into the database, then keep article mentions up to
+/// date as articles arrive or change
+pub async fn run(pool: PgPool) {
+    let extractor = match sync_gazetteer(&pool).await {
+        Ok(extractor) => extractor,
+        Err(e) => {
+            tracing::error!("Failed to load entity gazetteer: {}", e);
+            return;
+        }
+    };
+
+    loop {
+        match extract_pending(&pool, &extractor).await {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("Extracted entities from {} articles", count),
+            Err(e) => tracing::error!("Entity extraction failed: {}", e),
+        }
+        tokio::time::sleep(SYNC_INTERVAL).await;
+    }
+}
+
+async fn sync_gazetteer(pool: &PgPool) -> Result<Extractor> {
+    let entries = gazetteer()?;
+    let ids = queries::sync_entities(pool, &entries).await?;
+    Extractor::new(&entries, &ids)
+}
+
+async fn extract_pending(pool: &PgPool, extractor: &Extractor) -> Result<usize> {
+    let mut checked = 0;
+
+    loop {
+        let rows =
+            queries::get_articles_pending_entities(pool, &extractor.version, BATCH_SIZE).await?;
+        if rows.is_empty() {
+            break;
+        }
+
+        for (id, title, content) in rows {
+            let mentions = extractor.extract(&title, &content);
+            queries::replace_article_entities(pool, id, &mentions, &extractor.version).await?;
+            checked += 1;
+        }
+    }
+
+    Ok(checked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extractor() -> (Extractor, Vec<GazetteerEntry>) {
+        let entries = gazetteer().unwrap();
+        let ids = entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| (entry.slug.clone(), i as i32))
+            .collect();
+        (Extractor::new(&entries, &ids).unwrap(), entries)
+    }
+
+    /// `(slug, mentions, in_title)` of every entity found
+    fn extract(title: &str, content: &str) -> Vec<(String, i32, bool)> {
+        let (extractor, entries) = extractor();
+        extractor
+            .extract(title, content)
+            .into_iter()
+            .map(|(id, count, in_title)| (entries[id as usize].slug.clone(), count, in_title))
+            .collect()
+    }
+
+    fn slugs(title: &str, content: &str) -> Vec<String> {
+        extract(title, content).into_iter().map(|(slug, _, _)| slug).collect()
+    }
+
+    #[test]
+    fn gazetteer_is_valid() {
+        let entries = gazetteer().unwrap();
+        for entry in &entries {
+            assert!(!entry.aliases.is_empty(), "{}", entry.slug);
+        }
+        let mut seen = std::collections::HashSet::new();
+        assert!(entries.iter().all(|entry| seen.insert(&entry.slug)));
+    }
+
+    #[test]
+    fn counts_mentions_and_title() {
+        assert_eq!(
+            extract("OpenAI ships a model", "openai said Anthropic and OpenAI compete."),
+            [
+                ("openai".to_string(), 3, true),
+                ("anthropic".to_string(), 1, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn matches_whole_words_only() {
+        assert!(slugs("", "Geminis and OpenAIs and SuperClaude").is_empty());
+        assert_eq!(slugs("", "Gemini, Claude."), ["gemini", "claude"]);
+    }
+
+    #[test]
+    fn case_sensitive_aliases() {
+        assert!(slugs("", "A meta-analysis of mistral winds").is_empty());
+        assert_eq!(slugs("", "Meta and Mistral released models"), ["meta", "mistral-ai"]);
+        assert!(slugs("", "O1 visa").is_empty());
+        assert_eq!(slugs("", "o1-preview and o3"), ["openai-o-series"]);
+    }
+
+    #[test]
+    fn prefix_aliases_match_longer_words() {
+        assert_eq!(slugs("", "Qwen2.5-Max beats Llama-4 and GPT-4o"), ["qwen", "llama", "gpt"]);
+        // but only at the start of a word
+        assert!(slugs("", "AQwen MyLlama").is_empty());
+    }
+
+    #[test]
+    fn cjk_aliases_match_anywhere() {
+        assert_eq!(slugs("谷歌发布Gemini", "微软和英伟达合作"), ["google", "gemini", "microsoft", "nvidia"]);
+    }
+
+    #[test]
+    fn longest_overlapping_alias_wins() {
+        // 阿里巴巴 contains 阿里, 通义千问 contains 千问: one mention each
+        assert_eq!(
+            extract("", "阿里巴巴发布通义千问3"),
+            [("alibaba".to_string(), 1, false), ("qwen".to_string(), 1, false)]
+        );
+        // "Google DeepMind" is DeepMind, not Google as well
+        assert_eq!(slugs("", "Google DeepMind"), ["google-deepmind"]);
+    }
+}
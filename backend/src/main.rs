@@ -1,7 +1,13 @@
 mod api;
+mod backfill;
+mod bulk;
 mod config;
 mod db;
+mod entities;
+mod maintenance;
 mod scraper;
+mod semantic;
+mod text;
 
 use anyhow::Result;
 use axum::{
@@ -15,14 +21,22 @@ use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 use crate::api::handlers;
+use crate::bulk::BulkJobs;
 use crate::config::Config;
 use crate::db::pool::create_pool;
-use crate::scraper::ScrapeProgress;
+use crate::maintenance::Maintenance;
+use crate::scraper::{assets::AssetStore, tagger::Tagger, ScrapeProgress};
+use crate::semantic::{Embedder, SemanticIndex};
 
 pub struct AppState {
     pub pool: sqlx::PgPool,
     pub config: Config,
     pub progress_tx: broadcast::Sender<ScrapeProgress>,
+    pub assets: Arc<AssetStore>,
+    pub semantic: Arc<SemanticIndex>,
+    pub tagger: Arc<Tagger>,
+    pub bulk: BulkJobs,
+    pub maintenance: Maintenance,
 }
 
 #[tokio::main]
@@ -31,26 +45,44 @@ async fn main() -> Result<()> {
 
     tracing_subscriber::registry()
         .with(tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| {
-            "aibase_scraper=debug,tower_http=debug,axum=debug".into()
+            "aibase_scraper=debug,tower_http=debug,axum=debug,sqlx=warn".into()
         }))
         .with(tracing_subscriber::fmt::layer())
         .init();
 
     let config = Config::from_env()?;
-    let pool = create_pool(&config.database_url).await?;
+    let pool = create_pool(&config.database_url, config.slow_query_ms).await?;
 
     // Run migrations
     tracing::info!("Running database migrations...");
     sqlx::migrate!("./migrations").run(&pool).await?;
     tracing::info!("Migrations complete");
 
+    tokio::spawn(backfill::run(pool.clone()));
+
     let (progress_tx, _) = broadcast::channel::<ScrapeProgress>(100);
+    let assets = Arc::new(AssetStore::new(&config)?);
+
+    let semantic = Arc::new(SemanticIndex::new(Embedder::new(&config)?));
+    tokio::spawn(semantic::run(pool.clone(), semantic.clone()));
+    tokio::spawn(entities::run(pool.clone()));
+
+    bulk::clear_exports().await;
+
+    let tagger = Arc::new(Tagger::default());
+    tagger.reload(&pool).await?;
 
     let state = Arc::new(AppState {
         pool,
         config: config.clone(),
         progress_tx,
+        assets,
+        semantic,
+        tagger,
+        bulk: BulkJobs::default(),
+        maintenance: Maintenance::default(),
     });
+    tokio::spawn(maintenance::run(state.clone()));
 
     let cors = CorsLayer::new()
         .allow_origin(Any)
@@ -60,8 +92,57 @@ async fn main() -> Result<()> {
     let app = Router::new()
         // Articles
         .route("/api/articles", get(handlers::articles::list_articles))
-        .route("/api/articles/:id", get(handlers::articles::get_article))
+        .route(
+            "/api/articles/:id",
+            get(handlers::articles::get_article)
+                .patch(handlers::articles::edit_article)
+                .delete(handlers::articles::delete_article),
+        )
+        .route("/api/articles/:id/related", get(handlers::articles::related_articles))
+        .route("/api/articles/:id/tags", get(handlers::articles::article_tags))
         .route("/api/articles/:id/delete", axum::routing::delete(handlers::articles::delete_article))
+        // Bulk operations
+        .route("/api/bulk", get(handlers::bulk::list_jobs).post(handlers::bulk::start_bulk))
+        .route("/api/bulk/:id", get(handlers::bulk::get_job))
+        .route("/api/bulk/:id/cancel", post(handlers::bulk::cancel_job))
+        .route("/api/bulk/:id/export", get(handlers::bulk::download_export))
+        // Trash
+        .route(
+            "/api/trash",
+            get(handlers::trash::list_trash).delete(handlers::trash::empty_trash),
+        )
+        .route("/api/trash/:id", axum::routing::delete(handlers::trash::purge_article))
+        .route("/api/trash/:id/restore", post(handlers::trash::restore_article))
+        .route(
+            "/api/tombstones",
+            get(handlers::trash::list_tombstones).post(handlers::trash::add_tombstone),
+        )
+        .route(
+            "/api/tombstones/:source/:external_id",
+            axum::routing::delete(handlers::trash::delete_tombstone),
+        )
+        // Maintenance
+        .route("/api/maintenance", get(handlers::maintenance::get_status))
+        .route("/api/maintenance/report", get(handlers::maintenance::dry_run))
+        .route("/api/maintenance/run", post(handlers::maintenance::run_now))
+        .route("/api/archive", get(handlers::maintenance::list_archive))
+        .route("/api/archive/:id", get(handlers::maintenance::get_archived_article))
+        // Assets
+        .route("/api/assets/:hash", get(handlers::assets::get_asset))
+        // Clusters
+        .route("/api/clusters", get(handlers::clusters::list_clusters))
+        .route("/api/clusters/:id", get(handlers::clusters::get_cluster))
+        // Entities
+        .route("/api/entities", get(handlers::entities::list_entities))
+        .route("/api/entities/:id", get(handlers::entities::get_entity))
+        .route("/api/entities/:id/articles", get(handlers::entities::entity_articles))
+        // Search
+        .route("/api/search/suggest", get(handlers::search::suggest))
+        .route("/api/search/semantic", get(handlers::search::semantic_search))
+        // Links
+        .route("/api/links/domains", get(handlers::links::top_domains))
+        .route("/api/links/urls", get(handlers::links::top_urls))
+        .route("/api/links/articles", get(handlers::links::linking_articles))
         // Scraper
         .route("/api/scraper/start", post(handlers::scraper::start_scrape))
         .route("/api/scraper/start-range", post(handlers::scraper::start_range_scrape))
@@ -73,6 +154,30 @@ async fn main() -> Result<()> {
         .route("/api/stats/tags", get(handlers::stats::get_tag_stats))
         // Sources
         .route("/api/sources", get(handlers::sources::list_sources))
+        // Tags
+        .route("/api/tags", get(handlers::tags::list_tags))
+        .route(
+            "/api/tags/:id",
+            get(handlers::tags::get_tag)
+                .patch(handlers::tags::update_tag)
+                .delete(handlers::tags::delete_tag),
+        )
+        .route("/api/tags/:id/merge", post(handlers::tags::merge_tag))
+        .route("/api/tags/:id/aliases", post(handlers::tags::add_alias))
+        .route("/api/tags/aliases/:alias", axum::routing::delete(handlers::tags::delete_alias))
+        // Tag rules
+        .route(
+            "/api/tag-rules",
+            get(handlers::tag_rules::list_rules).post(handlers::tag_rules::create_rule),
+        )
+        .route("/api/tag-rules/preview", post(handlers::tag_rules::preview_rule))
+        .route("/api/tag-rules/apply", post(handlers::tag_rules::apply_rules))
+        .route(
+            "/api/tag-rules/:id",
+            get(handlers::tag_rules::get_rule)
+                .put(handlers::tag_rules::update_rule)
+                .delete(handlers::tag_rules::delete_rule),
+        )
         // Settings
         .route("/api/settings", get(handlers::settings::get_settings))
         .route("/api/settings/:key", patch(handlers::settings::update_setting))
@@ -0,0 +1,331 @@
+//! Background maintenance: retention rules from the `retention` setting,
+//! purging of expired trash and removal of unused image files
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::db::queries;
+use crate::scraper::Source;
+use crate::AppState;
+
+/// How often the task checks whether a run is due
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3600);
+const SETTING_KEY: &str = "retention";
+/// Setting holding the last run's report, so restarts keep the schedule
+pub const LAST_RUN_KEY: &str = "maintenance_last_run";
+
+/// The `retention` setting. Ages are in days; `null` or 0 keeps forever.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetentionPolicy {
+    /// Whether the rules below run; expired trash is purged regardless
+    pub enabled: bool,
+    pub interval_hours: i64,
+    /// Finished scrape runs, with their article logs
+    pub scrape_runs_days: Option<i64>,
+    pub article_logs_days: Option<i64>,
+    /// Rules for sources not listed in `sources`
+    pub default: SourceRetention,
+    /// Rules by source id or name; unset fields fall back to `default`
+    pub sources: HashMap<String, SourceRetention>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_hours: 24,
+            scrape_runs_days: Some(180),
+            article_logs_days: Some(30),
+            default: SourceRetention::default(),
+            sources: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SourceRetention {
+    /// Clear stored HTML of articles scraped this long ago, keeping Markdown
+    pub raw_html_days: Option<i64>,
+    /// Move articles published this long ago to the archive
+    pub archive_after_days: Option<i64>,
+}
+
+impl RetentionPolicy {
+    /// Parse and check a value for the `retention` setting
+    pub fn from_value(value: serde_json::Value) -> Result<Self, String> {
+        let policy: Self = serde_json::from_value(value).map_err(|e| e.to_string())?;
+
+        if policy.interval_hours < 1 {
+            return Err("interval_hours must be at least 1".to_string());
+        }
+        let mut rules = vec![("default", &policy.default)];
+        for (source, source_rules) in &policy.sources {
+            if Source::from_str(source).is_none() {
+                return Err(format!("Unknown source '{}'", source));
+            }
+            rules.push((source.as_str(), source_rules));
+        }
+        let mut days = vec![
+            ("scrape_runs_days".to_string(), policy.scrape_runs_days),
+            ("article_logs_days".to_string(), policy.article_logs_days),
+        ];
+        for (name, rules) in rules {
+            days.push((format!("{}.raw_html_days", name), rules.raw_html_days));
+            days.push((
+                format!("{}.archive_after_days", name),
+                rules.archive_after_days,
+            ));
+        }
+        if let Some((name, _)) = days.iter().find(|(_, days)| days.is_some_and(|d| d < 0)) {
+            return Err(format!("{} can't be negative", name));
+        }
+
+        Ok(policy)
+    }
+
+    /// The stored policy, or the defaults if it is missing or invalid
+    pub async fn load(pool: &sqlx::PgPool) -> Result<Self> {
+        let Some(setting) = queries::get_setting(pool, SETTING_KEY).await? else {
+            return Ok(Self::default());
+        };
+        Ok(Self::from_value(setting.value).unwrap_or_else(|e| {
+            tracing::warn!("Invalid retention setting, using defaults: {}", e);
+            Self::default()
+        }))
+    }
+
+    /// `source`'s rules, falling back to the defaults field by field
+    fn for_source(&self, source: Source) -> SourceRetention {
+        let rules = self
+            .sources
+            .iter()
+            .find(|(name, _)| Source::from_str(name) == Some(source))
+            .map(|(_, rules)| rules);
+        SourceRetention {
+            raw_html_days: rules
+                .and_then(|rules| rules.raw_html_days)
+                .or(self.default.raw_html_days),
+            archive_after_days: rules
+                .and_then(|rules| rules.archive_after_days)
+                .or(self.default.archive_after_days),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceReport {
+    pub source: String,
+    pub raw_html_purged: u64,
+    pub articles_archived: u64,
+}
+
+/// What a maintenance run removed, or with `dry_run`, would remove
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceReport {
+    pub dry_run: bool,
+    pub sources: Vec<SourceReport>,
+    pub trash_purged: u64,
+    pub scrape_runs_pruned: u64,
+    pub article_logs_pruned: u64,
+    /// Mirrored image files no remaining article uses
+    #[serde(default)]
+    pub assets_removed: u64,
+    /// Steps that failed; the others still ran
+    pub errors: Vec<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
+
+/// The last maintenance run, and whether one is in progress
+#[derive(Default)]
+pub struct Maintenance {
+    last_run: Mutex<Option<MaintenanceReport>>,
+    running: AtomicBool,
+}
+
+impl Maintenance {
+    pub fn last_run(&self) -> Option<MaintenanceReport> {
+        self.last_run.lock().unwrap().clone()
+    }
+
+    /// When the background task will next run under `policy`
+    pub fn next_run_at(&self, policy: &RetentionPolicy) -> DateTime<Utc> {
+        match self.last_run() {
+            Some(report) => report.started_at + Duration::hours(policy.interval_hours),
+            None => Utc::now(),
+        }
+    }
+
+    /// Pick up the report saved by the last run before a restart
+    pub async fn load(&self, pool: &sqlx::PgPool) -> Result<()> {
+        let Some(setting) = queries::get_setting(pool, LAST_RUN_KEY).await? else {
+            return Ok(());
+        };
+        match serde_json::from_value(setting.value) {
+            Ok(report) => *self.last_run.lock().unwrap() = Some(report),
+            Err(e) => tracing::warn!("Ignoring invalid {} setting: {}", LAST_RUN_KEY, e),
+        }
+        Ok(())
+    }
+
+    /// Run maintenance now. Returns `None` if a run is already in progress.
+    pub async fn run(
+        &self,
+        state: &AppState,
+        policy: &RetentionPolicy,
+        dry_run: bool,
+    ) -> Option<MaintenanceReport> {
+        if !dry_run && self.running.swap(true, Ordering::SeqCst) {
+            return None;
+        }
+
+        let report = run_once(state, policy, dry_run).await;
+        if !dry_run {
+            log(&report);
+            let saved = match serde_json::to_value(&report) {
+                Ok(value) => queries::set_setting(&state.pool, LAST_RUN_KEY, value).await,
+                Err(e) => Err(e.into()),
+            };
+            if let Err(e) = saved {
+                tracing::error!("Failed to save maintenance report: {}", e);
+            }
+            *self.last_run.lock().unwrap() = Some(report.clone());
+            self.running.store(false, Ordering::SeqCst);
+        }
+        Some(report)
+    }
+}
+
+/// Check hourly and run maintenance whenever `interval_hours` have passed
+/// since the last run
+pub async fn run(state: Arc<AppState>) {
+    if let Err(e) = state.maintenance.load(&state.pool).await {
+        tracing::error!("Failed to load last maintenance run: {}", e);
+    }
+    loop {
+        match RetentionPolicy::load(&state.pool).await {
+            Ok(policy) if state.maintenance.next_run_at(&policy) <= Utc::now() => {
+                state.maintenance.run(&state, &policy, false).await;
+            }
+            Ok(_) => {}
+            Err(e) => tracing::error!("Failed to load retention policy: {}", e),
+        }
+        tokio::time::sleep(CHECK_INTERVAL).await;
+    }
+}
+
+async fn run_once(state: &AppState, policy: &RetentionPolicy, dry_run: bool) -> MaintenanceReport {
+    let pool = &state.pool;
+    let now = Utc::now();
+    let cutoff = |days: Option<i64>| days.filter(|&d| d > 0).map(|d| now - Duration::days(d));
+    let mut errors = Vec::new();
+    let mut record = |step: &str, result: Result<u64>| {
+        result.unwrap_or_else(|e| {
+            errors.push(format!("{}: {}", step, e));
+            0
+        })
+    };
+
+    let trash_purged = match cutoff(Some(state.config.trash_retention_days)) {
+        Some(before) if dry_run => record("trash", queries::count_trash(pool, before).await),
+        Some(before) => record("trash", queries::purge_trash(pool, Some(before)).await),
+        None => 0,
+    };
+
+    let mut sources = Vec::new();
+    let (mut scrape_runs_pruned, mut article_logs_pruned) = (0, 0);
+    if policy.enabled {
+        for source in Source::all() {
+            let rules = policy.for_source(source);
+            let name = source.display_name();
+            let mut report = SourceReport {
+                source: name.to_string(),
+                raw_html_purged: 0,
+                articles_archived: 0,
+            };
+            // HTML first, so a dry run counts the same rows a real run clears
+            if let Some(before) = cutoff(rules.raw_html_days) {
+                report.raw_html_purged = record(
+                    &format!("{} raw HTML", name),
+                    queries::purge_raw_html(pool, name, before, dry_run).await,
+                );
+            }
+            if let Some(before) = cutoff(rules.archive_after_days) {
+                let archived = if dry_run {
+                    queries::count_archivable(pool, name, before).await
+                } else {
+                    queries::archive_articles(pool, name, before).await.map(|ids| {
+                        state.semantic.remove(&ids);
+                        ids.len() as u64
+                    })
+                };
+                report.articles_archived = record(&format!("{} archive", name), archived);
+            }
+            sources.push(report);
+        }
+
+        if let Some(before) = cutoff(policy.scrape_runs_days) {
+            scrape_runs_pruned = record(
+                "scrape runs",
+                queries::prune_scrape_runs(pool, before, dry_run).await,
+            );
+        }
+        if let Some(before) = cutoff(policy.article_logs_days) {
+            article_logs_pruned = record(
+                "article logs",
+                queries::prune_article_logs(pool, before, dry_run).await,
+            );
+        }
+    }
+
+    // Last, so files of articles purged or archived above go too. A dry run
+    // only counts files that are unused already.
+    let assets_removed = record("assets", state.assets.remove_unreferenced(pool, dry_run).await);
+
+    MaintenanceReport {
+        dry_run,
+        sources,
+        trash_purged,
+        scrape_runs_pruned,
+        article_logs_pruned,
+        assets_removed,
+        errors,
+        started_at: now,
+        finished_at: Utc::now(),
+    }
+}
+
+fn log(report: &MaintenanceReport) {
+    for source in &report.sources {
+        if source.raw_html_purged > 0 || source.articles_archived > 0 {
+            tracing::info!(
+                "Retention for {}: archived {} articles, cleared HTML of {}",
+                source.source,
+                source.articles_archived,
+                source.raw_html_purged
+            );
+        }
+    }
+    if report.trash_purged > 0 {
+        tracing::info!("Purged {} articles from the trash", report.trash_purged);
+    }
+    if report.scrape_runs_pruned > 0 || report.article_logs_pruned > 0 {
+        tracing::info!(
+            "Pruned {} scrape runs and {} article logs",
+            report.scrape_runs_pruned,
+            report.article_logs_pruned
+        );
+    }
+    if report.assets_removed > 0 {
+        tracing::info!("Removed {} unused asset files", report.assets_removed);
+    }
+    for error in &report.errors {
+        tracing::error!("Maintenance step failed: {}", error);
+    }
+}
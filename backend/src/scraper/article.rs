@@ -4,11 +4,13 @@ use scraper::{Html, Selector};
 use sha2::{Digest, Sha256};
 
 use super::client::ScraperClient;
+use super::{excerpt, fingerprint};
 use super::selectors::{
     ARTICLE_AUTHOR, ARTICLE_CONTENT, ARTICLE_DATE, ARTICLE_PARAGRAPHS, ARTICLE_TAGS,
     ARTICLE_THUMBNAIL, ARTICLE_TITLE, ARTICLE_VIEW_COUNT, BASE_URL,
 };
-use crate::db::models::NewArticle;
+use crate::db::models::{ContentFormat, NewArticle, NewTag};
+use crate::text;
 
 pub struct ArticleScraper {
     client: ScraperClient,
@@ -41,12 +43,8 @@ impl ArticleScraper {
         // Content - try multiple selectors
         let content = self.extract_content(&document);
 
-        // Excerpt - first 200 chars of content
-        let excerpt = if content.len() > 200 {
-            Some(format!("{}...", &content[..200].trim()))
-        } else {
-            Some(content.clone())
-        };
+        // Excerpt - meta description, or the start of the content
+        let excerpt = excerpt::from_document(&document, &title, &content);
 
         // Author
         let author = self.extract_text(&document, ARTICLE_AUTHOR);
@@ -65,10 +63,11 @@ impl ArticleScraper {
 
         // Content hash for change detection
         let content_hash = self.compute_hash(&content);
+        let simhash = fingerprint::simhash(&title, &content);
 
-        // Estimate read time (average 200 words per minute)
-        let word_count = content.split_whitespace().count();
-        let read_time_minutes = Some((word_count / 200).max(1) as i32);
+        // Detect the language, and estimate read time at its reading pace
+        let language = text::language(&format!("{}\n{}", title, content));
+        let read_time_minutes = Some(text::reading_minutes(&content, language));
 
         Ok(NewArticle {
             external_id: external_id.to_string(),
@@ -81,9 +80,15 @@ impl ArticleScraper {
             published_at,
             view_count,
             read_time_minutes,
+            language: language.to_string(),
             thumbnail_url,
             content_hash,
+            simhash,
+            content_html: None,
+            content_markdown: None,
+            content_format: ContentFormat::Text,
             tags,
+            links: Vec::new(),
         })
     }
 
@@ -174,7 +179,7 @@ impl ArticleScraper {
         None
     }
 
-    fn extract_tags(&self, document: &Html) -> Vec<String> {
+    fn extract_tags(&self, document: &Html) -> Vec<NewTag> {
         let selector = match Selector::parse(ARTICLE_TAGS) {
             Ok(s) => s,
             Err(_) => return Vec::new(),
@@ -185,6 +190,7 @@ impl ArticleScraper {
             .map(|e| e.text().collect::<String>().trim().to_string())
             .filter(|s| !s.is_empty() && s.len() < 50)
             .take(10)
+            .map(NewTag::new)
             .collect()
     }
 
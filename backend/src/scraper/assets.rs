@@ -0,0 +1,487 @@
+use anyhow::{Context, Result};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::redirect;
+use scraper::node::Element;
+use scraper::{Html, Node, Selector};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use url::{Host, Url};
+
+use super::content::html_to_markdown;
+use crate::config::Config;
+use crate::db::models::{AssetKind, NewArticle, NewAsset};
+use crate::db::queries;
+
+/// How old an unreferenced file must be before it is removed
+const UNREFERENCED_GRACE: Duration = Duration::from_secs(3600);
+
+/// Local, content-addressed mirror of article images
+pub struct AssetStore {
+    client: reqwest::Client,
+    dir: PathBuf,
+    max_bytes: usize,
+    enabled: bool,
+}
+
+impl AssetStore {
+    pub fn new(config: &Config) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36")
+            .timeout(Duration::from_secs(30))
+            // Image URLs come from third-party pages, so every hop must stay
+            // on the public internet
+            .dns_resolver(Arc::new(PublicResolver))
+            .redirect(redirect::Policy::custom(|attempt| {
+                if attempt.previous().len() >= MAX_REDIRECTS {
+                    attempt.error("Too many redirects")
+                } else if let Err(e) = check_url(attempt.url()) {
+                    attempt.error(e)
+                } else {
+                    attempt.follow()
+                }
+            }))
+            .build()
+            .context("Failed to create HTTP client")?;
+
+        Ok(Self {
+            client,
+            dir: PathBuf::from(&config.asset_dir),
+            max_bytes: config.asset_max_bytes,
+            enabled: config.mirror_assets,
+        })
+    }
+
+    pub fn public_url(hash: &str) -> String {
+        format!("/api/assets/{}", hash)
+    }
+
+    /// Files are sharded by the first two hex chars of their hash
+    pub fn path_for(&self, hash: &str) -> PathBuf {
+        self.dir.join(&hash[..2]).join(hash)
+    }
+
+    /// Download the thumbnail and inline images of an article and point its
+    /// content at the local copies. Images that fail to download keep their
+    /// original URL. The returned assets are recorded once the article is saved.
+    pub async fn mirror_article(&self, pool: &PgPool, article: &mut NewArticle) -> Vec<NewAsset> {
+        if !self.enabled {
+            return Vec::new();
+        }
+
+        let mut assets: Vec<NewAsset> = Vec::new();
+
+        if let Some(url) = article.thumbnail_url.clone() {
+            if let Some(asset) = self.mirror_url(pool, &url, AssetKind::Thumbnail).await {
+                article.thumbnail_url = Some(Self::public_url(&asset.hash));
+                assets.push(asset);
+            }
+        }
+
+        let mut local_urls = HashMap::new();
+        let inline_urls = article
+            .content_html
+            .as_deref()
+            .map(inline_image_urls)
+            .unwrap_or_default();
+
+        for url in inline_urls {
+            let hash = match assets.iter().find(|a| a.original_url == url) {
+                // Thumbnails are often repeated as the first inline image
+                Some(existing) => existing.hash.clone(),
+                None => match self.mirror_url(pool, &url, AssetKind::Inline).await {
+                    Some(asset) => {
+                        let hash = asset.hash.clone();
+                        assets.push(asset);
+                        hash
+                    }
+                    None => continue,
+                },
+            };
+
+            local_urls.insert(url, Self::public_url(&hash));
+        }
+
+        if !local_urls.is_empty() {
+            if let Some(html) = article.content_html.as_mut() {
+                *html = rewrite_image_urls(html, &local_urls);
+                if article.content_markdown.is_some() {
+                    article.content_markdown = Some(html_to_markdown(html));
+                }
+            }
+        }
+
+        assets
+    }
+
+    async fn mirror_url(&self, pool: &PgPool, url: &str, kind: AssetKind) -> Option<NewAsset> {
+        // Reuse an earlier download of the same URL if the file is still on disk
+        match queries::get_asset_by_original_url(pool, url).await {
+            Ok(Some(existing)) if self.path_for(&existing.hash).exists() => {
+                return Some(NewAsset {
+                    kind,
+                    original_url: existing.original_url,
+                    hash: existing.hash,
+                    content_type: existing.content_type,
+                    byte_size: existing.byte_size,
+                    width: existing.width,
+                    height: existing.height,
+                });
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Failed to look up asset {}: {}", url, e),
+        }
+
+        match self.download(url, kind).await {
+            Ok(asset) => Some(asset),
+            Err(e) => {
+                tracing::warn!("Failed to mirror asset {}: {}", url, e);
+                None
+            }
+        }
+    }
+
+    async fn download(&self, url: &str, kind: AssetKind) -> Result<NewAsset> {
+        check_url(&Url::parse(url).context("Invalid URL")?)?;
+
+        let mut response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .context("HTTP request failed")?
+            .error_for_status()?;
+
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split(';').next())
+            .map(|v| v.trim().to_lowercase())
+            .unwrap_or_default();
+
+        // SVG can carry scripts, so only raster images are mirrored
+        if !content_type.starts_with("image/") || content_type == "image/svg+xml" {
+            anyhow::bail!("Unsupported content type: {:?}", content_type);
+        }
+
+        if response
+            .content_length()
+            .is_some_and(|len| len > self.max_bytes as u64)
+        {
+            anyhow::bail!("Asset larger than {} bytes", self.max_bytes);
+        }
+
+        let mut bytes = Vec::new();
+        while let Some(chunk) = response.chunk().await.context("Failed to read asset body")? {
+            bytes.extend_from_slice(&chunk);
+            if bytes.len() > self.max_bytes {
+                anyhow::bail!("Asset larger than {} bytes", self.max_bytes);
+            }
+        }
+
+        let size = imagesize::blob_size(&bytes).context("Not a recognizable image")?;
+        let hash = hex::encode(Sha256::digest(&bytes));
+        self.write(&hash, &bytes).await?;
+
+        Ok(NewAsset {
+            kind,
+            original_url: url.to_string(),
+            hash,
+            content_type,
+            byte_size: bytes.len() as i32,
+            width: i32::try_from(size.width).ok(),
+            height: i32::try_from(size.height).ok(),
+        })
+    }
+
+    /// Delete mirrored files that no article references any more, such as
+    /// those of purged or archived articles. With `dry_run`, only count them.
+    pub async fn remove_unreferenced(&self, pool: &PgPool, dry_run: bool) -> Result<u64> {
+        let referenced: HashSet<String> = queries::get_asset_hashes(pool).await?.into_iter().collect();
+        // A scrape writes files before it saves the article that records them
+        let written_before = SystemTime::now() - UNREFERENCED_GRACE;
+
+        let mut shards = match tokio::fs::read_dir(&self.dir).await {
+            Ok(shards) => shards,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e).context("Failed to read asset directory"),
+        };
+
+        let mut removed = 0;
+        while let Some(shard) = shards.next_entry().await? {
+            if !shard.file_type().await?.is_dir() {
+                continue;
+            }
+            let mut files = tokio::fs::read_dir(shard.path()).await?;
+            while let Some(file) = files.next_entry().await? {
+                if file.file_name().to_str().is_some_and(|name| referenced.contains(name)) {
+                    continue;
+                }
+                let metadata = file.metadata().await?;
+                if !metadata.is_file() || metadata.modified()? > written_before {
+                    continue;
+                }
+                if !dry_run {
+                    tokio::fs::remove_file(file.path())
+                        .await
+                        .context("Failed to remove asset")?;
+                }
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
+    }
+
+    async fn write(&self, hash: &str, bytes: &[u8]) -> Result<()> {
+        let path = self.path_for(hash);
+        if path.exists() {
+            return Ok(());
+        }
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .context("Failed to create asset directory")?;
+        }
+
+        // Write then rename so readers never see a partial file
+        let tmp = path.with_extension("tmp");
+        tokio::fs::write(&tmp, bytes).await.context("Failed to write asset")?;
+        tokio::fs::rename(&tmp, &path).await.context("Failed to store asset")?;
+
+        Ok(())
+    }
+}
+
+/// Absolute image URLs in an article body, in document order
+fn inline_image_urls(html: &str) -> Vec<String> {
+    let fragment = Html::parse_fragment(html);
+    let img_selector = Selector::parse("img").unwrap();
+
+    let mut urls: Vec<String> = Vec::new();
+    for img in fragment.select(&img_selector) {
+        if let Some(src) = image_source(img.value()) {
+            if !urls.iter().any(|u| u == src) {
+                urls.push(src.to_string());
+            }
+        }
+    }
+    urls
+}
+
+/// The absolute URL an image is mirrored from: its `src`, or the largest
+/// `srcset` candidate if it has no `src`
+fn image_source(img: &Element) -> Option<&str> {
+    if let Some(src) = img.attr("src").filter(|src| src.starts_with("http")) {
+        return Some(src);
+    }
+
+    // Candidates are `url [width]w` or `url [density]x`; the sanitizer has
+    // already made their URLs absolute
+    let mut best: Option<(&str, f64)> = None;
+    for candidate in img.attr("srcset")?.split(',') {
+        let mut parts = candidate.split_whitespace();
+        let Some(url) = parts.next().filter(|url| url.starts_with("http")) else {
+            continue;
+        };
+        let size = parts
+            .next()
+            .and_then(|d| d.get(..d.len() - 1))
+            .and_then(|d| d.parse().ok())
+            .unwrap_or(1.0);
+        if best.is_none_or(|(_, best)| size > best) {
+            best = Some((url, size));
+        }
+    }
+    best.map(|(url, _)| url)
+}
+
+/// Point every image whose source is in `local` at its mirrored copy. Their
+/// `srcset` is dropped, since browsers would prefer it over the copy.
+fn rewrite_image_urls(html: &str, local: &HashMap<String, String>) -> String {
+    let mut fragment = Html::parse_fragment(html);
+    let img_selector = Selector::parse("img").unwrap();
+
+    let images: Vec<_> = fragment
+        .select(&img_selector)
+        .filter_map(|img| Some((img.id(), local.get(image_source(img.value())?)?)))
+        .collect();
+
+    for (id, url) in images {
+        let Some(mut node) = fragment.tree.get_mut(id) else {
+            continue;
+        };
+        if let Node::Element(element) = node.value() {
+            let srcset = element.attrs.keys().find(|name| &*name.local == "srcset").cloned();
+            let src = element
+                .attrs
+                .keys()
+                .find(|name| &*name.local == "src")
+                .cloned()
+                .or_else(|| {
+                    srcset.clone().map(|mut name| {
+                        name.local = "src".into();
+                        name
+                    })
+                });
+            if let Some(srcset) = srcset {
+                element.attrs.shift_remove(&srcset);
+            }
+            if let Some(src) = src {
+                element.attrs.insert(src, url.as_str().into());
+            }
+        }
+    }
+
+    fragment.root_element().inner_html()
+}
+
+const MAX_REDIRECTS: usize = 5;
+
+/// Reject URLs that aren't http(s) or that name a non-public IP directly.
+/// Hostnames are checked when they resolve, by `PublicResolver`.
+fn check_url(url: &Url) -> Result<()> {
+    if !matches!(url.scheme(), "http" | "https") {
+        anyhow::bail!("Unsupported URL scheme: {}", url.scheme());
+    }
+    let ip = match url.host() {
+        Some(Host::Domain(_)) => return Ok(()),
+        Some(Host::Ipv4(ip)) => IpAddr::V4(ip),
+        Some(Host::Ipv6(ip)) => IpAddr::V6(ip),
+        None => anyhow::bail!("URL has no host"),
+    };
+    if !is_public(ip) {
+        anyhow::bail!("Refusing to fetch from non-public address {}", ip);
+    }
+    Ok(())
+}
+
+/// DNS resolver that drops loopback, private and other non-public addresses,
+/// so a hostname can't be used to reach internal services
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| is_public(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} has no public address", host).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                || a == 0
+                // Carrier-grade NAT, 100.64.0.0/10
+                || (a == 100 && (b & 0xc0) == 64))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(v4) = ip.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(v4));
+            }
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // Unique local, fc00::/7
+                || (first & 0xfe00) == 0xfc00
+                // Link-local, fe80::/10
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_non_public_addresses() {
+        for ip in [
+            "127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254", "0.0.0.0",
+            "100.64.0.1", "::1", "fd00::1", "fe80::1", "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in ["93.184.216.34", "8.8.8.8", "2606:4700::1111"] {
+            assert!(is_public(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[test]
+    fn checks_url_scheme_and_literal_hosts() {
+        let check = |url: &str| check_url(&Url::parse(url).unwrap()).is_ok();
+        assert!(check("https://example.com/a.png"));
+        assert!(!check("file:///etc/passwd"));
+        assert!(!check("http://127.0.0.1:5432/"));
+        assert!(!check("http://[::1]/a.png"));
+        assert!(!check("http://169.254.169.254/latest/meta-data"));
+    }
+
+    #[test]
+    fn rewrites_matching_image_sources() {
+        let local = HashMap::from([(
+            "https://example.com/a.png?x=1&y=2".to_string(),
+            "/api/assets/abc".to_string(),
+        )]);
+        let html = r#"<p>See https://example.com/a.png?x=1&amp;y=2</p><img src="https://example.com/a.png?x=1&amp;y=2" alt="a"><img src="https://example.com/b.png">"#;
+        assert_eq!(
+            rewrite_image_urls(html, &local),
+            r#"<p>See https://example.com/a.png?x=1&amp;y=2</p><img src="/api/assets/abc" alt="a"><img src="https://example.com/b.png">"#
+        );
+    }
+
+    #[test]
+    fn rewrites_srcset_images() {
+        let local = HashMap::from([
+            ("https://cdn.example.com/a.png".to_string(), "/api/assets/abc".to_string()),
+            ("https://cdn.example.com/c-800.png".to_string(), "/api/assets/def".to_string()),
+        ]);
+        // A mirrored src drops the srcset; an image with only a srcset gets
+        // its largest candidate mirrored as src
+        let html = concat!(
+            r#"<img src="https://cdn.example.com/a.png" srcset="https://cdn.example.com/a-2x.png 2x" alt="a">"#,
+            r#"<img srcset="https://cdn.example.com/c-400.png 400w, https://cdn.example.com/c-800.png 800w">"#,
+            r#"<img src="https://cdn.example.com/b.png" srcset="https://cdn.example.com/b-2x.png 2x">"#,
+        );
+        assert_eq!(
+            rewrite_image_urls(html, &local),
+            concat!(
+                r#"<img src="/api/assets/abc" alt="a">"#,
+                r#"<img src="/api/assets/def">"#,
+                r#"<img src="https://cdn.example.com/b.png" srcset="https://cdn.example.com/b-2x.png 2x">"#,
+            )
+        );
+        assert_eq!(
+            inline_image_urls(html),
+            [
+                "https://cdn.example.com/a.png",
+                "https://cdn.example.com/c-800.png",
+                "https://cdn.example.com/b.png",
+            ]
+        );
+    }
+}
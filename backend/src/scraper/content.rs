@@ -0,0 +1,571 @@
+use scraper::{ElementRef, Html, Node, Selector};
+use std::cell::Cell;
+
+use super::links::extract_links;
+use super::sanitize::sanitize_html;
+use crate::db::models::{ContentFormat, NewLink};
+
+// Elements whose contents never belong in an article body
+const SKIPPED_TAGS: &[&str] = &[
+    "script", "style", "noscript", "template", "head", "svg", "iframe", "form", "button",
+];
+
+const BLOCK_TAGS: &[&str] = &[
+    "address", "article", "aside", "blockquote", "details", "dd", "div", "dl", "dt",
+    "figcaption", "figure", "footer", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hr",
+    "li", "main", "nav", "ol", "p", "pre", "section", "summary", "table", "ul",
+];
+
+/// Article body in every representation we store
+#[derive(Debug, Clone)]
+pub struct ArticleBody {
+    pub text: String,
+    pub html: Option<String>,
+    pub markdown: Option<String>,
+    pub format: ContentFormat,
+    pub links: Vec<NewLink>,
+}
+
+impl ArticleBody {
+    /// Build all representations from an extracted HTML fragment. The HTML is
+    /// sanitized first, with relative URLs resolved against the article URL.
+    pub fn from_html(html: &str, article_url: &str) -> Self {
+        let html = sanitize_html(html, article_url);
+        Self {
+            text: html_to_text(&html),
+            markdown: Some(html_to_markdown(&html)),
+            links: extract_links(&html, article_url),
+            html: Some(html),
+            format: ContentFormat::Html,
+        }
+    }
+
+    /// Body where only plain text is available (e.g. fallback extraction)
+    pub fn from_text(text: String) -> Self {
+        Self {
+            text,
+            html: None,
+            markdown: None,
+            format: ContentFormat::Text,
+            links: Vec::new(),
+        }
+    }
+}
+
+pub fn html_to_markdown(html: &str) -> String {
+    render(html, Mode::Markdown)
+}
+
+pub fn html_to_text(html: &str) -> String {
+    render(html, Mode::Text)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Markdown,
+    Text,
+}
+
+fn render(html: &str, mode: Mode) -> String {
+    let fragment = Html::parse_fragment(html);
+    let renderer = Renderer {
+        mode,
+        in_link: Cell::new(false),
+    };
+    renderer.blocks(fragment.root_element()).join("\n\n")
+}
+
+struct Renderer {
+    mode: Mode,
+    /// Set while rendering a link label, where brackets must be escaped
+    in_link: Cell<bool>,
+}
+
+impl Renderer {
+    fn markdown(&self) -> bool {
+        self.mode == Mode::Markdown
+    }
+
+    /// Render the children of a block container, one string per block
+    fn blocks(&self, el: ElementRef) -> Vec<String> {
+        let mut blocks = Vec::new();
+        let mut inline = String::new();
+
+        for child in el.children() {
+            match child.value() {
+                Node::Text(text) => inline.push_str(&collapse_whitespace(text)),
+                Node::Element(element) => {
+                    let name = element.name();
+                    if SKIPPED_TAGS.contains(&name) {
+                        continue;
+                    }
+
+                    let child_el = match ElementRef::wrap(child) {
+                        Some(e) => e,
+                        None => continue,
+                    };
+
+                    if BLOCK_TAGS.contains(&name) {
+                        self.flush_inline(&mut blocks, &mut inline);
+                        let block = self.block(child_el);
+                        if !block.trim().is_empty() {
+                            blocks.push(block);
+                        }
+                    } else {
+                        inline.push_str(&self.inline(child_el));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        self.flush_inline(&mut blocks, &mut inline);
+        blocks
+    }
+
+    fn flush_inline(&self, blocks: &mut Vec<String>, inline: &mut String) {
+        let text = inline
+            .split('\n')
+            .map(|line| {
+                let squeezed = squeeze_spaces(line.trim());
+                // Keep Markdown hard line breaks
+                if self.markdown() && line.ends_with("  ") {
+                    format!("{}  ", squeezed)
+                } else {
+                    squeezed
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let text = text.trim();
+
+        if !text.is_empty() {
+            blocks.push(text.to_string());
+        }
+        inline.clear();
+    }
+
+    fn block(&self, el: ElementRef) -> String {
+        let name = el.value().name();
+
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let text = self.inline_text(el);
+                if self.markdown() && !text.is_empty() {
+                    let level = name[1..].parse::<usize>().unwrap_or(1);
+                    format!("{} {}", "#".repeat(level), text)
+                } else {
+                    text
+                }
+            }
+            "ul" | "ol" => self.list(el, name == "ol"),
+            "li" => indent_item("- ", &self.blocks(el).join("\n")),
+            "blockquote" => {
+                let inner = self.blocks(el).join("\n\n");
+                if self.markdown() {
+                    inner
+                        .lines()
+                        .map(|line| if line.is_empty() { ">".to_string() } else { format!("> {}", line) })
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                } else {
+                    inner
+                }
+            }
+            "pre" => {
+                let code = el.text().collect::<String>();
+                let code = code.trim_end_matches('\n');
+                if self.markdown() {
+                    let fence = "`".repeat(longest_backtick_run(code).max(2) + 1);
+                    format!(
+                        "{}{}\n{}\n{}",
+                        fence,
+                        code_language(el).unwrap_or_default(),
+                        code,
+                        fence
+                    )
+                } else {
+                    code.to_string()
+                }
+            }
+            "hr" => {
+                if self.markdown() {
+                    "---".to_string()
+                } else {
+                    String::new()
+                }
+            }
+            "table" => self.table(el),
+            _ => self.blocks(el).join("\n\n"),
+        }
+    }
+
+    fn list(&self, el: ElementRef, ordered: bool) -> String {
+        let mut index = el
+            .value()
+            .attr("start")
+            .and_then(|s| s.trim().parse::<usize>().ok())
+            .unwrap_or(1);
+        let mut items = Vec::new();
+
+        for child in el.children().filter_map(ElementRef::wrap) {
+            match child.value().name() {
+                "li" => {
+                    let marker = if ordered {
+                        format!("{}. ", index)
+                    } else {
+                        "- ".to_string()
+                    };
+                    index += 1;
+
+                    let body = self.blocks(child).join("\n");
+                    if !body.trim().is_empty() {
+                        items.push(indent_item(&marker, &body));
+                    }
+                }
+                // Sloppy markup nests lists directly inside lists
+                "ul" | "ol" => {
+                    let nested = self.list(child, child.value().name() == "ol");
+                    if !nested.is_empty() {
+                        items.push(indent_item("  ", &nested));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        items.join("\n")
+    }
+
+    fn table(&self, el: ElementRef) -> String {
+        let row_selector = Selector::parse("tr").unwrap();
+        let rows: Vec<Vec<String>> = el
+            .select(&row_selector)
+            .map(|row| {
+                row.children()
+                    .filter_map(ElementRef::wrap)
+                    .filter(|cell| matches!(cell.value().name(), "th" | "td"))
+                    .map(|cell| {
+                        let text = self.inline_text(cell);
+                        if self.markdown() {
+                            text.replace('|', "\\|")
+                        } else {
+                            text
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .filter(|cells| !cells.is_empty())
+            .collect();
+
+        if rows.is_empty() {
+            return String::new();
+        }
+
+        if !self.markdown() {
+            return rows
+                .iter()
+                .map(|cells| cells.join(" | "))
+                .collect::<Vec<_>>()
+                .join("\n");
+        }
+
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let format_row = |cells: &Vec<String>| {
+            let mut padded = cells.clone();
+            padded.resize(columns, String::new());
+            format!("| {} |", padded.join(" | "))
+        };
+
+        let mut lines = vec![format_row(&rows[0])];
+        lines.push(format!("|{}", " --- |".repeat(columns)));
+        lines.extend(rows[1..].iter().map(format_row));
+        lines.join("\n")
+    }
+
+    /// Inline content of an element collapsed onto a single line
+    fn inline_text(&self, el: ElementRef) -> String {
+        squeeze_spaces(collapse_whitespace(&self.inline_children(el)).trim())
+    }
+
+    fn inline_children(&self, el: ElementRef) -> String {
+        let mut out = String::new();
+
+        for child in el.children() {
+            match child.value() {
+                Node::Text(text) => {
+                    let text = collapse_whitespace(text);
+                    if self.in_link.get() {
+                        out.push_str(&escape_brackets(&text));
+                    } else {
+                        out.push_str(&text);
+                    }
+                }
+                Node::Element(element) => {
+                    if SKIPPED_TAGS.contains(&element.name()) {
+                        continue;
+                    }
+                    if let Some(child_el) = ElementRef::wrap(child) {
+                        out.push_str(&self.inline(child_el));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        out
+    }
+
+    fn inline(&self, el: ElementRef) -> String {
+        let name = el.value().name();
+
+        if !self.markdown() {
+            return match name {
+                "br" => "\n".to_string(),
+                "img" => String::new(),
+                _ => self.inline_children(el),
+            };
+        }
+
+        match name {
+            "br" => "  \n".to_string(),
+            "a" => match el.value().attr("href").map(str::trim) {
+                Some(href) if !href.is_empty() && !href.starts_with("javascript:") => {
+                    let in_link = self.in_link.replace(true);
+                    let text = self.inline_children(el);
+                    self.in_link.set(in_link);
+
+                    let label = if text.trim().is_empty() {
+                        escape_brackets(href)
+                    } else {
+                        text.trim().to_string()
+                    };
+                    surround_like(&text, &format!("[{}]({})", label, destination(href)))
+                }
+                _ => self.inline_children(el),
+            },
+            "img" => match el.value().attr("src").map(str::trim) {
+                Some(src) if !src.is_empty() => format!(
+                    "![{}]({})",
+                    escape_brackets(el.value().attr("alt").unwrap_or("").trim()),
+                    destination(src)
+                ),
+                _ => String::new(),
+            },
+            "strong" | "b" => emphasize(&self.inline_children(el), "**"),
+            "em" | "i" => emphasize(&self.inline_children(el), "*"),
+            "del" | "s" | "strike" => emphasize(&self.inline_children(el), "~~"),
+            "code" => {
+                let code = el.text().collect::<String>();
+                if code.is_empty() {
+                    String::new()
+                } else if code.contains('`') {
+                    let fence = "`".repeat(longest_backtick_run(&code) + 1);
+                    format!("{} {} {}", fence, code, fence)
+                } else {
+                    format!("`{}`", code)
+                }
+            }
+            _ => self.inline_children(el),
+        }
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_space = false;
+
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !in_space {
+                out.push(' ');
+            }
+            in_space = true;
+        } else {
+            out.push(c);
+            in_space = false;
+        }
+    }
+
+    out
+}
+
+fn squeeze_spaces(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c == ' ' && out.ends_with(' ') {
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Wrap inline content in a marker, keeping surrounding spaces outside it
+fn emphasize(content: &str, marker: &str) -> String {
+    if content.trim().is_empty() {
+        return content.to_string();
+    }
+    surround_like(content, &format!("{}{}{}", marker, content.trim(), marker))
+}
+
+/// Re-apply the leading/trailing whitespace of `original` around `rendered`
+fn surround_like(original: &str, rendered: &str) -> String {
+    let lead = if original.starts_with(char::is_whitespace) { " " } else { "" };
+    let trail = if original.ends_with(char::is_whitespace) { " " } else { "" };
+    format!("{}{}{}", lead, rendered, trail)
+}
+
+/// Escape brackets that would otherwise end a link label early
+fn escape_brackets(text: &str) -> String {
+    text.replace('[', "\\[").replace(']', "\\]")
+}
+
+/// A link destination, in angle brackets if it contains characters that
+/// would end a bare one
+fn destination(url: &str) -> String {
+    if url.contains(|c: char| c.is_whitespace() || matches!(c, '(' | ')' | '<' | '>')) {
+        format!("<{}>", url.replace('<', "%3C").replace('>', "%3E"))
+    } else {
+        url.to_string()
+    }
+}
+
+/// Length of the longest run of backticks, so code can be fenced with more
+fn longest_backtick_run(code: &str) -> usize {
+    code.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
+
+fn indent_item(marker: &str, body: &str) -> String {
+    let padding = " ".repeat(marker.len());
+    body.lines()
+        .enumerate()
+        .map(|(i, line)| {
+            if i == 0 {
+                format!("{}{}", marker, line)
+            } else if line.is_empty() {
+                String::new()
+            } else {
+                format!("{}{}", padding, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn code_language(pre: ElementRef) -> Option<String> {
+    let classes = pre
+        .children()
+        .filter_map(ElementRef::wrap)
+        .find(|child| child.value().name() == "code")
+        .unwrap_or(pre)
+        .value()
+        .classes()
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+
+    classes
+        .iter()
+        .find_map(|class| class.strip_prefix("language-").or_else(|| class.strip_prefix("lang-")))
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_blocks_and_inline_formatting() {
+        let html = "<h2>Title</h2><p>Some <strong>bold</strong> and <em>italic</em> text.</p>\
+                    <ul><li>one</li><li>two</li></ul><blockquote><p>quoted</p></blockquote>";
+        assert_eq!(
+            html_to_markdown(html),
+            "## Title\n\nSome **bold** and *italic* text.\n\n- one\n- two\n\n> quoted"
+        );
+        assert_eq!(
+            html_to_text(html),
+            "Title\n\nSome bold and italic text.\n\n- one\n- two\n\nquoted"
+        );
+    }
+
+    #[test]
+    fn renders_ordered_lists_from_start() {
+        assert_eq!(
+            html_to_markdown(r#"<ol start="3"><li>three</li><li>four</li></ol>"#),
+            "3. three\n4. four"
+        );
+    }
+
+    #[test]
+    fn renders_tables() {
+        let html = "<table><tr><th>a</th><th>b</th></tr><tr><td>1</td><td>x|y</td></tr></table>";
+        assert_eq!(
+            html_to_markdown(html),
+            "| a | b |\n| --- | --- |\n| 1 | x\\|y |"
+        );
+    }
+
+    #[test]
+    fn renders_links_and_images() {
+        assert_eq!(
+            html_to_markdown(r#"<p>See <a href="https://example.com/a">the docs</a>.</p>"#),
+            "See [the docs](https://example.com/a)."
+        );
+        assert_eq!(
+            html_to_markdown(r#"<p><img src="https://example.com/x.png" alt="A chart"></p>"#),
+            "![A chart](https://example.com/x.png)"
+        );
+        assert_eq!(
+            html_to_markdown(r#"<p><a href="javascript:void(0)">click</a></p>"#),
+            "click"
+        );
+    }
+
+    #[test]
+    fn wraps_destinations_with_parentheses() {
+        assert_eq!(
+            html_to_markdown(r#"<p><a href="https://en.wikipedia.org/wiki/Rust_(language)">Rust</a></p>"#),
+            "[Rust](<https://en.wikipedia.org/wiki/Rust_(language)>)"
+        );
+        assert_eq!(
+            html_to_markdown(r#"<p><img src="https://example.com/a (1).png" alt="x"></p>"#),
+            "![x](<https://example.com/a (1).png>)"
+        );
+    }
+
+    #[test]
+    fn escapes_brackets_in_labels() {
+        assert_eq!(
+            html_to_markdown(r#"<p><a href="https://example.com">[1] paper]</a></p>"#),
+            r"[\[1\] paper\]](https://example.com)"
+        );
+        assert_eq!(
+            html_to_markdown(r#"<p><img src="https://example.com/x.png" alt="a [b]"></p>"#),
+            r"![a \[b\]](https://example.com/x.png)"
+        );
+        // Brackets outside links are left alone
+        assert_eq!(html_to_markdown("<p>[draft]</p>"), "[draft]");
+    }
+
+    #[test]
+    fn keeps_images_inside_link_labels() {
+        assert_eq!(
+            html_to_markdown(r#"<a href="https://example.com"><img src="https://example.com/x.png" alt="x"></a>"#),
+            "[![x](https://example.com/x.png)](https://example.com)"
+        );
+    }
+
+    #[test]
+    fn fences_code_longer_than_its_backticks() {
+        assert_eq!(
+            html_to_markdown(r#"<pre><code class="language-rust">let x = 1;</code></pre>"#),
+            "```rust\nlet x = 1;\n```"
+        );
+        assert_eq!(
+            html_to_markdown("<pre>```\nnested\n```</pre>"),
+            "````\n```\nnested\n```\n````"
+        );
+        assert_eq!(html_to_markdown("<p><code>a``b</code></p>"), "``` a``b ```");
+        assert_eq!(html_to_markdown("<p><code>a`b</code></p>"), "`` a`b ``");
+    }
+}
@@ -0,0 +1,212 @@
+//! Short article summaries for listings, cut from the page's own description
+//! or the plain-text body without splitting characters, words or, where
+//! possible, sentences
+
+use scraper::{Html, Selector};
+
+use crate::text::{inside_word, is_cjk};
+
+/// Longest excerpt, in characters
+pub const MAX_CHARS: usize = 200;
+/// A sentence end closer to the start than this is ignored, so excerpts
+/// aren't cut down to a short first sentence
+const MIN_CHARS: usize = MAX_CHARS / 2;
+
+/// Descriptions publishers write for search results and link previews, most
+/// preferred first
+const DESCRIPTION_SELECTORS: &[&str] = &[
+    r#"meta[property="og:description"]"#,
+    r#"meta[name="description"]"#,
+    r#"meta[name="twitter:description"]"#,
+];
+
+const SENTENCE_ENDS: &[char] = &['。', '！', '？', '!', '?', '.', '…'];
+/// Closing quotes and brackets kept with the sentence they end
+const CLOSERS: &[char] = &['"', '\'', '”', '’', '」', '』', ')', '）', '】'];
+/// Dangling punctuation dropped before the ellipsis
+const TRAILING: &[char] = &[',', ';', ':', '，', '；', '：', '、', '-', '—', '(', '（'];
+
+/// The page's meta description if it has one, otherwise the start of `text`
+pub fn from_document(document: &Html, title: &str, text: &str) -> Option<String> {
+    description(document, title).or_else(|| from_text(text))
+}
+
+/// The start of `text`, up to `MAX_CHARS`. `None` if it is blank.
+pub fn from_text(text: &str) -> Option<String> {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        return None;
+    }
+    Some(truncate(&text))
+}
+
+/// The first non-empty description meta tag, unless it only repeats the title
+fn description(document: &Html, title: &str) -> Option<String> {
+    DESCRIPTION_SELECTORS.iter().find_map(|selector| {
+        let selector = Selector::parse(selector).ok()?;
+        document
+            .select(&selector)
+            .filter_map(|meta| meta.value().attr("content"))
+            .filter_map(from_text)
+            .find(|description| description.trim() != title.trim())
+    })
+}
+
+/// Cut whitespace-collapsed `text` to at most `MAX_CHARS`: after the last
+/// sentence that fits, or else at a word boundary with an ellipsis
+fn truncate(text: &str) -> String {
+    let Some((limit, _)) = text.char_indices().nth(MAX_CHARS) else {
+        return text.to_string();
+    };
+    let min = text.char_indices().nth(MIN_CHARS).map_or(0, |(i, _)| i);
+
+    if let Some(end) = sentence_end(text, min, limit) {
+        return text[..end].to_string();
+    }
+
+    // Leave room for the ellipsis. CJK text can be cut between any two
+    // characters; other text at spaces.
+    let mut cut = text
+        .char_indices()
+        .nth(MAX_CHARS - 1)
+        .map_or(limit, |(i, _)| i);
+    if inside_word(text, cut) {
+        if let Some(space) = text[min..cut].rfind(' ') {
+            cut = min + space;
+        }
+    }
+    let head = text[..cut].trim_end().trim_end_matches(TRAILING).trim_end();
+    format!("{}…", head)
+}
+
+/// Byte offset just past the last sentence ending between `min` and `limit`
+fn sentence_end(text: &str, min: usize, limit: usize) -> Option<usize> {
+    text[..limit]
+        .char_indices()
+        .rev()
+        .take_while(|&(i, _)| i >= min)
+        .filter(|&(_, c)| SENTENCE_ENDS.contains(&c))
+        .map(|(i, c)| {
+            let rest = &text[i + c.len_utf8()..];
+            let closers: usize = rest
+                .chars()
+                .take_while(|c| CLOSERS.contains(c))
+                .map(char::len_utf8)
+                .sum();
+            (c, i + c.len_utf8() + closers)
+        })
+        .find(|&(c, end)| {
+            // ASCII punctuation only ends a sentence before a space or CJK
+            // text, so "3.5" and "example.com" aren't cut
+            end <= limit
+                && (!c.is_ascii() || {
+                    let next = text[end..].chars().next();
+                    next.is_none_or(|next| next.is_whitespace() || is_cjk(next))
+                })
+        })
+        .map(|(_, end)| end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_text_collapses_whitespace() {
+        assert_eq!(from_text("  A short\n\n post.  ").as_deref(), Some("A short post."));
+        assert_eq!(from_text(" \n\t "), None);
+    }
+
+    #[test]
+    fn truncate_after_last_sentence_that_fits() {
+        let text = format!("{}. {}. More text after the limit.", "a".repeat(120), "b".repeat(70));
+        assert_eq!(truncate(&text), format!("{}. {}.", "a".repeat(120), "b".repeat(70)));
+    }
+
+    #[test]
+    fn truncate_ignores_short_first_sentence() {
+        let text = format!("Short. {}", "word ".repeat(60));
+        let excerpt = truncate(&text);
+        assert!(excerpt.ends_with("word…"), "{}", excerpt);
+        assert!(excerpt.chars().count() <= MAX_CHARS);
+    }
+
+    #[test]
+    fn truncate_keeps_numbers_and_domains() {
+        // Neither "3.5" nor "example.com" ends a sentence
+        let text = format!("{} GPT-3.5 on example.com {}", "x ".repeat(60), "y ".repeat(60));
+        let excerpt = truncate(&text);
+        assert!(excerpt.ends_with('…'), "{}", excerpt);
+        assert!(excerpt.contains("GPT-3.5 on example.com"));
+    }
+
+    #[test]
+    fn truncate_at_word_boundary() {
+        let text = format!("{} extraordinarily", "word ".repeat(39));
+        let excerpt = truncate(&text);
+        assert_eq!(excerpt, format!("{}…", "word ".repeat(39).trim_end()));
+        assert!(excerpt.chars().count() <= MAX_CHARS);
+    }
+
+    #[test]
+    fn truncate_drops_trailing_punctuation() {
+        let text = format!("{}, {}", "a ".repeat(99).trim_end(), "b".repeat(50));
+        assert_eq!(truncate(&text), format!("{}…", "a ".repeat(99).trim_end()));
+    }
+
+    #[test]
+    fn truncate_cjk_between_characters() {
+        let text = "模型".repeat(150);
+        let excerpt = truncate(&text);
+        assert_eq!(excerpt.chars().count(), MAX_CHARS);
+        assert!(excerpt.starts_with("模型模型"));
+        assert!(excerpt.ends_with('…'));
+
+        let text = format!("{}。「{}", "发".repeat(120), "布".repeat(100));
+        assert_eq!(truncate(&text), format!("{}。", "发".repeat(120)));
+    }
+
+    #[test]
+    fn truncate_keeps_closing_quotes() {
+        let text = format!("{}。」{}", "发".repeat(150), "布".repeat(100));
+        assert_eq!(truncate(&text), format!("{}。」", "发".repeat(150)));
+    }
+
+    #[test]
+    fn short_text_unchanged() {
+        let text = "a".repeat(MAX_CHARS);
+        assert_eq!(truncate(&text), text);
+    }
+
+    #[test]
+    fn prefers_og_description() {
+        let document = Html::parse_document(
+            r#"<html><head>
+            <meta name="description" content="Plain description">
+            <meta property="og:description" content="Open Graph description">
+            </head><body></body></html>"#,
+        );
+        assert_eq!(
+            from_document(&document, "Title", "Body text").as_deref(),
+            Some("Open Graph description")
+        );
+    }
+
+    #[test]
+    fn skips_description_repeating_title() {
+        let document = Html::parse_document(
+            r#"<html><head>
+            <meta property="og:description" content=" The Title ">
+            <meta name="description" content="">
+            <meta name="twitter:description" content="Twitter description">
+            </head><body></body></html>"#,
+        );
+        assert_eq!(
+            from_document(&document, "The Title", "Body text").as_deref(),
+            Some("Twitter description")
+        );
+
+        let bare = Html::parse_document("<html><head><title>The Title</title></head></html>");
+        assert_eq!(from_document(&bare, "The Title", " Body\ntext ").as_deref(), Some("Body text"));
+    }
+}
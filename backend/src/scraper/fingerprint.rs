@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+
+use crate::text::{fnv1a, terms};
+
+/// Maximum Hamming distance between SimHashes of near-duplicate articles
+pub const SIMHASH_MAX_DISTANCE: i32 = 3;
+
+/// 64-bit SimHash of an article's title and text, stored as a signed BIGINT.
+/// Reposts and lightly edited copies differ in only a few bits.
+pub fn simhash(title: &str, content: &str) -> i64 {
+    let terms = terms(&format!("{}\n{}", title, content));
+
+    // Adjacent term pairs make the hash sensitive to word order as well
+    let mut weights: HashMap<String, i32> = HashMap::new();
+    for term in &terms {
+        *weights.entry(term.clone()).or_default() += 1;
+    }
+    for pair in terms.windows(2) {
+        *weights
+            .entry(format!("{} {}", pair[0], pair[1]))
+            .or_default() += 1;
+    }
+
+    let mut bits = [0i32; 64];
+    for (feature, weight) in weights {
+        let hash = fnv1a(feature.as_bytes());
+        for (i, bit) in bits.iter_mut().enumerate() {
+            if hash >> i & 1 == 1 {
+                *bit += weight;
+            } else {
+                *bit -= weight;
+            }
+        }
+    }
+
+    let hash = bits
+        .iter()
+        .enumerate()
+        .filter(|(_, &bit)| bit > 0)
+        .fold(0u64, |hash, (i, _)| hash | 1 << i);
+    hash as i64
+}
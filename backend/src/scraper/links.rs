@@ -0,0 +1,124 @@
+use scraper::{Html, Selector};
+use url::Url;
+
+use crate::db::models::NewLink;
+
+/// Outbound links in an article body, in document order.
+///
+/// Links back to the article's own site (navigation, previous issues) are
+/// skipped, as are non-HTTP schemes. Fragments are dropped so the same page
+/// linked with different anchors counts once.
+pub fn extract_links(html: &str, article_url: &str) -> Vec<NewLink> {
+    let fragment = Html::parse_fragment(html);
+    let link_selector = Selector::parse("a[href]").unwrap();
+
+    let base = Url::parse(article_url).ok();
+    let own_domain = base.as_ref().and_then(|u| u.host_str()).map(normalize_domain);
+
+    let mut links = Vec::new();
+    for element in fragment.select(&link_selector) {
+        let href = match element.value().attr("href") {
+            Some(h) => h.trim(),
+            None => continue,
+        };
+
+        let parsed = match &base {
+            Some(base) => base.join(href),
+            None => Url::parse(href),
+        };
+        let url = match parsed.ok().and_then(canonical) {
+            Some(u) => u,
+            None => continue,
+        };
+
+        let domain = match url.host_str() {
+            Some(host) => normalize_domain(host),
+            None => continue,
+        };
+        if own_domain.as_deref() == Some(domain.as_str()) {
+            continue;
+        }
+
+        let anchor_text = element.text().collect::<String>();
+        let anchor_text = anchor_text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        links.push(NewLink {
+            url: url.to_string(),
+            domain,
+            anchor_text: (!anchor_text.is_empty()).then_some(anchor_text),
+            position: links.len() as i32,
+        });
+    }
+
+    links
+}
+
+/// A URL in the form links are stored in, so lookups match stored links.
+/// `None` if it isn't an absolute http(s) URL.
+pub fn normalize_url(url: &str) -> Option<String> {
+    canonical(Url::parse(url.trim()).ok()?).map(String::from)
+}
+
+/// Domain as stored for links: lowercase, without `www.`
+pub fn normalize_domain(host: &str) -> String {
+    let host = host.trim().to_lowercase();
+    host.strip_prefix("www.").map(str::to_string).unwrap_or(host)
+}
+
+fn canonical(mut url: Url) -> Option<Url> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    url.set_fragment(None);
+    Some(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_urls_like_stored_links() {
+        assert_eq!(
+            normalize_url("https://Example.com#intro").as_deref(),
+            Some("https://example.com/")
+        );
+        assert_eq!(
+            normalize_url(" https://example.com/a/b?q=1#x ").as_deref(),
+            Some("https://example.com/a/b?q=1")
+        );
+        assert_eq!(normalize_url("ftp://example.com/file"), None);
+        assert_eq!(normalize_url("/relative/path"), None);
+    }
+
+    #[test]
+    fn normalizes_domains() {
+        assert_eq!(normalize_domain("WWW.Example.COM"), "example.com");
+        assert_eq!(normalize_domain("docs.example.com"), "docs.example.com");
+    }
+
+    #[test]
+    fn extracts_outbound_links() {
+        let html = r#"<p><a href="/about">About</a> <a href="https://www.Other.org/post#top">A
+            post</a> <a href="mailto:a@b.c">mail</a> <a href="https://other.org/post">again</a></p>"#;
+        let links = extract_links(html, "https://news.example.com/article/1");
+
+        let urls: Vec<_> = links.iter().map(|l| l.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            ["https://www.other.org/post", "https://other.org/post"]
+        );
+        assert_eq!(links[0].domain, "other.org");
+        assert_eq!(links[0].anchor_text.as_deref(), Some("A post"));
+        assert_eq!(links[1].position, 1);
+    }
+
+    #[test]
+    fn skips_links_to_the_articles_own_site() {
+        let links = extract_links(
+            r#"<a href="https://www.example.com/prev">prev</a>"#,
+            "https://example.com/article/1",
+        );
+        assert!(links.is_empty());
+    }
+}
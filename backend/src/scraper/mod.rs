@@ -1,8 +1,15 @@
 pub mod article;
+pub mod assets;
 pub mod client;
+pub mod content;
+pub mod excerpt;
+pub mod fingerprint;
+pub mod links;
 pub mod listing;
+pub mod sanitize;
 pub mod selectors;
 pub mod sources;
+pub mod tagger;
 
 pub use sources::{Source, SourceInfo};
 
@@ -0,0 +1,178 @@
+use ammonia::{Builder, UrlRelative};
+use scraper::{Html, Selector};
+use std::borrow::Cow;
+use url::Url;
+
+// Markup we keep when storing third-party article bodies
+const ALLOWED_TAGS: &[&str] = &[
+    "a", "b", "blockquote", "br", "code", "del", "div", "em", "figcaption", "figure", "h1", "h2",
+    "h3", "h4", "h5", "h6", "hr", "i", "img", "li", "ol", "p", "pre", "s", "span", "strong", "sub",
+    "sup", "table", "tbody", "td", "tfoot", "th", "thead", "tr", "ul",
+];
+
+// Elements removed together with everything inside them
+const STRIPPED_WITH_CONTENT: &[&str] = &[
+    "script", "style", "iframe", "noscript", "object", "embed", "template", "svg", "form",
+];
+
+// Hosts that only ever serve analytics beacons
+const TRACKER_HOSTS: &[&str] = &[
+    "www.google-analytics.com",
+    "google-analytics.com",
+    "stats.g.doubleclick.net",
+    "pixel.wp.com",
+    "bat.bing.com",
+    "www.facebook.com",
+    "hm.baidu.com",
+];
+
+/// Clean third-party HTML for storage and display.
+///
+/// Only allowlisted tags and attributes survive, scripts/iframes/event handlers
+/// and tracking pixels are dropped, relative URLs are resolved against
+/// `base_url`, and links get `rel="noopener noreferrer"`.
+pub fn sanitize_html(html: &str, base_url: &str) -> String {
+    let html = remove_tracking_pixels(html);
+    let base = Url::parse(base_url).ok();
+
+    let mut builder = Builder::empty();
+    builder
+        .tags(ALLOWED_TAGS.iter().copied().collect())
+        .clean_content_tags(STRIPPED_WITH_CONTENT.iter().copied().collect())
+        .add_tag_attributes("a", ["href", "title"])
+        .add_tag_attributes("img", ["src", "srcset", "alt", "title", "width", "height"])
+        .add_tag_attributes("code", ["class"])
+        .add_tag_attributes("pre", ["class"])
+        .add_tag_attributes("td", ["colspan", "rowspan"])
+        .add_tag_attributes("th", ["colspan", "rowspan"])
+        .add_tag_attributes("ol", ["start"])
+        .url_schemes(["http", "https", "mailto"].into_iter().collect())
+        .link_rel(Some("noopener noreferrer"))
+        .strip_comments(true)
+        .attribute_filter(move |element, attribute, value| match (element, attribute) {
+            // ammonia doesn't treat srcset as a URL attribute
+            ("img", "srcset") => srcset(value, base.as_ref()).map(Cow::Owned),
+            // Only syntax highlighting hints are worth keeping
+            ("code" | "pre", "class") => {
+                let languages: Vec<&str> = value
+                    .split_whitespace()
+                    .filter(|class| class.starts_with("language-") || class.starts_with("lang-"))
+                    .collect();
+                if languages.is_empty() {
+                    None
+                } else {
+                    Some(Cow::Owned(languages.join(" ")))
+                }
+            }
+            _ => Some(Cow::Borrowed(value)),
+        });
+
+    if let Ok(base) = Url::parse(base_url) {
+        builder.url_relative(UrlRelative::RewriteWithBase(base));
+    } else {
+        builder.url_relative(UrlRelative::Deny);
+    }
+
+    builder.clean(&html).to_string()
+}
+
+/// Drop images that exist only to track readers, before ammonia sees them
+fn remove_tracking_pixels(html: &str) -> Cow<'_, str> {
+    let mut fragment = Html::parse_fragment(html);
+    let img_selector = Selector::parse("img").unwrap();
+
+    let pixels: Vec<_> = fragment
+        .select(&img_selector)
+        .filter(|img| {
+            let el = img.value();
+
+            let tiny = |attr: &str| {
+                el.attr(attr)
+                    .map(|v| v.trim().trim_end_matches("px"))
+                    .and_then(|v| v.parse::<u32>().ok())
+                    .is_some_and(|v| v <= 1)
+            };
+            let hidden = el
+                .attr("style")
+                .map(|s| s.replace(' ', "").to_lowercase())
+                .is_some_and(|s| s.contains("display:none") || s.contains("visibility:hidden"));
+            let tracker_host = el
+                .attr("src")
+                .and_then(|src| Url::parse(src).ok())
+                .and_then(|u| u.host_str().map(str::to_string))
+                .is_some_and(|host| TRACKER_HOSTS.contains(&host.as_str()));
+
+            tiny("width") || tiny("height") || hidden || tracker_host
+        })
+        .map(|img| img.id())
+        .collect();
+
+    if pixels.is_empty() {
+        return Cow::Borrowed(html);
+    }
+    for id in pixels {
+        if let Some(mut node) = fragment.tree.get_mut(id) {
+            node.detach();
+        }
+    }
+    Cow::Owned(fragment.root_element().inner_html())
+}
+
+/// A `srcset` with each candidate URL resolved against `base`, keeping only
+/// http(s) candidates. `None` if none are left.
+fn srcset(value: &str, base: Option<&Url>) -> Option<String> {
+    let candidates: Vec<String> = value
+        .split(',')
+        .filter_map(|candidate| {
+            let mut parts = candidate.split_whitespace();
+            let url = parts.next()?;
+            let url = match base {
+                Some(base) => base.join(url).ok()?,
+                None => Url::parse(url).ok()?,
+            };
+            if !matches!(url.scheme(), "http" | "https") {
+                return None;
+            }
+            let descriptor = parts.collect::<Vec<_>>().join(" ");
+            Some(if descriptor.is_empty() {
+                url.to_string()
+            } else {
+                format!("{} {}", url, descriptor)
+            })
+        })
+        .collect();
+
+    (!candidates.is_empty()).then(|| candidates.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "https://example.com/news/1";
+
+    #[test]
+    fn drops_tracking_pixels() {
+        let html = r#"<p>Text<img src="https://example.com/p.gif" width="1" height="1"></p>"#;
+        assert_eq!(sanitize_html(html, BASE), "<p>Text</p>");
+        let html = r#"<p>Text<img src="https://hm.baidu.com/hm.gif"></p>"#;
+        assert_eq!(sanitize_html(html, BASE), "<p>Text</p>");
+    }
+
+    #[test]
+    fn keeps_images_without_src() {
+        let html = r#"<p><img srcset="/a.png 1x, /a@2x.png 2x" alt="chart"></p>"#;
+        assert_eq!(
+            sanitize_html(html, BASE),
+            r#"<p><img srcset="https://example.com/a.png 1x, https://example.com/a@2x.png 2x" alt="chart"></p>"#
+        );
+        let html = r#"<p><img alt="lazy" width="600"></p>"#;
+        assert_eq!(sanitize_html(html, BASE), r#"<p><img alt="lazy" width="600"></p>"#);
+    }
+
+    #[test]
+    fn drops_unsafe_srcset_candidates() {
+        let html = r#"<img src="/a.png" srcset="javascript:alert(1) 1x">"#;
+        assert_eq!(sanitize_html(html, BASE), r#"<img src="https://example.com/a.png">"#);
+    }
+}
@@ -3,8 +3,11 @@ use chrono::{DateTime, NaiveDateTime, Utc};
 use scraper::{Html, Selector};
 use sha2::{Digest, Sha256};
 
-use crate::db::models::NewArticle;
+use crate::db::models::{NewArticle, NewTag};
 use crate::scraper::client::ScraperClient;
+use crate::scraper::content::ArticleBody;
+use crate::scraper::{excerpt, fingerprint};
+use crate::text;
 use super::Source;
 
 // CSS selectors for AIBase website
@@ -143,13 +146,10 @@ impl AIBaseScraper {
             .map(|e| e.text().collect::<String>().trim().to_string())
             .unwrap_or_else(|| format!("Article {}", external_id));
 
-        let content = self.extract_content(&document);
+        let body = self.extract_content(&document, url);
+        let content = body.text;
 
-        let excerpt = if content.len() > 200 {
-            Some(format!("{}...", &content[..200].trim()))
-        } else {
-            Some(content.clone())
-        };
+        let excerpt = excerpt::from_document(&document, &title, &content);
 
         let author = self.extract_text(&document, selectors::ARTICLE_AUTHOR);
         let published_at = self.extract_date(&document);
@@ -157,9 +157,10 @@ impl AIBaseScraper {
         let view_count = self.extract_view_count(&document);
         let thumbnail_url = self.extract_thumbnail(&document);
         let content_hash = self.compute_hash(&content);
+        let simhash = fingerprint::simhash(&title, &content);
 
-        let word_count = content.split_whitespace().count();
-        let read_time_minutes = Some((word_count / 200).max(1) as i32);
+        let language = text::language(&format!("{}\n{}", title, content));
+        let read_time_minutes = Some(text::reading_minutes(&content, language));
 
         Ok(NewArticle {
             external_id: external_id.to_string(),
@@ -172,13 +173,19 @@ impl AIBaseScraper {
             published_at,
             view_count,
             read_time_minutes,
+            language: language.to_string(),
             thumbnail_url,
             content_hash,
+            simhash,
+            content_html: body.html,
+            content_markdown: body.markdown,
+            content_format: body.format,
             tags,
+            links: body.links,
         })
     }
 
-    fn extract_content(&self, document: &Html) -> String {
+    fn extract_content(&self, document: &Html, url: &str) -> ArticleBody {
         let content_selector = Selector::parse(selectors::ARTICLE_CONTENT).unwrap();
         let p_selector = Selector::parse(selectors::ARTICLE_PARAGRAPHS).unwrap();
 
@@ -190,21 +197,34 @@ impl AIBaseScraper {
                 .collect();
 
             if !paragraphs.is_empty() {
-                return paragraphs.join("\n\n");
+                // Keep paragraph text as `content`; the container markup feeds HTML/Markdown
+                let mut body = ArticleBody::from_html(&content_el.inner_html(), url);
+                body.text = paragraphs.join("\n\n");
+                return body;
             }
         }
 
-        let paragraphs: Vec<String> = document
+        let paragraphs: Vec<_> = document
             .select(&p_selector)
-            .map(|p| p.text().collect::<String>().trim().to_string())
-            .filter(|s| !s.is_empty() && s.len() > 20)
+            .filter(|p| {
+                let text = p.text().collect::<String>();
+                let text = text.trim();
+                !text.is_empty() && text.len() > 20
+            })
             .collect();
 
         if !paragraphs.is_empty() {
-            return paragraphs.join("\n\n");
+            let html = paragraphs.iter().map(|p| p.html()).collect::<Vec<_>>().join("\n");
+            let mut body = ArticleBody::from_html(&html, url);
+            body.text = paragraphs
+                .iter()
+                .map(|p| p.text().collect::<String>().trim().to_string())
+                .collect::<Vec<_>>()
+                .join("\n\n");
+            return body;
         }
 
-        "Content not available".to_string()
+        ArticleBody::from_text("Content not available".to_string())
     }
 
     fn extract_text(&self, document: &Html, selector_str: &str) -> Option<String> {
@@ -259,7 +279,7 @@ impl AIBaseScraper {
         None
     }
 
-    fn extract_tags(&self, document: &Html) -> Vec<String> {
+    fn extract_tags(&self, document: &Html) -> Vec<NewTag> {
         let selector = match Selector::parse(selectors::ARTICLE_TAGS) {
             Ok(s) => s,
             Err(_) => return Vec::new(),
@@ -270,6 +290,7 @@ impl AIBaseScraper {
             .map(|e| e.text().collect::<String>().trim().to_string())
             .filter(|s| !s.is_empty() && s.len() < 50)
             .take(10)
+            .map(NewTag::new)
             .collect()
     }
 
@@ -3,8 +3,11 @@ use chrono::{DateTime, NaiveDate, Utc};
 use scraper::{Html, Selector};
 use sha2::{Digest, Sha256};
 
-use crate::db::models::NewArticle;
+use crate::db::models::{NewArticle, NewTag, TagCategory};
 use crate::scraper::client::ScraperClient;
+use crate::scraper::content::ArticleBody;
+use crate::scraper::{excerpt, fingerprint};
+use crate::text;
 use super::Source;
 
 // CSS selectors for smol.ai website
@@ -95,14 +98,11 @@ impl SmolAIScraper {
         let title = self.extract_title(&document, external_id);
 
         // Extract content
-        let content = self.extract_content(&document);
+        let body = self.extract_content(&document, url);
+        let content = body.text;
 
-        // Excerpt - first 200 chars of content
-        let excerpt = if content.len() > 200 {
-            Some(format!("{}...", &content[..200].trim()))
-        } else {
-            Some(content.clone())
-        };
+        // Excerpt - meta description, or the start of the content
+        let excerpt = excerpt::from_document(&document, &title, &content);
 
         // Extract date from slug (YY-MM-DD format)
         let published_at = self.parse_date_from_slug(external_id);
@@ -112,10 +112,11 @@ impl SmolAIScraper {
 
         // Compute content hash
         let content_hash = self.compute_hash(&content);
+        let simhash = fingerprint::simhash(&title, &content);
 
-        // Estimate read time (average 200 words per minute)
-        let word_count = content.split_whitespace().count();
-        let read_time_minutes = Some((word_count / 200).max(1) as i32);
+        // Detect the language, and estimate read time at its reading pace
+        let language = text::language(&format!("{}\n{}", title, content));
+        let read_time_minutes = Some(text::reading_minutes(&content, language));
 
         Ok(NewArticle {
             external_id: external_id.to_string(),
@@ -128,9 +129,15 @@ impl SmolAIScraper {
             published_at,
             view_count: None,
             read_time_minutes,
+            language: language.to_string(),
             thumbnail_url: None, // smol.ai doesn't have thumbnails
             content_hash,
+            simhash,
+            content_html: body.html,
+            content_markdown: body.markdown,
+            content_format: body.format,
             tags,
+            links: body.links,
         })
     }
 
@@ -169,7 +176,7 @@ impl SmolAIScraper {
         }
     }
 
-    fn extract_content(&self, document: &Html) -> String {
+    fn extract_content(&self, document: &Html, url: &str) -> ArticleBody {
         let content_selector = Selector::parse(selectors::ARTICLE_CONTENT).unwrap();
 
         // Get the full inner HTML of the content area
@@ -183,7 +190,7 @@ impl SmolAIScraper {
                     .filter(|line| !line.is_empty())
                     .collect::<Vec<_>>()
                     .join("\n");
-                return cleaned;
+                return ArticleBody::from_html(&cleaned, url);
             }
         }
 
@@ -196,10 +203,10 @@ impl SmolAIScraper {
             .collect();
 
         if !paragraphs.is_empty() {
-            return paragraphs.join("\n\n");
+            return ArticleBody::from_text(paragraphs.join("\n\n"));
         }
 
-        "Content not available".to_string()
+        ArticleBody::from_text("Content not available".to_string())
     }
 
     fn parse_date_from_slug(&self, slug: &str) -> Option<DateTime<Utc>> {
@@ -221,7 +228,7 @@ impl SmolAIScraper {
         }
     }
 
-    fn extract_tags(&self, document: &Html) -> Vec<String> {
+    fn extract_tags(&self, document: &Html) -> Vec<NewTag> {
         let selector = match Selector::parse(selectors::ARTICLE_TAGS) {
             Ok(s) => s,
             Err(_) => return Vec::new(),
@@ -229,8 +236,19 @@ impl SmolAIScraper {
 
         document
             .select(&selector)
-            .map(|e| e.text().collect::<String>().trim().to_string())
-            .filter(|s| !s.is_empty() && s.len() < 50)
+            .map(|e| {
+                // Pagefind filters tell companies from topics
+                let category = match e.value().attr("data-pagefind-filter") {
+                    Some("company") => Some(TagCategory::Company),
+                    Some("topic") => Some(TagCategory::Topic),
+                    _ => None,
+                };
+                NewTag {
+                    name: e.text().collect::<String>().trim().to_string(),
+                    category,
+                }
+            })
+            .filter(|tag| !tag.name.is_empty() && tag.name.len() < 50)
             .take(20) // smol.ai has more tags
             .collect()
     }
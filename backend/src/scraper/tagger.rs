@@ -0,0 +1,334 @@
+use anyhow::{bail, Result};
+use regex::{Regex, RegexBuilder};
+use sqlx::PgPool;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+use uuid::Uuid;
+
+use crate::db::models::{NewTagRule, TagRuleKind};
+use crate::db::queries;
+use crate::text::{inside_word, language};
+
+const BATCH_SIZE: i64 = 200;
+/// Compiled size limit per rule, so a huge pattern list can't exhaust memory
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+/// An article as seen by tagging rules
+pub struct TagText {
+    source: String,
+    language: &'static str,
+    text: String,
+}
+
+impl TagText {
+    pub fn new(source: &str, title: &str, content: &str) -> Self {
+        let text = format!("{}\n{}", title, content);
+        Self {
+            source: source.to_string(),
+            language: language(&text),
+            text,
+        }
+    }
+}
+
+/// A rule with all of its patterns compiled into one regex
+pub struct CompiledRule {
+    pub id: i32,
+    pub tag_id: i32,
+    source: Option<String>,
+    language: Option<String>,
+    regex: Regex,
+    /// Keywords and phrases only match whole words
+    whole_words: bool,
+}
+
+impl CompiledRule {
+    pub fn new(id: i32, tag_id: i32, rule: &NewTagRule) -> Result<Self> {
+        let alternatives: Vec<String> = rule
+            .patterns
+            .iter()
+            .map(|pattern| pattern.trim())
+            .filter(|pattern| !pattern.is_empty())
+            .map(|pattern| match rule.kind {
+                TagRuleKind::Keyword => regex::escape(pattern),
+                TagRuleKind::Phrase => {
+                    let words: Vec<String> = pattern
+                        .split(|c: char| c.is_whitespace() || c == '-')
+                        .filter(|word| !word.is_empty())
+                        .map(regex::escape)
+                        .collect();
+                    words.join(r"[\s\-]+")
+                }
+                TagRuleKind::Regex => format!("(?:{})", pattern),
+            })
+            .collect();
+
+        if alternatives.is_empty() {
+            bail!("Rule has no patterns");
+        }
+
+        let regex = RegexBuilder::new(&alternatives.join("|"))
+            .case_insensitive(!rule.case_sensitive)
+            .size_limit(REGEX_SIZE_LIMIT)
+            .build()?;
+
+        Ok(Self {
+            id,
+            tag_id,
+            source: rule.source.clone(),
+            language: rule.language.clone(),
+            regex,
+            whole_words: rule.kind != TagRuleKind::Regex,
+        })
+    }
+
+    /// First text in the article the rule matches, if it applies to the
+    /// article's source and language at all
+    pub fn find<'t>(&self, article: &'t TagText) -> Option<&'t str> {
+        if self.source.as_ref().is_some_and(|s| *s != article.source)
+            || self
+                .language
+                .as_ref()
+                .is_some_and(|l| l != article.language)
+        {
+            return None;
+        }
+        // Word edges are checked here rather than with `\b`, which treats
+        // CJK characters as word characters
+        self.regex
+            .find_iter(&article.text)
+            .find(|m| {
+                !self.whole_words
+                    || !(inside_word(&article.text, m.start())
+                        || inside_word(&article.text, m.end()))
+            })
+            .map(|m| m.as_str())
+    }
+}
+
+/// Enabled tagging rules, applied to articles as they are stored and, on
+/// request, to every stored article
+#[derive(Default)]
+pub struct Tagger {
+    rules: RwLock<Vec<CompiledRule>>,
+    backfilling: AtomicBool,
+}
+
+impl Tagger {
+    /// Recompile the enabled rules. Rules that no longer compile are skipped.
+    pub async fn reload(&self, pool: &PgPool) -> Result<()> {
+        let rules = queries::get_tag_rules(pool).await?;
+        let compiled = rules
+            .iter()
+            .filter(|rule| rule.rule.enabled)
+            .filter_map(
+                |rule| match CompiledRule::new(rule.id, rule.tag_id, &rule.rule) {
+                    Ok(compiled) => Some(compiled),
+                    Err(e) => {
+                        tracing::warn!("Skipping tag rule {}: {}", rule.id, e);
+                        None
+                    }
+                },
+            )
+            .collect();
+
+        *self.rules.write().unwrap() = compiled;
+        Ok(())
+    }
+
+    /// `(rule_id, tag_id)` of the rules matching an article, one per tag
+    pub fn matches(&self, article: &TagText) -> Vec<(i32, i32)> {
+        let mut matches: Vec<(i32, i32)> = Vec::new();
+        for rule in self.rules.read().unwrap().iter() {
+            if !matches.iter().any(|(_, tag_id)| *tag_id == rule.tag_id)
+                && rule.find(article).is_some()
+            {
+                matches.push((rule.id, rule.tag_id));
+            }
+        }
+        matches
+    }
+
+    /// Replace an article's rule tags with those of the current rules
+    pub async fn tag_article(
+        &self,
+        pool: &PgPool,
+        id: Uuid,
+        source: &str,
+        title: &str,
+        content: &str,
+    ) -> Result<()> {
+        let matches = self.matches(&TagText::new(source, title, content));
+        queries::replace_rule_tags(pool, id, &matches).await
+    }
+
+    pub fn is_backfilling(&self) -> bool {
+        self.backfilling.load(Ordering::SeqCst)
+    }
+
+    /// Re-tag every stored article with the current rules. Returns the
+    /// number of articles processed and how many got rule tags, or `None`
+    /// if a backfill is already running.
+    pub async fn backfill(&self, pool: &PgPool) -> Result<Option<(usize, usize)>> {
+        if self.backfilling.swap(true, Ordering::SeqCst) {
+            return Ok(None);
+        }
+        let result = self.backfill_all(pool).await;
+        self.backfilling.store(false, Ordering::SeqCst);
+        result.map(Some)
+    }
+
+    async fn backfill_all(&self, pool: &PgPool) -> Result<(usize, usize)> {
+        let mut after = Uuid::nil();
+        let (mut processed, mut tagged) = (0, 0);
+
+        loop {
+            let rows = queries::get_article_text_after(pool, after, BATCH_SIZE).await?;
+            if rows.is_empty() {
+                break;
+            }
+
+            for (id, source, title, content) in rows {
+                let matches = self.matches(&TagText::new(&source, &title, &content));
+                queries::replace_rule_tags(pool, id, &matches).await?;
+                after = id;
+                processed += 1;
+                if !matches.is_empty() {
+                    tagged += 1;
+                }
+            }
+        }
+
+        Ok((processed, tagged))
+    }
+}
+
+/// Articles a single rule matches, without tagging them: the total number
+/// of matches and the ids and matched text of the first `limit`
+pub async fn preview(
+    pool: &PgPool,
+    rule: &CompiledRule,
+    limit: usize,
+) -> Result<(usize, Vec<(Uuid, String)>)> {
+    let mut after = Uuid::nil();
+    let mut total = 0;
+    let mut sample = Vec::new();
+
+    loop {
+        let rows = queries::get_article_text_after(pool, after, BATCH_SIZE).await?;
+        if rows.is_empty() {
+            break;
+        }
+
+        for (id, source, title, content) in rows {
+            let article = TagText::new(&source, &title, &content);
+            if let Some(matched) = rule.find(&article) {
+                total += 1;
+                if sample.len() < limit {
+                    sample.push((id, matched.to_string()));
+                }
+            }
+            after = id;
+        }
+    }
+
+    Ok((total, sample))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(kind: TagRuleKind, patterns: &[&str]) -> NewTagRule {
+        NewTagRule {
+            tag: "test".to_string(),
+            kind,
+            patterns: patterns.iter().map(|p| p.to_string()).collect(),
+            source: None,
+            language: None,
+            case_sensitive: false,
+            enabled: true,
+        }
+    }
+
+    fn find<'t>(rule: &NewTagRule, article: &'t TagText) -> Option<&'t str> {
+        CompiledRule::new(1, 1, rule).unwrap().find(article)
+    }
+
+    fn article(title: &str) -> TagText {
+        TagText::new("AIBase", title, "")
+    }
+
+    #[test]
+    fn keywords_match_whole_words_only() {
+        let keyword = rule(TagRuleKind::Keyword, &["GPT-4"]);
+        assert_eq!(find(&keyword, &article("New gpt-4 results")), Some("gpt-4"));
+        assert_eq!(find(&keyword, &article("GPT-4o is out")), None);
+        assert_eq!(find(&keyword, &article("MyGPT-4 clone")), None);
+        // A later whole-word match still counts
+        assert_eq!(find(&keyword, &article("GPT-4o beats GPT-4")), Some("GPT-4"));
+    }
+
+    #[test]
+    fn keywords_are_literal() {
+        let keyword = rule(TagRuleKind::Keyword, &["C++"]);
+        assert_eq!(find(&keyword, &article("Rewritten in C++ today")), Some("C++"));
+        assert_eq!(find(&keyword, &article("Written in C")), None);
+    }
+
+    #[test]
+    fn cjk_keywords_match_inside_cjk_text() {
+        let keyword = rule(TagRuleKind::Keyword, &["大模型"]);
+        assert_eq!(find(&keyword, &article("阿里发布大模型Qwen3")), Some("大模型"));
+    }
+
+    #[test]
+    fn phrases_allow_any_spacing_and_hyphens() {
+        let phrase = rule(TagRuleKind::Phrase, &["open source"]);
+        assert_eq!(find(&phrase, &article("An open-source model")), Some("open-source"));
+        assert_eq!(find(&phrase, &article("Open \n source weights")), Some("Open \n source"));
+        assert_eq!(find(&phrase, &article("reopen sourcecode")), None);
+    }
+
+    #[test]
+    fn regexes_match_anywhere() {
+        let regex = rule(TagRuleKind::Regex, &[r"llama\s?\d"]);
+        assert_eq!(find(&regex, &article("Metallama3 release")), Some("llama3"));
+    }
+
+    #[test]
+    fn case_sensitivity_is_optional() {
+        let mut keyword = rule(TagRuleKind::Keyword, &["AI"]);
+        assert_eq!(find(&keyword, &article("ai news")), Some("ai"));
+        keyword.case_sensitive = true;
+        assert_eq!(find(&keyword, &article("ai news")), None);
+        assert_eq!(find(&keyword, &article("AI news")), Some("AI"));
+    }
+
+    #[test]
+    fn rules_are_limited_to_source_and_language() {
+        let mut keyword = rule(TagRuleKind::Keyword, &["Qwen"]);
+        keyword.source = Some("smol.ai".to_string());
+        assert_eq!(find(&keyword, &article("Qwen release")), None);
+        assert!(find(&keyword, &TagText::new("smol.ai", "Qwen release", "")).is_some());
+
+        keyword.source = None;
+        keyword.language = Some("zh".to_string());
+        assert_eq!(find(&keyword, &article("Qwen release")), None);
+        assert_eq!(find(&keyword, &article("阿里发布Qwen模型")), Some("Qwen"));
+    }
+
+    #[test]
+    fn rejects_empty_and_invalid_rules() {
+        assert!(CompiledRule::new(1, 1, &rule(TagRuleKind::Keyword, &[" ", ""])).is_err());
+        assert!(CompiledRule::new(1, 1, &rule(TagRuleKind::Regex, &["(unclosed"])).is_err());
+    }
+
+    #[test]
+    fn regex_alternatives_stay_grouped() {
+        // Without grouping, `a|b` from one pattern would bleed into the next
+        let regex = rule(TagRuleKind::Regex, &["^x|y$", "z"]);
+        let compiled = CompiledRule::new(1, 1, &regex).unwrap();
+        assert_eq!(compiled.regex.as_str(), "(?:^x|y$)|(?:z)");
+    }
+}
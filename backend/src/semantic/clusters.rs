@@ -0,0 +1,113 @@
+use anyhow::Result;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use super::{related, SemanticIndex};
+use crate::db::queries;
+use crate::scraper::fingerprint::SIMHASH_MAX_DISTANCE;
+
+const BATCH_SIZE: i64 = 100;
+/// Reposts can trail the original by a while
+const DUPLICATE_WINDOW_DAYS: i32 = 30;
+/// Related-article score above which two reports count as the same story.
+/// A multilingual embedding model also matches reports in different
+/// languages, whose SimHashes share nothing.
+const SAME_STORY_SCORE: f32 = 0.35;
+/// The local embedder shares no features between Chinese and English text,
+/// so across languages only shared tags and publication proximity count,
+/// and at most an identical tag set on the same day reaches this. Within a
+/// language, shared bigrams alone score higher than with a real model.
+/// Reports in different languages (AIBase and smol.ai) therefore only
+/// cluster reliably with a multilingual model behind `EMBEDDING_URL`.
+const LOCAL_SAME_STORY_SCORE: f32 = 0.4;
+const SAME_STORY_CANDIDATES: usize = 10;
+/// Scores kept for checking an article against existing cluster members
+const CLUSTER_CANDIDATES: usize = 50;
+
+/// Assign unchecked articles to story clusters: near-duplicates by SimHash,
+/// and reports of the same story by related-article score. Runs after the
+/// embedding sync so new articles already have embeddings.
+pub async fn cluster_pending(pool: &PgPool, index: &SemanticIndex) -> Result<usize> {
+    let threshold = if index.is_local() {
+        LOCAL_SAME_STORY_SCORE
+    } else {
+        SAME_STORY_SCORE
+    };
+    let mut checked = 0;
+
+    loop {
+        let ids = queries::get_articles_pending_cluster(pool, BATCH_SIZE).await?;
+        if ids.is_empty() {
+            break;
+        }
+
+        for id in ids {
+            let duplicates =
+                queries::get_near_duplicates(pool, id, SIMHASH_MAX_DISTANCE, DUPLICATE_WINDOW_DAYS)
+                    .await?;
+            let matches = story_matches(pool, index, id, threshold, duplicates).await?;
+
+            queries::merge_story_cluster(pool, id, &matches).await?;
+            checked += 1;
+        }
+    }
+
+    Ok(checked)
+}
+
+/// `duplicates` of `id` and the articles scoring at least `threshold`
+/// against it. An article already in a cluster only counts if `id` also
+/// matches (either way) the cluster's earliest report or most of its
+/// members, so one borderline pair can't chain unrelated stories together.
+async fn story_matches(
+    pool: &PgPool,
+    index: &SemanticIndex,
+    id: Uuid,
+    threshold: f32,
+    duplicates: Vec<Uuid>,
+) -> Result<Vec<Uuid>> {
+    let scores: HashMap<Uuid, f32> = related::related_articles(pool, index, id, CLUSTER_CANDIDATES)
+        .await?
+        .into_iter()
+        .collect();
+    let matches_id = |other: &Uuid| {
+        duplicates.contains(other) || scores.get(other).is_some_and(|score| *score >= threshold)
+    };
+
+    let mut same_story: Vec<(Uuid, f32)> = scores
+        .iter()
+        .filter(|(other, score)| **score >= threshold && !duplicates.contains(other))
+        .map(|(other, score)| (*other, *score))
+        .collect();
+    same_story.sort_by(|a, b| b.1.total_cmp(&a.1));
+    same_story.truncate(SAME_STORY_CANDIDATES);
+
+    let mut candidates = duplicates.clone();
+    candidates.extend(same_story.into_iter().map(|(other, _)| other));
+    if candidates.is_empty() {
+        return Ok(candidates);
+    }
+
+    // Members of each cluster, earliest report first
+    let mut clusters: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+    for (cluster_id, member) in queries::get_clusters_containing(pool, &candidates).await? {
+        if member != id {
+            clusters.entry(cluster_id).or_default().push(member);
+        }
+    }
+    let accepts = |members: &Vec<Uuid>| {
+        let matching = members.iter().filter(|member| matches_id(member)).count();
+        members.first().is_some_and(matches_id) || matching * 2 > members.len()
+    };
+
+    Ok(candidates
+        .into_iter()
+        .filter(|other| {
+            match clusters.values().find(|members| members.contains(other)) {
+                Some(members) => accepts(members),
+                None => true,
+            }
+        })
+        .collect())
+}
@@ -0,0 +1,151 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::text::{fnv1a, is_cjk, terms};
+
+/// Dimensions of the local embedding
+const LOCAL_DIMS: usize = 384;
+const LOCAL_MODEL: &str = "local-hash-384-v1";
+
+/// Turns text into L2-normalized vectors, so cosine similarity is a dot product
+pub enum Embedder {
+    /// Lexical fallback used when no embedding service is configured:
+    /// feature-hashed words, CJK bigrams and character trigrams. Runs on the
+    /// CPU with no model files and matches shared or similar wording
+    /// ("open-source" and "open weights"), but knows nothing of meaning, so
+    /// synonyms and text in other languages don't match.
+    Local,
+    /// OpenAI-compatible `/embeddings` endpoint, e.g. a local Ollama,
+    /// llama.cpp or text-embeddings-inference server
+    Http {
+        client: reqwest::Client,
+        url: String,
+        model: String,
+        api_key: Option<String>,
+    },
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+impl Embedder {
+    pub fn new(config: &Config) -> Result<Self> {
+        let Some(url) = config.embedding_url.clone() else {
+            return Ok(Self::Local);
+        };
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(60))
+            .build()
+            .context("Failed to create HTTP client")?;
+
+        Ok(Self::Http {
+            client,
+            url,
+            model: config.embedding_model.clone(),
+            api_key: config.embedding_api_key.clone(),
+        })
+    }
+
+    /// Stored with each embedding; vectors from different models don't compare
+    pub fn model(&self) -> &str {
+        match self {
+            Self::Local => LOCAL_MODEL,
+            Self::Http { model, .. } => model,
+        }
+    }
+
+    pub async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        match self {
+            Self::Local => Ok(texts.iter().map(|text| hash_embedding(text)).collect()),
+            Self::Http {
+                client,
+                url,
+                model,
+                api_key,
+            } => {
+                let mut request = client.post(url).json(&serde_json::json!({
+                    "model": model,
+                    "input": texts,
+                }));
+                if let Some(key) = api_key {
+                    request = request.bearer_auth(key);
+                }
+
+                let response: EmbeddingResponse = request
+                    .send()
+                    .await
+                    .context("Embedding request failed")?
+                    .error_for_status()
+                    .context("Embedding request failed")?
+                    .json()
+                    .await
+                    .context("Invalid embedding response")?;
+
+                if response.data.len() != texts.len() {
+                    bail!(
+                        "Embedding provider returned {} vectors for {} inputs",
+                        response.data.len(),
+                        texts.len()
+                    );
+                }
+
+                let mut data = response.data;
+                data.sort_by_key(|d| d.index);
+                Ok(data
+                    .into_iter()
+                    .map(|d| {
+                        let mut embedding = d.embedding;
+                        normalize(&mut embedding);
+                        embedding
+                    })
+                    .collect())
+            }
+        }
+    }
+}
+
+fn hash_embedding(text: &str) -> Vec<f32> {
+    let mut vector = vec![0.0f32; LOCAL_DIMS];
+    let mut add = |feature: &str, weight: f32| {
+        let hash = fnv1a(feature.as_bytes());
+        let index = (hash % LOCAL_DIMS as u64) as usize;
+        // The sign bit spreads collisions so they cancel out on average
+        let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+        vector[index] += sign * weight;
+    };
+
+    for term in terms(text) {
+        if term.chars().next().is_some_and(is_cjk) {
+            add(&format!("c:{}", term), 1.0);
+            continue;
+        }
+
+        add(&format!("w:{}", term), 1.0);
+        // Trigrams of "#word#" relate inflections and compounds
+        let padded: Vec<char> = format!("#{}#", term).chars().collect();
+        for gram in padded.windows(3) {
+            add(&format!("g:{}", gram.iter().collect::<String>()), 0.3);
+        }
+    }
+
+    normalize(&mut vector);
+    vector
+}
+
+fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+}
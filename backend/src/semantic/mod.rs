@@ -0,0 +1,164 @@
+pub mod clusters;
+pub mod embedder;
+pub mod related;
+
+use anyhow::Result;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::db::queries;
+pub use embedder::Embedder;
+
+const BATCH_SIZE: i64 = 32;
+const SYNC_INTERVAL: Duration = Duration::from_secs(60);
+/// Characters of content embedded after the title
+const CONTENT_CHARS: usize = 2000;
+/// Matches below this cosine similarity are noise
+pub const MIN_SIMILARITY: f32 = 0.1;
+
+/// Article embeddings held in memory and searched by brute force, which is
+/// fast enough for tens of thousands of articles
+pub struct SemanticIndex {
+    embedder: Embedder,
+    vectors: RwLock<HashMap<Uuid, Vec<f32>>>,
+}
+
+impl SemanticIndex {
+    pub fn new(embedder: Embedder) -> Self {
+        Self {
+            embedder,
+            vectors: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Whether vectors come from the built-in feature-hashing embedder
+    pub fn is_local(&self) -> bool {
+        matches!(self.embedder, Embedder::Local)
+    }
+
+    /// Most similar articles to `query`, best first
+    pub async fn search(&self, query: &str, limit: usize) -> Result<Vec<(Uuid, f32)>> {
+        let query = self.embedder.embed(&[query.to_string()]).await?;
+        Ok(match query.first() {
+            Some(query) => self.nearest(query, None, limit),
+            None => Vec::new(),
+        })
+    }
+
+    /// Most similar articles to an already embedded article, which is not
+    /// itself included. Empty if the article has no embedding yet.
+    pub fn similar(&self, id: Uuid, limit: usize) -> Vec<(Uuid, f32)> {
+        let vector = self.vectors.read().unwrap().get(&id).cloned();
+        match vector {
+            Some(vector) => self.nearest(&vector, Some(id), limit),
+            None => Vec::new(),
+        }
+    }
+
+    fn nearest(&self, query: &[f32], exclude: Option<Uuid>, limit: usize) -> Vec<(Uuid, f32)> {
+        let vectors = self.vectors.read().unwrap();
+        let mut scores: Vec<(Uuid, f32)> = vectors
+            .iter()
+            .filter(|(id, vector)| Some(**id) != exclude && vector.len() == query.len())
+            .map(|(id, vector)| (*id, dot(query, vector)))
+            .filter(|(_, score)| *score >= MIN_SIMILARITY)
+            .collect();
+
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scores.truncate(limit);
+        scores
+    }
+
+    /// Forget articles that are no longer listed
+    pub fn remove(&self, ids: &[Uuid]) {
+        let mut vectors = self.vectors.write().unwrap();
+        for id in ids {
+            vectors.remove(id);
+        }
+    }
+
+    /// Put back the stored embedding of an article restored from the trash
+    pub async fn restore(&self, pool: &PgPool, id: Uuid) -> Result<()> {
+        if let Some(vector) = queries::get_article_embedding(pool, id, self.embedder.model()).await? {
+            self.vectors.write().unwrap().insert(id, vector);
+        }
+        Ok(())
+    }
+
+    async fn load(&self, pool: &PgPool) -> Result<()> {
+        let rows = queries::get_article_embeddings(pool, self.embedder.model()).await?;
+        let mut vectors = self.vectors.write().unwrap();
+        vectors.clear();
+        vectors.extend(rows);
+        Ok(())
+    }
+
+    /// Embed articles that are new, changed or embedded with another model
+    async fn sync(&self, pool: &PgPool) -> Result<usize> {
+        let model = self.embedder.model();
+        let mut embedded = 0;
+
+        loop {
+            let rows = queries::get_articles_missing_embedding(pool, model, BATCH_SIZE).await?;
+            if rows.is_empty() {
+                break;
+            }
+
+            let texts: Vec<String> = rows
+                .iter()
+                .map(|(_, title, content, _)| embedding_text(title, content))
+                .collect();
+            let embeddings = self.embedder.embed(&texts).await?;
+
+            for ((id, _, _, content_hash), embedding) in rows.into_iter().zip(embeddings) {
+                queries::upsert_article_embedding(
+                    pool,
+                    id,
+                    model,
+                    content_hash.as_deref(),
+                    &embedding,
+                )
+                .await?;
+                self.vectors.write().unwrap().insert(id, embedding);
+                embedded += 1;
+            }
+        }
+
+        Ok(embedded)
+    }
+}
+
+/// Load stored embeddings, then keep them and story clusters up to date as
+/// articles arrive
+pub async fn run(pool: PgPool, index: Arc<SemanticIndex>) {
+    if let Err(e) = index.load(&pool).await {
+        tracing::error!("Failed to load article embeddings: {}", e);
+    }
+
+    loop {
+        match index.sync(&pool).await {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("Embedded {} articles", count),
+            Err(e) => tracing::error!("Embedding sync failed: {}", e),
+        }
+        match clusters::cluster_pending(&pool, &index).await {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("Checked {} articles for story clusters", count),
+            Err(e) => tracing::error!("Story clustering failed: {}", e),
+        }
+        tokio::time::sleep(SYNC_INTERVAL).await;
+    }
+}
+
+/// The title counts twice so it outweighs incidental content
+fn embedding_text(title: &str, content: &str) -> String {
+    let content: String = content.chars().take(CONTENT_CHARS).collect();
+    format!("{}\n{}\n{}", title, title, content)
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
@@ -0,0 +1,50 @@
+use anyhow::Result;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::SemanticIndex;
+use crate::db::queries;
+
+/// Embedding neighbours considered besides the articles sharing a tag
+const TEXT_CANDIDATES: usize = 50;
+/// Days over which the publication-proximity score halves
+const PROXIMITY_HALF_LIFE_DAYS: f64 = 3.0;
+const MIN_SCORE: f32 = 0.15;
+
+/// Articles most likely to cover the same story as `id`, across all sources,
+/// scored from text similarity, shared tags and publication proximity
+pub async fn related_articles(
+    pool: &PgPool,
+    index: &SemanticIndex,
+    id: Uuid,
+    limit: usize,
+) -> Result<Vec<(Uuid, f32)>> {
+    let text_scores = index.similar(id, TEXT_CANDIDATES);
+    let text_ids: Vec<Uuid> = text_scores.iter().map(|(id, _)| *id).collect();
+    let candidates = queries::get_related_candidates(pool, id, &text_ids).await?;
+
+    let mut scored: Vec<(Uuid, f32)> = candidates
+        .into_iter()
+        .map(|candidate| {
+            let text = text_scores
+                .iter()
+                .find(|(id, _)| *id == candidate.id)
+                .map_or(0.0, |(_, score)| *score);
+            let tags = if candidate.tag_union > 0 {
+                candidate.shared_tags as f32 / candidate.tag_union as f32
+            } else {
+                0.0
+            };
+            let proximity = candidate.days_apart.map_or(0.0, |days| {
+                0.5f64.powf(days / PROXIMITY_HALF_LIFE_DAYS) as f32
+            });
+
+            (candidate.id, 0.6 * text + 0.25 * tags + 0.15 * proximity)
+        })
+        .filter(|(_, score)| *score >= MIN_SCORE)
+        .collect();
+
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored.truncate(limit);
+    Ok(scored)
+}
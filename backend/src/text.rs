@@ -0,0 +1,225 @@
+//! Language-agnostic tokenization shared by embeddings, fingerprints and
+//! tagging rules
+
+/// Common English words that carry little meaning on their own
+pub const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "has", "have", "in", "is",
+    "it", "its", "of", "on", "or", "that", "the", "this", "to", "was", "were", "will", "with",
+];
+
+pub enum Token<'a> {
+    Word(&'a str),
+    /// A run of CJK characters, which aren't separated by spaces
+    Cjk(&'a str),
+}
+
+/// Split into alphanumeric words and runs of CJK characters
+pub fn tokens(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut start: Option<(usize, bool)> = None;
+
+    for (i, c) in text
+        .char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+    {
+        let kind = if is_cjk(c) {
+            Some(true)
+        } else if c.is_alphanumeric() {
+            Some(false)
+        } else {
+            None
+        };
+
+        if let Some((begin, cjk)) = start {
+            if kind != Some(cjk) {
+                let run = &text[begin..i];
+                tokens.push(if cjk {
+                    Token::Cjk(run)
+                } else {
+                    Token::Word(run)
+                });
+                start = None;
+            }
+        }
+        if start.is_none() {
+            start = kind.map(|cjk| (i, cjk));
+        }
+    }
+
+    tokens
+}
+
+/// Lowercased words without stop words, and CJK runs as character bigrams
+/// (single characters stay as they are)
+pub fn terms(text: &str) -> Vec<String> {
+    let text = text.to_lowercase();
+    let mut terms = Vec::new();
+
+    for token in tokens(&text) {
+        match token {
+            Token::Word(word) if STOP_WORDS.contains(&word) => {}
+            Token::Word(word) => terms.push(word.to_string()),
+            Token::Cjk(run) => {
+                let chars: Vec<char> = run.chars().collect();
+                if chars.len() == 1 {
+                    terms.push(run.to_string());
+                }
+                terms.extend(chars.windows(2).map(|pair| pair.iter().collect()));
+            }
+        }
+    }
+
+    terms
+}
+
+/// Same ranges as the `cjk_*` SQL functions: Hiragana/Katakana, CJK
+/// Extension A, CJK Unified and Compatibility Ideographs, Hangul Syllables
+pub fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30ff}'
+        | '\u{3400}'..='\u{4dbf}'
+        | '\u{4e00}'..='\u{9fff}'
+        | '\u{ac00}'..='\u{d7af}'
+        | '\u{f900}'..='\u{faff}')
+}
+
+/// Whether `text` at byte offset `at` falls inside a word, between two
+/// letters or digits. CJK characters don't count, since CJK text has no
+/// spaces between words.
+pub fn inside_word(text: &str, at: usize) -> bool {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() && !is_cjk(c));
+    is_word(text[..at].chars().next_back()) && is_word(text[at..].chars().next())
+}
+
+/// Characters sampled by `language`
+const LANGUAGE_SAMPLE: usize = 2000;
+
+/// Best guess at the main language of `text` from its script: `ja` if it
+/// has kana, `ko` for Hangul, `zh` for other CJK text and `en` otherwise.
+/// Text counts as CJK when at least a fifth of its letters are.
+pub fn language(text: &str) -> &'static str {
+    let (mut letters, mut han, mut kana, mut hangul) = (0, 0, 0, 0);
+    for c in text
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .take(LANGUAGE_SAMPLE)
+    {
+        letters += 1;
+        match c {
+            '\u{3040}'..='\u{30ff}' => kana += 1,
+            '\u{ac00}'..='\u{d7af}' => hangul += 1,
+            c if is_cjk(c) => han += 1,
+            _ => {}
+        }
+    }
+
+    if (han + kana + hangul) * 5 < letters || letters == 0 {
+        "en"
+    } else if kana > 0 {
+        "ja"
+    } else if hangul > han {
+        "ko"
+    } else {
+        "zh"
+    }
+}
+
+/// Space-separated words read per minute
+const WORDS_PER_MINUTE: f64 = 200.0;
+
+/// CJK characters read per minute, by `language` code
+fn cjk_chars_per_minute(language: &str) -> f64 {
+    match language {
+        "ja" => 400.0,
+        "ko" => 500.0,
+        _ => 300.0,
+    }
+}
+
+/// Estimated minutes to read `text`, at least one. Words count at a fixed
+/// pace and CJK characters at the pace of the article's `language`.
+pub fn reading_minutes(text: &str, language: &str) -> i32 {
+    let (mut words, mut chars) = (0, 0);
+    for token in tokens(text) {
+        match token {
+            Token::Word(_) => words += 1,
+            Token::Cjk(run) => chars += run.chars().count(),
+        }
+    }
+
+    let minutes =
+        words as f64 / WORDS_PER_MINUTE + chars as f64 / cjk_chars_per_minute(language);
+    (minutes.round() as i32).max(1)
+}
+
+/// FNV-1a, stable across builds unlike `DefaultHasher`
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inside_word_between_letters_or_digits() {
+        let text = "GPT-4o model";
+        assert!(inside_word(text, 1));
+        assert!(!inside_word(text, 3));
+        assert!(!inside_word(text, 4));
+        assert!(inside_word(text, 5));
+        assert!(!inside_word(text, 6));
+        assert!(!inside_word(text, 0));
+        assert!(!inside_word(text, text.len()));
+    }
+
+    #[test]
+    fn inside_word_ignores_cjk() {
+        let text = "阿里Qwen3发布";
+        assert!(!inside_word(text, "阿".len()));
+        assert!(!inside_word(text, "阿里".len()));
+        assert!(inside_word(text, "阿里Q".len()));
+        assert!(!inside_word(text, "阿里Qwen3".len()));
+    }
+
+    #[test]
+    fn language_by_script() {
+        assert_eq!(language("OpenAI releases GPT-5"), "en");
+        assert_eq!(language("阿里发布通义千问新模型"), "zh");
+        assert_eq!(language("オープンAIが新しいモデルを発表"), "ja");
+        assert_eq!(language("오픈AI가 새 모델을 공개했다"), "ko");
+    }
+
+    #[test]
+    fn language_needs_a_fifth_cjk_letters() {
+        // Kanji-heavy Japanese still counts as Japanese once it has kana
+        assert_eq!(language("大規模言語模型の発表"), "ja");
+        // A CJK name in English text doesn't make it Chinese
+        assert_eq!(language("Alibaba Cloud released Qwen, or 通义, this week"), "en");
+        assert_eq!(language("阿里云发布Qwen3模型"), "zh");
+    }
+
+    #[test]
+    fn language_defaults_to_english() {
+        assert_eq!(language(""), "en");
+        assert_eq!(language("2025-01-01 12:00 !!!"), "en");
+    }
+
+    #[test]
+    fn language_samples_start_of_text() {
+        let text = format!("{}{}", "a".repeat(LANGUAGE_SAMPLE), "中".repeat(LANGUAGE_SAMPLE));
+        assert_eq!(language(&text), "en");
+    }
+
+    #[test]
+    fn reading_minutes_counts_words_and_cjk_characters() {
+        // 200 Latin words (one minute) and 1200 CJK characters
+        let text = "Qwen3大模型新发布 ".repeat(200);
+        assert_eq!(reading_minutes(&text, "zh"), 5);
+        assert_eq!(reading_minutes(&text, "ja"), 4);
+        assert_eq!(reading_minutes(&text, "ko"), 3);
+        assert_eq!(reading_minutes("GPT-5发布", "zh"), 1);
+    }
+}
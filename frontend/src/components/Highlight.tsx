@@ -0,0 +1,23 @@
+interface HighlightProps {
+  text: string
+}
+
+// Renders search snippets marked with the API's default <mark> markers as
+// text, so nothing else in the snippet is interpreted as HTML
+export default function Highlight({ text }: HighlightProps) {
+  const parts = text.split(/<mark>|<\/mark>/)
+
+  return (
+    <>
+      {parts.map((part, i) =>
+        i % 2 === 1 ? (
+          <mark key={i} className="bg-yellow-500/20 text-yellow-300 rounded px-0.5">
+            {part}
+          </mark>
+        ) : (
+          part
+        )
+      )}
+    </>
+  )
+}
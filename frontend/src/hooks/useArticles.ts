@@ -1,5 +1,5 @@
 import { useQuery } from '@tanstack/react-query'
-import { getArticles, getArticle, getSources } from '@/lib/api'
+import { getArticles, getArticle, getRelatedArticles, getSources } from '@/lib/api'
 
 export function useArticles(params: {
   page?: number
@@ -7,6 +7,7 @@ export function useArticles(params: {
   search?: string
   tag?: string
   source?: string
+  facets?: boolean
 }) {
   return useQuery({
     queryKey: ['articles', params],
@@ -22,6 +23,14 @@ export function useArticle(id: string) {
   })
 }
 
+export function useRelatedArticles(id: string) {
+  return useQuery({
+    queryKey: ['article', id, 'related'],
+    queryFn: () => getRelatedArticles(id),
+    enabled: !!id,
+  })
+}
+
 export function useSources() {
   return useQuery({
     queryKey: ['sources'],
@@ -24,8 +24,11 @@ export function useScrapeProgress() {
 
       ws.onmessage = (event) => {
         try {
-          const data = JSON.parse(event.data) as ScrapeProgress
-          setProgress(data)
+          const data = JSON.parse(event.data)
+          // Bulk job updates share the socket; they carry a job_id instead
+          if ('run_id' in data) {
+            setProgress(data as ScrapeProgress)
+          }
         } catch (e) {
           console.error('Failed to parse WebSocket message:', e)
         }
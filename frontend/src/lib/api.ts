@@ -39,6 +39,8 @@ export async function getArticles(params: {
   search?: string
   tag?: string
   source?: string
+  language?: string
+  facets?: boolean
 }): Promise<PaginatedResponse<ArticlePreview>> {
   const searchParams = new URLSearchParams()
   if (params.page) searchParams.set('page', String(params.page))
@@ -46,6 +48,8 @@ export async function getArticles(params: {
   if (params.search) searchParams.set('search', params.search)
   if (params.tag) searchParams.set('tag', params.tag)
   if (params.source) searchParams.set('source', params.source)
+  if (params.language) searchParams.set('language', params.language)
+  if (params.facets) searchParams.set('facets', 'true')
 
   const query = searchParams.toString()
   return fetchApi(`/articles${query ? `?${query}` : ''}`)
@@ -55,6 +59,10 @@ export async function getArticle(id: string): Promise<Article> {
   return fetchApi(`/articles/${id}`)
 }
 
+export async function getRelatedArticles(id: string): Promise<ArticlePreview[]> {
+  return fetchApi(`/articles/${id}/related`)
+}
+
 export async function deleteArticle(id: string): Promise<void> {
   return fetchApi(`/articles/${id}/delete`, { method: 'DELETE' })
 }
@@ -4,16 +4,21 @@ export interface Article {
   url: string
   title: string
   content: string
+  content_html: string | null
+  content_markdown: string | null
+  content_format: 'text' | 'html'
   excerpt: string | null
   author: string | null
   source: string | null
   published_at: string | null
   view_count: number | null
   read_time_minutes: number | null
+  language: string | null
   thumbnail_url: string | null
   tags?: string[]
   scraped_at: string
   updated_at: string
+  cluster_id: string | null
 }
 
 export interface ArticlePreview {
@@ -26,6 +31,23 @@ export interface ArticlePreview {
   view_count: number | null
   tags: string[]
   source: string
+  cluster_id?: string
+  rank?: number
+  snippet?: string
+  title_highlight?: string
+}
+
+export interface FacetCount {
+  value: string
+  count: number
+}
+
+export interface ArticleFacets {
+  sources: FacetCount[]
+  tags: FacetCount[]
+  authors: FacetCount[]
+  months: FacetCount[]
+  languages: FacetCount[]
 }
 
 export interface PaginatedResponse<T> {
@@ -36,6 +58,7 @@ export interface PaginatedResponse<T> {
     total: number
     total_pages: number
   }
+  facets?: ArticleFacets
 }
 
 export interface ScrapeRun {
@@ -2,14 +2,16 @@ import { useParams, Link } from 'react-router-dom'
 import { Card, CardContent } from '@/components/ui/card'
 import { Button } from '@/components/ui/button'
 import { Skeleton } from '@/components/ui/skeleton'
-import { useArticle } from '@/hooks/useArticles'
+import { useArticle, useRelatedArticles } from '@/hooks/useArticles'
 import { formatDateTime } from '@/lib/utils'
-import { ArrowLeft, ExternalLink, Calendar, User, Clock, Eye, Globe } from 'lucide-react'
+import { ArrowLeft, ExternalLink, Calendar, User, Clock, Eye, Globe, Link2 } from 'lucide-react'
 import DOMPurify from 'dompurify'
+import MarkdownRenderer from '@/components/MarkdownRenderer'
 
 export default function ArticleDetail() {
   const { id } = useParams<{ id: string }>()
   const { data: article, isLoading, error } = useArticle(id || '')
+  const { data: related } = useRelatedArticles(id || '')
 
   if (isLoading) {
     return (
@@ -95,7 +97,9 @@ export default function ArticleDetail() {
 
         <Card className="card-glow bg-card/50 backdrop-blur mb-8">
           <CardContent className="py-8 px-8">
-            {isHtmlContent ? (
+            {article.content_markdown ? (
+              <MarkdownRenderer content={article.content_markdown} />
+            ) : isHtmlContent ? (
               <div
                 className="prose dark:prose-invert max-w-none
                   prose-headings:text-foreground prose-headings:font-semibold
@@ -140,6 +144,30 @@ export default function ArticleDetail() {
             {formatDateTime(article.scraped_at)}
           </div>
         </div>
+
+        {related && related.length > 0 && (
+          <section className="mt-8">
+            <h2 className="flex items-center gap-2 text-lg font-semibold mb-3">
+              <Link2 className="h-4 w-4 text-blue-400" />
+              Related coverage
+            </h2>
+            <div className="space-y-2">
+              {related.map((item) => (
+                <Link
+                  key={item.id}
+                  to={`/articles/${item.id}`}
+                  className="flex items-center justify-between gap-4 p-3 rounded-lg bg-secondary/20 hover:bg-secondary/40 transition-colors"
+                >
+                  <span className="font-medium line-clamp-1">{item.title}</span>
+                  <span className="shrink-0 text-xs text-muted-foreground">
+                    {item.source}
+                    {item.published_at && ` · ${formatDateTime(item.published_at)}`}
+                  </span>
+                </Link>
+              ))}
+            </div>
+          </section>
+        )}
       </article>
     </div>
   )
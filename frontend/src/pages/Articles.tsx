@@ -5,15 +5,17 @@ import { Input } from '@/components/ui/input'
 import { Button } from '@/components/ui/button'
 import { Badge } from '@/components/ui/badge'
 import { Skeleton } from '@/components/ui/skeleton'
+import Highlight from '@/components/Highlight'
 import { useArticles, useSources } from '@/hooks/useArticles'
 import { formatDate, truncate } from '@/lib/utils'
-import { Search, ChevronLeft, ChevronRight, ExternalLink, ImageOff, Globe } from 'lucide-react'
+import { Search, ChevronLeft, ChevronRight, ExternalLink, ImageOff, Globe, Tag } from 'lucide-react'
 
 export default function Articles() {
   const [page, setPage] = useState(1)
   const [search, setSearch] = useState('')
   const [searchInput, setSearchInput] = useState('')
   const [sourceFilter, setSourceFilter] = useState<string | undefined>(undefined)
+  const [tagFilter, setTagFilter] = useState<string | undefined>(undefined)
   const perPage = 9
 
   const { data: sources } = useSources()
@@ -21,8 +23,18 @@ export default function Articles() {
     page,
     per_page: perPage,
     search: search || undefined,
+    tag: tagFilter,
     source: sourceFilter,
+    facets: true,
   })
+  const facets = data?.facets
+  // Facets leave out their own selection, so these stay the counts for every
+  // source and tag while one is picked
+  const allSourcesCount = facets?.sources.reduce((sum, f) => sum + f.count, 0)
+  const tagFacets =
+    facets && tagFilter && !facets.tags.some((f) => f.value === tagFilter)
+      ? [{ value: tagFilter, count: data?.pagination.total ?? 0 }, ...facets.tags]
+      : facets?.tags ?? []
 
   const handleSearch = (e: React.FormEvent) => {
     e.preventDefault()
@@ -66,6 +78,7 @@ export default function Articles() {
           className={sourceFilter === undefined ? 'bg-indigo-600 hover:bg-indigo-700' : 'bg-secondary/30'}
         >
           All
+          {facets && <span className="ml-1.5 text-xs opacity-70">{allSourcesCount}</span>}
         </Button>
         {sources?.map((s) => (
           <Button
@@ -76,10 +89,40 @@ export default function Articles() {
             className={sourceFilter === s.id ? 'bg-indigo-600 hover:bg-indigo-700' : 'bg-secondary/30'}
           >
             {s.name}
+            {facets && (
+              <span className="ml-1.5 text-xs opacity-70">
+                {facets.sources.find((f) => f.value === s.name)?.count ?? 0}
+              </span>
+            )}
           </Button>
         ))}
       </div>
 
+      {tagFacets.length > 0 && (
+        <div className="flex flex-wrap items-center gap-2">
+          <Tag className="h-4 w-4 text-muted-foreground" />
+          <span className="text-sm text-muted-foreground mr-2">Tags:</span>
+          {tagFacets.map((f) => (
+            <Badge
+              key={f.value}
+              variant="secondary"
+              onClick={() => {
+                setTagFilter(tagFilter === f.value ? undefined : f.value)
+                setPage(1)
+              }}
+              className={`cursor-pointer text-xs ${
+                tagFilter === f.value
+                  ? 'bg-indigo-600 text-white hover:bg-indigo-700'
+                  : 'bg-blue-500/10 text-blue-400 border-blue-500/20 hover:bg-blue-500/20'
+              }`}
+            >
+              {f.value}
+              <span className="ml-1.5 opacity-70">{f.count}</span>
+            </Badge>
+          ))}
+        </div>
+      )}
+
       {search && (
         <div className="flex items-center gap-2 p-3 rounded-lg bg-blue-500/10 border border-blue-500/20">
           <span className="text-sm">
@@ -161,12 +204,22 @@ export default function Articles() {
                     )}
                     <div className="p-4">
                       <h3 className="font-semibold line-clamp-2 group-hover:text-blue-400 transition-colors">
-                        {article.title}
+                        {article.title_highlight ? (
+                          <Highlight text={article.title_highlight} />
+                        ) : (
+                          article.title
+                        )}
                       </h3>
-                      {article.excerpt && (
-                        <p className="text-sm text-muted-foreground mt-2 line-clamp-2">
-                          {truncate(article.excerpt, 100)}
+                      {article.snippet ? (
+                        <p className="text-sm text-muted-foreground mt-2 line-clamp-3">
+                          <Highlight text={article.snippet} />
                         </p>
+                      ) : (
+                        article.excerpt && (
+                          <p className="text-sm text-muted-foreground mt-2 line-clamp-2">
+                            {truncate(article.excerpt, 100)}
+                          </p>
+                        )
                       )}
                       <div className="flex items-center justify-between mt-4 pt-3 border-t border-border">
                         <div className="flex items-center gap-2">